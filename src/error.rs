@@ -0,0 +1,192 @@
+use async_graphql::ErrorExtensions;
+use thiserror::Error;
+
+use crate::graphql::schemas::money_schema::Money;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Every failure the service can report. Each variant maps to a stable
+/// `extensions.code` value when surfaced through GraphQL.
+#[derive(Debug, Clone, Error)]
+pub enum AppError {
+    #[error("{entity} {id} was not found")]
+    NotFound { entity: &'static str, id: String },
+
+    #[error("{0}")]
+    Validation(String),
+
+    #[error("item {item_id} has {available} available but {requested} were requested")]
+    InsufficientQuantity {
+        item_id: String,
+        available: u32,
+        requested: u32,
+    },
+
+    #[error("inventory {inventory_id} would carry {bulk} bulk, over its capacity of {capacity}")]
+    CapacityExceeded {
+        inventory_id: String,
+        capacity: u32,
+        bulk: u32,
+    },
+
+    #[error("container {container_id} would hold {bulk} bulk, over its capacity of {capacity}")]
+    ContainerCapacityExceeded {
+        container_id: String,
+        capacity: u32,
+        bulk: u32,
+    },
+
+    #[error(
+        "inventory {inventory_id} would need {hands} hands to hold its items, but has only {limit}"
+    )]
+    HandsExceeded {
+        inventory_id: String,
+        hands: u32,
+        limit: u32,
+    },
+
+    #[error(
+        "inventory {inventory_id} would have {invested} invested items, over the limit of {limit}"
+    )]
+    InvestmentLimitExceeded {
+        inventory_id: String,
+        invested: u32,
+        limit: u32,
+    },
+
+    #[error("item {item_id} has no uses remaining")]
+    NoUsesRemaining { item_id: String },
+
+    #[error("unknown traits: {}", .names.join(", "))]
+    UnknownTraits { names: Vec<String> },
+
+    #[error("{required} is required but only {available} is available")]
+    InsufficientFunds { required: Money, available: Money },
+
+    #[error("storage failure: {0}")]
+    Storage(String),
+
+    #[error("{0}")]
+    Conflict(String),
+}
+
+impl AppError {
+    pub fn not_found(entity: &'static str, id: impl Into<String>) -> Self {
+        Self::NotFound {
+            entity,
+            id: id.into(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound { .. } => "NOT_FOUND",
+            AppError::Validation(_) => "VALIDATION_ERROR",
+            AppError::InsufficientQuantity { .. } => "INSUFFICIENT_QUANTITY",
+            AppError::CapacityExceeded { .. } => "CAPACITY_EXCEEDED",
+            AppError::ContainerCapacityExceeded { .. } => "CONTAINER_CAPACITY_EXCEEDED",
+            AppError::HandsExceeded { .. } => "HANDS_EXCEEDED",
+            AppError::InvestmentLimitExceeded { .. } => "INVESTMENT_LIMIT_EXCEEDED",
+            AppError::NoUsesRemaining { .. } => "NO_USES_REMAINING",
+            AppError::UnknownTraits { .. } => "UNKNOWN_TRAITS",
+            AppError::InsufficientFunds { .. } => "INSUFFICIENT_FUNDS",
+            AppError::Storage(_) => "STORAGE_FAILURE",
+            AppError::Conflict(_) => "CONFLICT",
+        }
+    }
+}
+
+impl ErrorExtensions for AppError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, e| {
+            e.set("code", self.code());
+            match self {
+                AppError::NotFound { entity, id } => {
+                    e.set("entity", *entity);
+                    e.set("id", id.as_str());
+                }
+                AppError::InsufficientQuantity {
+                    item_id,
+                    available,
+                    requested,
+                } => {
+                    e.set("itemId", item_id.as_str());
+                    e.set("available", *available);
+                    e.set("requested", *requested);
+                }
+                AppError::CapacityExceeded {
+                    inventory_id,
+                    capacity,
+                    bulk,
+                } => {
+                    e.set("inventoryId", inventory_id.as_str());
+                    e.set("capacity", *capacity);
+                    e.set("bulk", *bulk);
+                }
+                AppError::ContainerCapacityExceeded {
+                    container_id,
+                    capacity,
+                    bulk,
+                } => {
+                    e.set("containerId", container_id.as_str());
+                    e.set("capacity", *capacity);
+                    e.set("bulk", *bulk);
+                }
+                AppError::HandsExceeded {
+                    inventory_id,
+                    hands,
+                    limit,
+                } => {
+                    e.set("inventoryId", inventory_id.as_str());
+                    e.set("hands", *hands);
+                    e.set("limit", *limit);
+                }
+                AppError::InvestmentLimitExceeded {
+                    inventory_id,
+                    invested,
+                    limit,
+                } => {
+                    e.set("inventoryId", inventory_id.as_str());
+                    e.set("invested", *invested);
+                    e.set("limit", *limit);
+                }
+                AppError::NoUsesRemaining { item_id } => {
+                    e.set("itemId", item_id.as_str());
+                }
+                AppError::UnknownTraits { names } => {
+                    e.set("traits", names.clone());
+                }
+                AppError::InsufficientFunds {
+                    required,
+                    available,
+                } => {
+                    e.set("required", required.copper());
+                    e.set("available", available.copper());
+                }
+                _ => {}
+            }
+        })
+    }
+}
+
+impl From<neo4rs::Error> for AppError {
+    fn from(err: neo4rs::Error) -> Self {
+        let message = err.to_string();
+        // Deadlocks and other transient failures are safe for the client to retry
+        if message.contains("Neo.TransientError") {
+            tracing::warn!("neo4j transient error: {}", message);
+            return AppError::Conflict(
+                "the request conflicted with a concurrent change, please retry".to_string(),
+            );
+        }
+        tracing::error!("neo4j error: {}", message);
+        AppError::Storage(message)
+    }
+}
+
+impl From<neo4rs::DeError> for AppError {
+    fn from(err: neo4rs::DeError) -> Self {
+        tracing::error!("failed to read neo4j result: {}", err);
+        AppError::Storage(err.to_string())
+    }
+}
@@ -1,2 +1,158 @@
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Schema};
+
+use crate::graphql::resolvers::root_resolver::{MutationRoot, QueryRoot};
+use crate::models::{
+    inventory_item_model::InventoryItemModelManager, inventory_model::InventoryModelManager,
+    inventory_with_items_model::InventoryWithItemsModelManager, item_model::ItemModelManager,
+    owner_model::OwnerModelManager, shop_model::ShopModelManager,
+};
+use crate::storage::Repository;
+
 pub mod resolvers;
 pub mod schemas;
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Wires every model manager to the given storage backend and builds the
+/// schema. `sell_percent` is the campaign-wide share of value paid for sold
+/// items.
+pub fn build_schema(repository: Arc<dyn Repository>, sell_percent: u32) -> AppSchema {
+    let inventory_item_model_manager =
+        || InventoryItemModelManager::new(repository.clone(), sell_percent);
+
+    Schema::build(
+        QueryRoot::new(
+            InventoryModelManager::new(repository.clone()),
+            inventory_item_model_manager(),
+            InventoryWithItemsModelManager::new(
+                inventory_item_model_manager(),
+                InventoryModelManager::new(repository.clone()),
+            ),
+            ItemModelManager::new(repository.clone()),
+            OwnerModelManager::new(repository.clone()),
+            ShopModelManager::new(repository.clone()),
+        ),
+        MutationRoot::new(
+            inventory_item_model_manager(),
+            ItemModelManager::new(repository.clone()),
+            InventoryModelManager::new(repository.clone()),
+            OwnerModelManager::new(repository.clone()),
+            ShopModelManager::new(repository.clone()),
+        ),
+        EmptySubscription,
+    )
+    .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{value, Value};
+
+    use super::*;
+    use crate::storage::InMemoryRepository;
+
+    const INVENTORY: &str = "00000000-0000-4000-8000-000000000006";
+    const TORCH: &str = "00000000-0000-4000-8000-000000000002";
+
+    async fn execute(query: &str) -> Value {
+        let schema = build_schema(Arc::new(InMemoryRepository::with_demo_data()), 50);
+        let response = schema.execute(query).await;
+        assert_eq!(response.errors, [], "{query}");
+        response.data
+    }
+
+    #[tokio::test]
+    async fn pages_through_the_catalog() {
+        let data = execute(
+            r#"{ items { getItems(pageIndex: 0, pageSize: 2, orderBy: "name",
+                orderDirection: "ASC", filter: {}) { entities { name value } totalPages } } }"#,
+        )
+        .await;
+        assert_eq!(
+            data,
+            value!({
+                "items": { "getItems": {
+                    "entities": [
+                        { "name": "Backpack", "value": 10 },
+                        { "name": "Longsword", "value": 100 },
+                    ],
+                    "totalPages": 3,
+                } }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_far_past_the_end_are_empty() {
+        let data = execute(
+            r#"{ items { getItems(pageIndex: 100000, pageSize: 100000, orderBy: "name",
+                orderDirection: "ASC", filter: {}) { entities { name } totalPages } } }"#,
+        )
+        .await;
+        assert_eq!(
+            data,
+            value!({ "items": { "getItems": { "entities": [], "totalPages": 1 } } })
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_an_inventory_and_its_items() {
+        let data = execute(&format!(
+            r#"{{
+                inventory {{ getInventory(id: "{INVENTORY}") {{ name gp sp cp }} }}
+                inventoryItems {{ getInventoryItems(inventoryId: "{INVENTORY}", pageIndex: 0,
+                    pageSize: 10, orderBy: "name", orderDirection: "ASC", filter: {{}}) {{
+                    entities {{ name quantity }}
+                }} }}
+            }}"#
+        ))
+        .await;
+        assert_eq!(
+            data,
+            value!({
+                "inventory": { "getInventory": {
+                    "name": "Valeros's Pack", "gp": 15, "sp": 4, "cp": 7,
+                } },
+                "inventoryItems": { "getInventoryItems": { "entities": [
+                    { "name": "Backpack", "quantity": 1 },
+                    { "name": "Longsword", "quantity": 1 },
+                    { "name": "Minor Healing Potion", "quantity": 2 },
+                    { "name": "Rope", "quantity": 1 },
+                    { "name": "Torch", "quantity": 5 },
+                ] } },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn adds_items_to_an_inventory() {
+        let data = execute(&format!(
+            r#"mutation {{ inventoryItems {{ addOrRemoveItemsFromInventory(
+                inventoryId: "{INVENTORY}", items: [{{ itemId: "{TORCH}", quantityChange: 3 }}]
+            ) {{ inventoryItems {{ name quantity containerId }} userErrors {{ code }} }} }} }}"#
+        ))
+        .await;
+        assert_eq!(
+            data,
+            value!({
+                "inventoryItems": { "addOrRemoveItemsFromInventory": {
+                    "inventoryItems": [{ "name": "Torch", "quantity": 3, "containerId": null }],
+                    "userErrors": [],
+                } }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_missing_inventories_with_an_error_code() {
+        let schema = build_schema(Arc::new(InMemoryRepository::with_demo_data()), 50);
+        let response = schema
+            .execute(r#"{ inventory { getInventory(id: "missing") { name } } }"#)
+            .await;
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("code"), Some(&value!("NOT_FOUND")));
+        assert_eq!(response.data, Value::Null);
+    }
+}
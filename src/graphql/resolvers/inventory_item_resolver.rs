@@ -1,12 +1,19 @@
 use crate::{
     graphql::schemas::{
-        inventory_item_schema::{InventoryItem, InventoryItemQuantityAdjustmentParams},
+        inventory_item_schema::{
+            BuyItemParams, BuyItemsPayload, CarryStateParams, DailyPreparationsPayload,
+            InventoryItem, InventoryItemQuantityAdjustmentParams, InventoryItemsPayload,
+            ItemLocationFilter, SaleQuote, SellItemParams, SellItemsPayload, TransferItemParams,
+            TransferItemsPayload, UseItemParams,
+        },
+        item_instance_schema::ItemInstanceInput,
         item_schema::ItemQueryFilter,
         paginated_response_schema::PaginatedResponse,
+        shop_schema::ShopPurchaseParams,
     },
     models::inventory_item_model::InventoryItemModelManager,
 };
-use async_graphql::Object;
+use async_graphql::{Object, Result, ResultExt};
 
 pub struct InventoryItemQuery {
     inventory_item_model_manager: InventoryItemModelManager,
@@ -19,8 +26,12 @@ impl InventoryItemQuery {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[Object]
 impl InventoryItemQuery {
+    /// Every stack and item instance in the inventory, each with the
+    /// container it is kept in, or only those kept directly in one place when
+    /// `location` is given. `equippedOnly` keeps just the worn and held rows.
     pub async fn get_inventory_items(
         &self,
         inventory_id: String,
@@ -29,7 +40,9 @@ impl InventoryItemQuery {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<PaginatedResponse<InventoryItem>> {
+        location: Option<ItemLocationFilter>,
+        #[graphql(default)] equipped_only: bool,
+    ) -> Result<PaginatedResponse<InventoryItem>> {
         self.inventory_item_model_manager
             .get_inventory_items(
                 inventory_id,
@@ -38,8 +51,25 @@ impl InventoryItemQuery {
                 order_by,
                 order_direction,
                 filter,
+                location,
+                equipped_only,
             )
             .await
+            .extend()
+    }
+
+    /// Previews what `sellItems` would pay for the same lines, without
+    /// selling anything.
+    pub async fn quote_sale(
+        &self,
+        inventory_id: String,
+        items: Vec<SellItemParams>,
+        merchant_id: Option<String>,
+    ) -> Result<SaleQuote> {
+        self.inventory_item_model_manager
+            .quote_sale(inventory_id, items, merchant_id)
+            .await
+            .extend()
     }
 }
 
@@ -56,27 +86,186 @@ impl InventoryItemMutation {
 
 #[Object]
 impl InventoryItemMutation {
+    /// `overrideCapacity` lets a GM add items to an inventory that would
+    /// otherwise refuse them for going over its capacity.
     pub async fn add_or_remove_items_from_inventory(
         &self,
         inventory_id: String,
         items: Vec<InventoryItemQuantityAdjustmentParams>,
-    ) -> bool {
-        let res = self
-            .inventory_item_model_manager
-            .add_or_remove_items_from_inventory(inventory_id, items)
-            .await;
-        res
+        #[graphql(default)] override_capacity: bool,
+    ) -> Result<InventoryItemsPayload> {
+        self.inventory_item_model_manager
+            .add_or_remove_items_from_inventory(inventory_id, items, override_capacity)
+            .await
+            .extend()
+    }
+
+    /// Buys items into the inventory, charging their value times the quantity
+    /// and making change from its purse. Nothing is bought when the purse
+    /// cannot cover the total.
+    pub async fn buy_items(
+        &self,
+        inventory_id: String,
+        items: Vec<BuyItemParams>,
+        #[graphql(default)] override_capacity: bool,
+    ) -> Result<BuyItemsPayload> {
+        self.inventory_item_model_manager
+            .buy_items(inventory_id, items, override_capacity)
+            .await
+            .extend()
+    }
+
+    /// Buys items from a shop at its prices, moving the items from its stock
+    /// and the coins into its purse.
+    pub async fn buy_from_shop(
+        &self,
+        shop_id: String,
+        buyer_inventory_id: String,
+        items: Vec<ShopPurchaseParams>,
+        #[graphql(default)] override_capacity: bool,
+    ) -> Result<BuyItemsPayload> {
+        self.inventory_item_model_manager
+            .buy_from_shop(shop_id, buyer_inventory_id, items, override_capacity)
+            .await
+            .extend()
+    }
+
+    /// Moves items from one inventory to another atomically: either every
+    /// line moves or none does.
+    pub async fn transfer_items(
+        &self,
+        source_inventory_id: String,
+        target_inventory_id: String,
+        items: Vec<TransferItemParams>,
+        #[graphql(default)] override_capacity: bool,
+    ) -> Result<TransferItemsPayload> {
+        self.inventory_item_model_manager
+            .transfer_items(
+                source_inventory_id,
+                target_inventory_id,
+                items,
+                override_capacity,
+            )
+            .await
+            .extend()
+    }
+
+    /// Moves items within the inventory, between loose and the container
+    /// items it holds. Leaving a container id out means loose.
+    pub async fn move_items(
+        &self,
+        inventory_id: String,
+        items: Vec<TransferItemParams>,
+        from_container_id: Option<String>,
+        to_container_id: Option<String>,
+        #[graphql(default)] override_capacity: bool,
+    ) -> Result<InventoryItemsPayload> {
+        self.inventory_item_model_manager
+            .move_items(
+                inventory_id,
+                items,
+                from_container_id,
+                to_container_id,
+                override_capacity,
+            )
+            .await
+            .extend()
+    }
+
+    /// Sets how loose stacks and instances are carried: stowed, worn or
+    /// held, and whether they are invested. Every line is applied or none is.
+    pub async fn set_carry_states(
+        &self,
+        inventory_id: String,
+        items: Vec<CarryStateParams>,
+    ) -> Result<InventoryItemsPayload> {
+        self.inventory_item_model_manager
+            .set_carry_states(inventory_id, items)
+            .await
+            .extend()
+    }
+
+    /// Adds one copy of the item with its own name, notes, charges, hit
+    /// points and runes.
+    pub async fn create_item_instance(
+        &self,
+        inventory_id: String,
+        item_id: String,
+        container_id: Option<String>,
+        #[graphql(default)] instance: ItemInstanceInput,
+        #[graphql(default)] override_capacity: bool,
+    ) -> Result<InventoryItemsPayload> {
+        self.inventory_item_model_manager
+            .create_item_instance(
+                inventory_id,
+                item_id,
+                container_id,
+                instance,
+                override_capacity,
+            )
+            .await
+            .extend()
     }
 
+    /// Replaces every override of the instance; fields left out are cleared.
+    pub async fn update_item_instance(
+        &self,
+        inventory_id: String,
+        instance_id: String,
+        instance: ItemInstanceInput,
+    ) -> Result<InventoryItemsPayload> {
+        self.inventory_item_model_manager
+            .update_item_instance(inventory_id, instance_id, instance)
+            .await
+            .extend()
+    }
+
+    pub async fn remove_item_instance(
+        &self,
+        inventory_id: String,
+        instance_id: String,
+    ) -> Result<InventoryItemsPayload> {
+        self.inventory_item_model_manager
+            .remove_item_instance(inventory_id, instance_id)
+            .await
+            .extend()
+    }
+
+    /// Uses up a charge of an instance, or one copy of a consumable.
+    pub async fn use_item(
+        &self,
+        inventory_id: String,
+        params: UseItemParams,
+    ) -> Result<InventoryItemsPayload> {
+        self.inventory_item_model_manager
+            .use_item(inventory_id, params)
+            .await
+            .extend()
+    }
+
+    /// Restores the charges of wands, staffs and other items with daily
+    /// charges, and removes the inventory's temporary item instances.
+    pub async fn daily_preparations(
+        &self,
+        inventory_id: String,
+    ) -> Result<DailyPreparationsPayload> {
+        self.inventory_item_model_manager
+            .daily_preparations(inventory_id)
+            .await
+            .extend()
+    }
+
+    /// Sells items for a share of their value, set per line, by the merchant
+    /// named by `merchantId`, or campaign-wide. Treasure sells at full value.
     pub async fn sell_items(
         &self,
         inventory_id: String,
-        items: Vec<InventoryItemQuantityAdjustmentParams>,
-    ) -> bool {
-        let res = self
-            .inventory_item_model_manager
-            .sell_items(inventory_id, items)
-            .await;
-        res
+        items: Vec<SellItemParams>,
+        merchant_id: Option<String>,
+    ) -> Result<SellItemsPayload> {
+        self.inventory_item_model_manager
+            .sell_items(inventory_id, items, merchant_id)
+            .await
+            .extend()
     }
 }
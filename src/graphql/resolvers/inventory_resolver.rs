@@ -1,7 +1,12 @@
-use crate::graphql::schemas::inventory_schema::{Inventory, InventoryCurrencyChangeInput};
+use crate::graphql::schemas::inventory_schema::{
+    CreateInventoryInput, CurrencySplitPayload, CurrencyTransferPayload, DeleteInventoryInput,
+    Inventory, InventoryCurrencyChangeInput, InventoryQueryFilter, SplitCurrencyInput,
+    UpdateInventoryInput,
+};
+use crate::graphql::schemas::money_schema::Money;
 use crate::graphql::schemas::paginated_response_schema::PaginatedResponse;
 use crate::models::inventory_model::InventoryModelManager;
-use async_graphql::Object;
+use async_graphql::{Object, Result, ResultExt};
 
 pub struct InventoryQuery {
     inventory_model_manager: InventoryModelManager,
@@ -17,30 +22,48 @@ impl InventoryQuery {
 
 #[Object]
 impl InventoryQuery {
-    pub async fn get_inventory(&self, id: String) -> Option<Inventory> {
-        self.inventory_model_manager.get_inventory_by_uuid(id).await
+    pub async fn get_inventory(&self, id: String) -> Result<Inventory> {
+        self.inventory_model_manager
+            .get_inventory_by_uuid(id)
+            .await
+            .extend()
     }
 
-    pub async fn get_chracter_inventory(&self, id: String) -> Option<Inventory> {
+    pub async fn get_chracter_inventory(&self, id: String) -> Result<Inventory> {
         self.inventory_model_manager
             .get_inventory_by_character_uuid(id)
             .await
+            .extend()
     }
 
-    pub async fn get_inventory_by_owner(&self, id: String) -> Option<Inventory> {
+    pub async fn get_inventory_by_owner(&self, id: String) -> Result<Inventory> {
         self.inventory_model_manager
             .get_inventory_by_owner_uuid(id)
             .await
+            .extend()
     }
 
-    pub async fn get_inventory_by_owner_name(&self, name_term: String) -> Option<Inventory> {
+    pub async fn get_inventory_by_owner_name(&self, name_term: String) -> Result<Inventory> {
         self.inventory_model_manager
             .get_inventory_by_owner_name(name_term)
             .await
+            .extend()
     }
 
-    pub async fn get_inventories(&self) -> PaginatedResponse<Inventory> {
-        self.inventory_model_manager.get_inventories().await
+    /// Lists inventories a page at a time. `orderBy` accepts `name`,
+    /// `capacity` or `wealth`.
+    pub async fn get_inventories(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: String,
+        order_direction: String,
+        filter: InventoryQueryFilter,
+    ) -> Result<PaginatedResponse<Inventory>> {
+        self.inventory_model_manager
+            .get_inventories(page_index, page_size, order_by, order_direction, filter)
+            .await
+            .extend()
     }
 }
 
@@ -62,9 +85,88 @@ impl InventoryMutation {
         &self,
         inventory_id: String,
         params: InventoryCurrencyChangeInput,
-    ) -> Option<Inventory> {
+    ) -> Result<Inventory> {
         self.inventory_model_manager
             .update_inventory_currency(inventory_id, params)
             .await
+            .extend()
+    }
+
+    /// Takes `amount` from the purse, breaking larger coins into change when
+    /// the exact coins are not at hand.
+    pub async fn spend_inventory_currency(
+        &self,
+        inventory_id: String,
+        amount: Money,
+    ) -> Result<Inventory> {
+        self.inventory_model_manager
+            .spend_inventory_currency(inventory_id, amount)
+            .await
+            .extend()
+    }
+
+    /// Moves `amount` from one inventory's purse to another's.
+    pub async fn transfer_currency(
+        &self,
+        source_inventory_id: String,
+        target_inventory_id: String,
+        amount: Money,
+    ) -> Result<CurrencyTransferPayload> {
+        self.inventory_model_manager
+            .transfer_currency(source_inventory_id, target_inventory_id, amount)
+            .await
+            .extend()
+    }
+
+    /// Divides coins evenly between inventories, such as a party splitting
+    /// loot, and reports each share.
+    pub async fn split_currency(&self, params: SplitCurrencyInput) -> Result<CurrencySplitPayload> {
+        self.inventory_model_manager
+            .split_currency(params)
+            .await
+            .extend()
+    }
+
+    pub async fn create_inventory(&self, params: CreateInventoryInput) -> Result<Inventory> {
+        self.inventory_model_manager
+            .create_inventory(params)
+            .await
+            .extend()
+    }
+
+    pub async fn update_inventory(
+        &self,
+        inventory_id: String,
+        params: UpdateInventoryInput,
+    ) -> Result<Inventory> {
+        self.inventory_model_manager
+            .update_inventory(inventory_id, params)
+            .await
+            .extend()
+    }
+
+    /// Moves the inventory to a new owner, or leaves it unowned when `ownerId`
+    /// is omitted.
+    pub async fn reassign_inventory(
+        &self,
+        inventory_id: String,
+        owner_id: Option<String>,
+    ) -> Result<Inventory> {
+        self.inventory_model_manager
+            .reassign_inventory(inventory_id, owner_id)
+            .await
+            .extend()
+    }
+
+    /// Deletes the inventory and returns it as it was before deletion.
+    pub async fn delete_inventory(
+        &self,
+        inventory_id: String,
+        params: DeleteInventoryInput,
+    ) -> Result<Inventory> {
+        self.inventory_model_manager
+            .delete_inventory(inventory_id, params)
+            .await
+            .extend()
     }
 }
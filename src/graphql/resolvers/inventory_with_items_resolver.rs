@@ -1,7 +1,7 @@
 use crate::graphql::schemas::inventory_with_items_schema::InventoryWithItems;
 use crate::graphql::schemas::item_schema::ItemQueryFilter;
 use crate::models::inventory_with_items_model::InventoryWithItemsModelManager;
-use async_graphql::Object;
+use async_graphql::{Object, Result, ResultExt};
 
 pub struct InventoryWithItemsQuery {
     inventory_with_items_model_manager: InventoryWithItemsModelManager,
@@ -14,6 +14,7 @@ impl InventoryWithItemsQuery {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[Object]
 impl InventoryWithItemsQuery {
     pub async fn get_inventory_with_items_by_owner_name(
@@ -24,7 +25,7 @@ impl InventoryWithItemsQuery {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<InventoryWithItems> {
+    ) -> Result<InventoryWithItems> {
         self.inventory_with_items_model_manager
             .get_inventory_with_items_by_owner_name(
                 name_term,
@@ -35,6 +36,7 @@ impl InventoryWithItemsQuery {
                 filter,
             )
             .await
+            .extend()
     }
 
     pub async fn get_inventory_with_items_by_id(
@@ -45,7 +47,7 @@ impl InventoryWithItemsQuery {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<InventoryWithItems> {
+    ) -> Result<InventoryWithItems> {
         self.inventory_with_items_model_manager
             .get_inventory_with_items_by_id(
                 id,
@@ -56,5 +58,6 @@ impl InventoryWithItemsQuery {
                 filter,
             )
             .await
+            .extend()
     }
 }
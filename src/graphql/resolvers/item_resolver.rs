@@ -2,11 +2,11 @@ use crate::{
     graphql::schemas::{
         item_schema::{Item, ItemProperties, ItemQueryFilter},
         paginated_response_schema::PaginatedResponse,
-        trait_schema::Trait,
+        trait_schema::{Trait, TraitCategory, TraitInput, TraitUpdate},
     },
     models::item_model::ItemModelManager,
 };
-use async_graphql::Object;
+use async_graphql::{Object, Result, ResultExt};
 
 pub struct ItemQuery {
     item_model_manager: ItemModelManager,
@@ -27,18 +27,25 @@ impl ItemQuery {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<PaginatedResponse<Item>> {
+    ) -> Result<PaginatedResponse<Item>> {
         self.item_model_manager
             .get_items(page_index, page_size, order_by, order_direction, filter)
             .await
+            .extend()
     }
 
-    pub async fn get_item(&self, id: String) -> Option<Item> {
-        self.item_model_manager.get_item(&id).await
+    pub async fn get_item(&self, id: String) -> Result<Item> {
+        self.item_model_manager.get_item(&id).await.extend()
     }
 
-    pub async fn get_traits(&self) -> Vec<Trait> {
-        self.item_model_manager.get_traits().await
+    /// Every trait with how many items have it, optionally only those of
+    /// one category.
+    pub async fn get_traits(&self, category: Option<TraitCategory>) -> Result<Vec<Trait>> {
+        self.item_model_manager.get_traits(category).await.extend()
+    }
+
+    pub async fn get_trait(&self, name: String) -> Result<Trait> {
+        self.item_model_manager.get_trait(&name).await.extend()
     }
 }
 pub struct ItemMutation {
@@ -52,13 +59,53 @@ impl ItemMutation {
 
 #[Object]
 impl ItemMutation {
-    pub async fn create_item(&self, params: ItemProperties) -> Option<Item> {
-        let res = self.item_model_manager.create_item(params).await;
-        res
+    /// With `rejectUnknownTraits`, traits that were not created through
+    /// `createTrait` fail with UNKNOWN_TRAITS instead of being created.
+    pub async fn create_item(
+        &self,
+        params: ItemProperties,
+        #[graphql(default)] reject_unknown_traits: bool,
+    ) -> Result<Item> {
+        self.item_model_manager
+            .create_item(params, reject_unknown_traits)
+            .await
+            .extend()
+    }
+
+    pub async fn update_item(
+        &self,
+        item_uuid: String,
+        params: ItemProperties,
+        #[graphql(default)] reject_unknown_traits: bool,
+    ) -> Result<Item> {
+        self.item_model_manager
+            .update_item(item_uuid, params, reject_unknown_traits)
+            .await
+            .extend()
     }
 
-    pub async fn update_item(&self, item_uuid: String, params: ItemProperties) -> Option<Item> {
-        let res = self.item_model_manager.update_item(item_uuid, params).await;
-        res
+    pub async fn create_trait(&self, params: TraitInput) -> Result<Trait> {
+        self.item_model_manager.create_trait(params).await.extend()
+    }
+
+    pub async fn update_trait(&self, name: String, params: TraitUpdate) -> Result<Trait> {
+        self.item_model_manager
+            .update_trait(name, params)
+            .await
+            .extend()
+    }
+
+    /// Removes the trait from every item that has it.
+    pub async fn delete_trait(&self, name: String) -> Result<Trait> {
+        self.item_model_manager.delete_trait(name).await.extend()
+    }
+
+    /// Moves `source` onto every item that has it as `target` and deletes
+    /// `source`, e.g. to fold a misspelt trait into the right one.
+    pub async fn merge_traits(&self, source: String, target: String) -> Result<Trait> {
+        self.item_model_manager
+            .merge_traits(source, target)
+            .await
+            .extend()
     }
 }
@@ -2,4 +2,6 @@ pub mod inventory_item_resolver;
 pub mod inventory_resolver;
 pub mod inventory_with_items_resolver;
 pub mod item_resolver;
+pub mod owner_resolver;
 pub mod root_resolver;
+pub mod shop_resolver;
@@ -0,0 +1,60 @@
+use crate::graphql::schemas::owner_schema::{CreateOwnerInput, Owner, UpdateOwnerInput};
+use crate::models::owner_model::OwnerModelManager;
+use async_graphql::{Object, Result, ResultExt};
+
+pub struct OwnerQuery {
+    owner_model_manager: OwnerModelManager,
+}
+
+impl OwnerQuery {
+    pub fn new(owner_model_manager: OwnerModelManager) -> Self {
+        Self {
+            owner_model_manager,
+        }
+    }
+}
+
+#[Object]
+impl OwnerQuery {
+    pub async fn get_owners(&self) -> Result<Vec<Owner>> {
+        self.owner_model_manager.get_owners().await.extend()
+    }
+
+    pub async fn get_owner(&self, id: String) -> Result<Owner> {
+        self.owner_model_manager.get_owner(id).await.extend()
+    }
+}
+
+pub struct OwnerMutation {
+    owner_model_manager: OwnerModelManager,
+}
+
+impl OwnerMutation {
+    pub fn new(owner_model_manager: OwnerModelManager) -> Self {
+        Self {
+            owner_model_manager,
+        }
+    }
+}
+
+#[Object]
+impl OwnerMutation {
+    pub async fn create_owner(&self, params: CreateOwnerInput) -> Result<Owner> {
+        self.owner_model_manager.create_owner(params).await.extend()
+    }
+
+    pub async fn update_owner(&self, owner_id: String, params: UpdateOwnerInput) -> Result<Owner> {
+        self.owner_model_manager
+            .update_owner(owner_id, params)
+            .await
+            .extend()
+    }
+
+    /// Deletes an owner that no longer owns any inventory.
+    pub async fn delete_owner(&self, owner_id: String) -> Result<Owner> {
+        self.owner_model_manager
+            .delete_owner(owner_id)
+            .await
+            .extend()
+    }
+}
@@ -1,22 +1,28 @@
 use crate::graphql::resolvers::{
     inventory_item_resolver::InventoryItemQuery, inventory_resolver::InventoryQuery,
     inventory_with_items_resolver::InventoryWithItemsQuery, item_resolver::ItemQuery,
+    owner_resolver::OwnerQuery, shop_resolver::ShopQuery,
 };
 use crate::models::{
     inventory_item_model::InventoryItemModelManager, inventory_model::InventoryModelManager,
     inventory_with_items_model::InventoryWithItemsModelManager, item_model::ItemModelManager,
+    owner_model::OwnerModelManager, shop_model::ShopModelManager,
 };
 use async_graphql::Object;
 
 use super::inventory_item_resolver::InventoryItemMutation;
 use super::inventory_resolver::InventoryMutation;
 use super::item_resolver::ItemMutation;
+use super::owner_resolver::OwnerMutation;
+use super::shop_resolver::ShopMutation;
 
 pub struct QueryRoot {
     inventory: InventoryQuery,
     inventory_items: InventoryItemQuery,
     inventory_with_items: InventoryWithItemsQuery,
     items: ItemQuery,
+    owners: OwnerQuery,
+    shops: ShopQuery,
 }
 
 impl QueryRoot {
@@ -25,12 +31,16 @@ impl QueryRoot {
         inventory_item_model_manager: InventoryItemModelManager,
         inventory_with_items_model_manager: InventoryWithItemsModelManager,
         item_model_manager: ItemModelManager,
+        owner_model_manager: OwnerModelManager,
+        shop_model_manager: ShopModelManager,
     ) -> Self {
         Self {
             inventory: InventoryQuery::new(inventory_model_manager),
             inventory_items: InventoryItemQuery::new(inventory_item_model_manager),
             inventory_with_items: InventoryWithItemsQuery::new(inventory_with_items_model_manager),
             items: ItemQuery::new(item_model_manager),
+            owners: OwnerQuery::new(owner_model_manager),
+            shops: ShopQuery::new(shop_model_manager),
         }
     }
 }
@@ -51,23 +61,37 @@ impl QueryRoot {
     async fn inventory_with_items(&self) -> &InventoryWithItemsQuery {
         &self.inventory_with_items
     }
+
+    async fn owners(&self) -> &OwnerQuery {
+        &self.owners
+    }
+
+    async fn shops(&self) -> &ShopQuery {
+        &self.shops
+    }
 }
 
 pub struct MutationRoot {
     inventory_items: InventoryItemMutation,
     items: ItemMutation,
     inventory: InventoryMutation,
+    owners: OwnerMutation,
+    shops: ShopMutation,
 }
 impl MutationRoot {
     pub fn new(
         inventory_item_model_manager: InventoryItemModelManager,
         item_model_manager: ItemModelManager,
         inventory_model_manager: InventoryModelManager,
+        owner_model_manager: OwnerModelManager,
+        shop_model_manager: ShopModelManager,
     ) -> Self {
         Self {
             inventory_items: InventoryItemMutation::new(inventory_item_model_manager),
             items: ItemMutation::new(item_model_manager),
             inventory: InventoryMutation::new(inventory_model_manager),
+            owners: OwnerMutation::new(owner_model_manager),
+            shops: ShopMutation::new(shop_model_manager),
         }
     }
 }
@@ -83,4 +107,10 @@ impl MutationRoot {
     async fn inventory(&self) -> &InventoryMutation {
         &self.inventory
     }
+    async fn owners(&self) -> &OwnerMutation {
+        &self.owners
+    }
+    async fn shops(&self) -> &ShopMutation {
+        &self.shops
+    }
 }
@@ -0,0 +1,88 @@
+use crate::graphql::schemas::{
+    inventory_schema::Inventory,
+    shop_schema::{ShopListing, ShopListingInput, ShopOffer, ShopSettingsInput},
+};
+use crate::models::shop_model::ShopModelManager;
+use async_graphql::{Object, Result, ResultExt};
+
+pub struct ShopQuery {
+    shop_model_manager: ShopModelManager,
+}
+
+impl ShopQuery {
+    pub fn new(shop_model_manager: ShopModelManager) -> Self {
+        Self { shop_model_manager }
+    }
+}
+
+#[Object]
+impl ShopQuery {
+    /// What the shop sells and at what price.
+    pub async fn get_shop_offers(&self, shop_id: String) -> Result<Vec<ShopOffer>> {
+        self.shop_model_manager
+            .get_shop_offers(shop_id)
+            .await
+            .extend()
+    }
+
+    pub async fn get_shop_listings(&self, shop_id: String) -> Result<Vec<ShopListing>> {
+        self.shop_model_manager
+            .get_shop_listings(shop_id)
+            .await
+            .extend()
+    }
+}
+
+pub struct ShopMutation {
+    shop_model_manager: ShopModelManager,
+}
+
+impl ShopMutation {
+    pub fn new(shop_model_manager: ShopModelManager) -> Self {
+        Self { shop_model_manager }
+    }
+}
+
+#[Object]
+impl ShopMutation {
+    /// Makes the inventory a shop with these settings; without settings it
+    /// becomes an ordinary inventory again.
+    pub async fn set_shop_settings(
+        &self,
+        inventory_id: String,
+        settings: Option<ShopSettingsInput>,
+    ) -> Result<Inventory> {
+        self.shop_model_manager
+            .set_shop_settings(inventory_id, settings)
+            .await
+            .extend()
+    }
+
+    /// Sets the price or unlimited stock of one item in a shop.
+    pub async fn set_shop_listing(
+        &self,
+        shop_id: String,
+        params: ShopListingInput,
+    ) -> Result<ShopListing> {
+        self.shop_model_manager
+            .set_shop_listing(shop_id, params)
+            .await
+            .extend()
+    }
+
+    pub async fn remove_shop_listing(
+        &self,
+        shop_id: String,
+        item_id: String,
+    ) -> Result<Vec<ShopListing>> {
+        self.shop_model_manager
+            .remove_shop_listing(shop_id, item_id)
+            .await
+            .extend()
+    }
+
+    /// Tops the shop's stock back up from its restock template.
+    pub async fn restock_shop(&self, shop_id: String) -> Result<Inventory> {
+        self.shop_model_manager.restock_shop(shop_id).await.extend()
+    }
+}
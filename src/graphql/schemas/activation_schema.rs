@@ -0,0 +1,65 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+
+/// How an item is activated, following the PF2e action economy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum ActivationKind {
+    OneAction,
+    TwoActions,
+    ThreeActions,
+    Reaction,
+    FreeAction,
+    /// Takes longer than a turn; the time is given in minutes.
+    Duration,
+}
+
+impl ActivationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivationKind::OneAction => "ONE_ACTION",
+            ActivationKind::TwoActions => "TWO_ACTIONS",
+            ActivationKind::ThreeActions => "THREE_ACTIONS",
+            ActivationKind::Reaction => "REACTION",
+            ActivationKind::FreeAction => "FREE_ACTION",
+            ActivationKind::Duration => "DURATION",
+        }
+    }
+
+    /// Reads a stored kind; anything unknown means the item has no
+    /// activation.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ONE_ACTION" => Some(ActivationKind::OneAction),
+            "TWO_ACTIONS" => Some(ActivationKind::TwoActions),
+            "THREE_ACTIONS" => Some(ActivationKind::ThreeActions),
+            "REACTION" => Some(ActivationKind::Reaction),
+            "FREE_ACTION" => Some(ActivationKind::FreeAction),
+            "DURATION" => Some(ActivationKind::Duration),
+            _ => None,
+        }
+    }
+}
+
+/// What it takes to activate an item, e.g. two actions or 10 minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SimpleObject, InputObject)]
+#[graphql(input_name = "ActivationCostInput")]
+pub struct ActivationCost {
+    pub kind: ActivationKind,
+    /// Only set, and required, for a `DURATION` activation.
+    pub minutes: Option<u32>,
+}
+
+impl ActivationCost {
+    /// Checks that exactly the duration activations carry minutes.
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.kind, self.minutes) {
+            (ActivationKind::Duration, None | Some(0)) => {
+                Err("a duration activation needs a positive number of minutes".to_string())
+            }
+            (ActivationKind::Duration, Some(_)) | (_, None) => Ok(()),
+            (kind, Some(_)) => Err(format!(
+                "a {} activation takes no minutes",
+                kind.as_str().to_lowercase().replace('_', " ")
+            )),
+        }
+    }
+}
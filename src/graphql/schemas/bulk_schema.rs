@@ -0,0 +1,110 @@
+use std::fmt;
+use std::str::FromStr;
+
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, Value};
+
+/// How much an item weighs in PF2e terms. Exposed as the `Bulk` scalar, which
+/// reads an integer or a string such as `"L"`, `"—"` or `"2"` and is written
+/// back in that string notation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bulk {
+    #[default]
+    Negligible,
+    Light,
+    /// A whole number of bulk, always at least 1.
+    Units(u32),
+}
+
+impl Bulk {
+    pub fn from_units(units: u32) -> Self {
+        match units {
+            0 => Bulk::Negligible,
+            units => Bulk::Units(units),
+        }
+    }
+}
+
+impl fmt::Display for Bulk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bulk::Negligible => write!(f, "—"),
+            Bulk::Light => write!(f, "L"),
+            Bulk::Units(units) => write!(f, "{}", units),
+        }
+    }
+}
+
+impl FromStr for Bulk {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "" | "—" | "–" | "-" | "negligible" => Ok(Bulk::Negligible),
+            "l" | "light" => Ok(Bulk::Light),
+            units => units
+                .parse::<u32>()
+                .map(Bulk::from_units)
+                .map_err(|_| format!("\"{}\" is not a valid bulk", value)),
+        }
+    }
+}
+
+#[Scalar]
+impl ScalarType for Bulk {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        match &value {
+            Value::Number(number) => match number.as_u64().and_then(|n| u32::try_from(n).ok()) {
+                Some(units) => Ok(Bulk::from_units(units)),
+                None => Err(InputValueError::custom(format!(
+                    "{} is not a valid bulk",
+                    number
+                ))),
+            },
+            Value::String(value) => value.parse().map_err(InputValueError::custom),
+            _ => Err(InputValueError::expected_type(value)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_negligible_notations() {
+        for value in ["", "—", "–", "-", "Negligible", " negligible "] {
+            assert_eq!(value.parse(), Ok(Bulk::Negligible), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn parses_light() {
+        assert_eq!("L".parse(), Ok(Bulk::Light));
+        assert_eq!("light".parse(), Ok(Bulk::Light));
+    }
+
+    #[test]
+    fn parses_whole_units() {
+        assert_eq!("2".parse(), Ok(Bulk::Units(2)));
+        assert_eq!("0".parse(), Ok(Bulk::Negligible));
+    }
+
+    #[test]
+    fn rejects_other_values() {
+        for value in ["0.1", "-1", "heavy", "2 bulk"] {
+            assert!(value.parse::<Bulk>().is_err(), "{:?} parsed", value);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        for bulk in [Bulk::Negligible, Bulk::Light, Bulk::Units(3)] {
+            assert_eq!(bulk.to_string().parse(), Ok(bulk));
+        }
+    }
+}
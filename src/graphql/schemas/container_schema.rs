@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+
+use async_graphql::{InputObject, SimpleObject};
+
+use crate::graphql::schemas::{
+    bulk_schema::Bulk, inventory_item_schema::CarryState, inventory_schema::InventoryLoad,
+};
+
+/// What lets an item hold other items, such as a backpack or a Bag of
+/// Holding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SimpleObject, InputObject)]
+#[graphql(input_name = "ContainerPropertiesInput")]
+pub struct ContainerProperties {
+    /// The bulk one of these containers can hold.
+    pub capacity: u32,
+    /// Bulk of the contents that does not count towards what the inventory
+    /// carries: 2 for a backpack, the whole capacity for a Bag of Holding.
+    #[graphql(default)]
+    pub ignored_bulk: u32,
+    /// The ignored bulk only applies while the container is worn, as with a
+    /// backpack.
+    #[graphql(default)]
+    pub ignored_only_when_worn: bool,
+}
+
+/// One stack of an item in an inventory, either loose or inside a container
+/// item held by the same inventory.
+#[derive(Debug, Clone)]
+pub struct InventoryContent {
+    pub item_uuid: String,
+    pub container_uuid: Option<String>,
+    pub quantity: u32,
+    pub bulk: Bulk,
+    /// Present when the item is itself a container.
+    pub container: Option<ContainerProperties>,
+    pub carry: CarryState,
+    pub invested: bool,
+    /// Set when the stack is a single item instance.
+    pub instance_uuid: Option<String>,
+}
+
+/// The stacks of an inventory arranged by where they are kept.
+pub struct ContentTree<'a> {
+    by_location: HashMap<Option<&'a str>, Vec<&'a InventoryContent>>,
+}
+
+impl<'a> ContentTree<'a> {
+    pub fn new(contents: &'a [InventoryContent]) -> Self {
+        let mut by_location: HashMap<Option<&str>, Vec<&InventoryContent>> = HashMap::new();
+        for content in contents {
+            by_location
+                .entry(content.container_uuid.as_deref())
+                .or_default()
+                .push(content);
+        }
+        Self { by_location }
+    }
+
+    /// What the inventory carries, with each container's ignored bulk taken
+    /// off its contents.
+    pub fn load(&self) -> InventoryLoad {
+        self.load_in(None, true, &mut HashSet::new())
+    }
+
+    /// What the stacks inside `container_uuid` weigh before any reduction.
+    pub fn contents_load(&self, container_uuid: &'a str) -> InventoryLoad {
+        self.load_in(Some(container_uuid), false, &mut HashSet::new())
+    }
+
+    /// The stacks kept directly in `location`.
+    pub fn stacks_in(&self, location: Option<&'a str>) -> &[&'a InventoryContent] {
+        self.by_location
+            .get(&location)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn load_in(
+        &self,
+        location: Option<&'a str>,
+        reduce: bool,
+        visited: &mut HashSet<&'a str>,
+    ) -> InventoryLoad {
+        let mut load = InventoryLoad::default();
+        for content in self.stacks_in(location) {
+            load.add(content.bulk, content.quantity);
+            let Some(container) = content.container else {
+                continue;
+            };
+            // Stored data may still hold a cycle; count each container once
+            if !visited.insert(content.item_uuid.as_str()) {
+                continue;
+            }
+            let inner = self.load_in(Some(content.item_uuid.as_str()), true, visited);
+            // The stack and instances of a container share its contents, so
+            // every copy that qualifies adds its ignored bulk
+            let ignored = if reduce {
+                self.stacks_in(location)
+                    .iter()
+                    .filter(|copy| copy.item_uuid == content.item_uuid)
+                    .filter(|copy| {
+                        copy.carry == CarryState::Worn || !container.ignored_only_when_worn
+                    })
+                    .fold(0u32, |ignored, copy| {
+                        ignored.saturating_add(container.ignored_bulk.saturating_mul(copy.quantity))
+                    })
+            } else {
+                0
+            };
+            load.absorb(inner.without_first(ignored));
+        }
+        load
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(item_uuid: &str, container_uuid: Option<&str>, bulk: Bulk) -> InventoryContent {
+        InventoryContent {
+            item_uuid: item_uuid.to_string(),
+            container_uuid: container_uuid.map(str::to_string),
+            quantity: 1,
+            bulk,
+            container: None,
+            carry: CarryState::Stowed,
+            invested: false,
+            instance_uuid: None,
+        }
+    }
+
+    /// A backpack holding a bedroll, a sword and a torch, carried as given.
+    fn packed(carry: CarryState, ignored_only_when_worn: bool) -> Vec<InventoryContent> {
+        let mut backpack = stack("backpack", None, Bulk::Light);
+        backpack.carry = carry;
+        backpack.container = Some(ContainerProperties {
+            capacity: 4,
+            ignored_bulk: 2,
+            ignored_only_when_worn,
+        });
+        vec![
+            backpack,
+            stack("bedroll", Some("backpack"), Bulk::Units(1)),
+            stack("longsword", Some("backpack"), Bulk::Units(1)),
+            stack("torch", Some("backpack"), Bulk::Light),
+        ]
+    }
+
+    fn load(bulk: u32, light_items: u32) -> InventoryLoad {
+        InventoryLoad { bulk, light_items }
+    }
+
+    #[test]
+    fn a_worn_backpack_ignores_the_first_two_bulk() {
+        let contents = packed(CarryState::Worn, true);
+        assert_eq!(ContentTree::new(&contents).load(), load(0, 2));
+    }
+
+    #[test]
+    fn a_backpack_that_is_not_worn_ignores_nothing() {
+        let contents = packed(CarryState::Stowed, true);
+        assert_eq!(ContentTree::new(&contents).load(), load(2, 2));
+    }
+
+    #[test]
+    fn ignored_bulk_not_tied_to_wearing_always_applies() {
+        let contents = packed(CarryState::Stowed, false);
+        assert_eq!(ContentTree::new(&contents).load(), load(0, 2));
+    }
+
+    #[test]
+    fn copies_of_a_container_share_the_reduction_in_any_order() {
+        // A worn backpack instance next to a stowed backpack stack, both
+        // holding the same three items
+        let mut contents = packed(CarryState::Stowed, true);
+        let mut worn = contents[0].clone();
+        worn.carry = CarryState::Worn;
+        worn.instance_uuid = Some("worn-backpack".to_string());
+        contents.push(worn);
+        assert_eq!(ContentTree::new(&contents).load(), load(0, 3));
+        contents.rotate_right(1);
+        assert_eq!(ContentTree::new(&contents).load(), load(0, 3));
+    }
+
+    #[test]
+    fn contents_load_is_not_reduced() {
+        let contents = packed(CarryState::Worn, true);
+        assert_eq!(
+            ContentTree::new(&contents).contents_load("backpack"),
+            load(2, 1)
+        );
+    }
+
+    #[test]
+    fn nested_containers_reduce_their_own_contents() {
+        let mut contents = packed(CarryState::Worn, true);
+        let mut pouch = stack("pouch", Some("backpack"), Bulk::Negligible);
+        pouch.container = Some(ContainerProperties {
+            capacity: 1,
+            ignored_bulk: 1,
+            ignored_only_when_worn: false,
+        });
+        contents.push(pouch);
+        contents.push(stack("lantern", Some("pouch"), Bulk::Units(1)));
+        assert_eq!(ContentTree::new(&contents).load(), load(0, 2));
+        assert_eq!(
+            ContentTree::new(&contents).contents_load("backpack"),
+            load(3, 1)
+        );
+    }
+}
@@ -1,10 +1,77 @@
-use crate::graphql::schemas::item_schema::Item;
-use async_graphql::{InputObject, Object, ID};
+use crate::error::AppError;
+use crate::graphql::schemas::{
+    activation_schema::ActivationCost,
+    bulk_schema::Bulk,
+    container_schema::ContainerProperties,
+    inventory_schema::{CapacityEnforcement, Inventory},
+    item_instance_schema::ItemInstance,
+    item_schema::Item,
+    money_schema::{Money, Purse},
+};
+use async_graphql::{Enum, InputObject, Object, SimpleObject, ID};
 
 #[derive(Debug, Clone)]
 pub struct InventoryItem {
     pub item: Item,
     pub quantity: u32,
+    /// The container item the stack is kept in; loose when `None`.
+    pub container_id: Option<ID>,
+    pub carry: CarryState,
+    pub invested: bool,
+    /// Present when the row is a single instance rather than a stack.
+    pub instance: Option<ItemInstance>,
+}
+
+/// How many hands a character has to hold items with.
+pub const MAX_HANDS: u32 = 2;
+
+/// How many items a character can have invested at once.
+pub const MAX_INVESTED_ITEMS: u32 = 10;
+
+/// How a loose stack is carried. Stacks inside containers are always stowed.
+/// The state covers the whole stack, so every item of a stack held in one
+/// hand takes a hand of its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Enum)]
+pub enum CarryState {
+    #[default]
+    Stowed,
+    Worn,
+    HeldInOneHand,
+    HeldInTwoHands,
+}
+
+impl CarryState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CarryState::Stowed => "STOWED",
+            CarryState::Worn => "WORN",
+            CarryState::HeldInOneHand => "HELD_IN_ONE_HAND",
+            CarryState::HeldInTwoHands => "HELD_IN_TWO_HANDS",
+        }
+    }
+
+    /// Reads a stored state, treating anything unknown as `Stowed`.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "WORN" => CarryState::Worn,
+            "HELD_IN_ONE_HAND" => CarryState::HeldInOneHand,
+            "HELD_IN_TWO_HANDS" => CarryState::HeldInTwoHands,
+            _ => CarryState::Stowed,
+        }
+    }
+
+    pub fn hands(&self) -> u32 {
+        match self {
+            CarryState::HeldInOneHand => 1,
+            CarryState::HeldInTwoHands => 2,
+            _ => 0,
+        }
+    }
+
+    /// Worn or held, as opposed to stowed away.
+    pub fn is_equipped(&self) -> bool {
+        *self != CarryState::Stowed
+    }
 }
 
 #[Object]
@@ -13,8 +80,13 @@ impl InventoryItem {
         &self.item.uuid
     }
 
+    /// The instance's custom name when it has one, otherwise the catalog
+    /// name.
     async fn name(&self) -> Option<&String> {
-        self.item.properties.name.as_ref()
+        self.instance
+            .as_ref()
+            .and_then(|instance| instance.custom_name.as_ref())
+            .or(self.item.properties.name.as_ref())
     }
 
     async fn level(&self) -> Option<u16> {
@@ -25,15 +97,38 @@ impl InventoryItem {
         self.quantity
     }
 
+    /// The container item this stack is kept in; empty for loose items.
+    async fn container_id(&self) -> Option<&ID> {
+        self.container_id.as_ref()
+    }
+
+    async fn container(&self) -> Option<ContainerProperties> {
+        self.item.properties.container
+    }
+
+    /// The per-copy state of an instance; empty for stacks.
+    async fn instance(&self) -> Option<&ItemInstance> {
+        self.instance.as_ref()
+    }
+
+    async fn carry(&self) -> CarryState {
+        self.carry
+    }
+
+    /// Every copy in an invested stack counts towards the investment limit.
+    async fn invested(&self) -> bool {
+        self.invested
+    }
+
     async fn traits(&self) -> Option<&Vec<String>> {
         self.item.properties.traits.as_ref()
     }
 
-    async fn activation_cost(&self) -> Option<&str> {
-        self.item.properties.activation_cost.as_deref()
+    async fn activation_cost(&self) -> Option<ActivationCost> {
+        self.item.properties.activation_cost
     }
 
-    async fn bulk(&self) -> Option<f32> {
+    async fn bulk(&self) -> Option<Bulk> {
         self.item.properties.bulk
     }
 
@@ -49,7 +144,7 @@ impl InventoryItem {
         self.item.properties.usage_requirements.as_deref()
     }
 
-    async fn value(&self) -> Option<u64> {
+    async fn value(&self) -> Option<Money> {
         self.item.properties.value
     }
 
@@ -61,16 +156,296 @@ impl InventoryItem {
         self.item.properties.effect.as_deref()
     }
 
+    async fn charges_per_day(&self) -> Option<u32> {
+        self.item.properties.charges_per_day
+    }
+
     async fn is_consumable(&self) -> bool {
-        match &self.item.properties.traits {
-            Some(traits) => traits.contains(&"Consumable".to_string()),
-            None => false,
-        }
+        self.item.has_consumable_trait()
     }
 }
 
+/// A change to one stack. `containerId` names the container item the stack
+/// is kept in; without it the loose stack is changed. Sales, purchases and
+/// transfers always use loose stacks.
 #[derive(Debug, Clone, InputObject)]
 pub struct InventoryItemQuantityAdjustmentParams {
     pub item_id: String,
     pub quantity_change: i32,
+    pub container_id: Option<String>,
+}
+
+/// Sets how the loose stack of an item, or one instance of it, is carried.
+/// Only worn or held items can be invested.
+#[derive(Debug, Clone, InputObject)]
+pub struct CarryStateParams {
+    pub item_id: String,
+    /// Changes this instance of the item instead of its loose stack.
+    pub instance_id: Option<String>,
+    pub carry: CarryState,
+    #[graphql(default)]
+    pub invested: bool,
+}
+
+/// Uses one of an item: a charge when `instanceId` names an instance with
+/// charges, otherwise one copy of a consumable, taken from the loose stack
+/// or from `containerId`.
+#[derive(Debug, Clone, InputObject)]
+pub struct UseItemParams {
+    pub item_id: String,
+    pub instance_id: Option<String>,
+    pub container_id: Option<String>,
+}
+
+/// Restricts inventory items to the stacks kept directly in one place: the
+/// container item `containerId`, or loose in the inventory when it is empty.
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct ItemLocationFilter {
+    pub container_id: Option<String>,
+}
+
+impl ItemLocationFilter {
+    pub fn loose() -> Self {
+        Self::default()
+    }
+
+    pub fn matches(&self, container_uuid: Option<&str>) -> bool {
+        self.container_id.as_deref() == container_uuid
+    }
+}
+
+/// A line of a sale; `quantityChange` is negative. `sellPercent` overrides
+/// the percentage of the item's value paid for it.
+#[derive(Debug, Clone, InputObject)]
+pub struct SellItemParams {
+    pub item_id: String,
+    pub quantity_change: i32,
+    pub sell_percent: Option<u32>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct TransferItemParams {
+    pub item_id: String,
+    pub quantity: u32,
+}
+
+/// A line of a purchase. `unitPrice` overrides the item's listed value, e.g.
+/// for a haggled or discounted price.
+#[derive(Debug, Clone, InputObject)]
+pub struct BuyItemParams {
+    pub item_id: String,
+    pub quantity: u32,
+    pub unit_price: Option<Money>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum InventoryItemUserErrorCode {
+    UnknownItem,
+    UnknownItemInstance,
+    NoUsesRemaining,
+    NotUsable,
+    InsufficientQuantity,
+    InvalidQuantity,
+    CapacityExceeded,
+    ContainerCapacityExceeded,
+    InsufficientFunds,
+    NotForSale,
+    HandsExceeded,
+    InvestmentLimitExceeded,
+    InvalidCarryState,
+}
+
+/// A problem with a quantity adjustment, usually with one of its lines. When
+/// there is any error nothing in the request is applied. `itemId` is empty
+/// for problems with the request as a whole, such as exceeding capacity.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct InventoryItemUserError {
+    pub item_id: Option<String>,
+    pub code: InventoryItemUserErrorCode,
+    pub message: String,
+}
+
+impl InventoryItemUserError {
+    /// Converts the errors that describe a single requested line; anything
+    /// else is a failure of the whole request and is returned unchanged.
+    pub fn from_app_error(err: AppError) -> Result<Self, AppError> {
+        match err {
+            AppError::NotFound {
+                entity: "Item",
+                ref id,
+            } => Ok(Self {
+                item_id: Some(id.clone()),
+                code: InventoryItemUserErrorCode::UnknownItem,
+                message: err.to_string(),
+            }),
+            AppError::NotFound {
+                entity: "ItemInstance",
+                ref id,
+            } => Ok(Self {
+                item_id: Some(id.clone()),
+                code: InventoryItemUserErrorCode::UnknownItemInstance,
+                message: err.to_string(),
+            }),
+            AppError::InsufficientQuantity { ref item_id, .. } => Ok(Self {
+                item_id: Some(item_id.clone()),
+                code: InventoryItemUserErrorCode::InsufficientQuantity,
+                message: err.to_string(),
+            }),
+            AppError::CapacityExceeded { .. } => Ok(Self {
+                item_id: None,
+                code: InventoryItemUserErrorCode::CapacityExceeded,
+                message: err.to_string(),
+            }),
+            AppError::ContainerCapacityExceeded {
+                ref container_id, ..
+            } => Ok(Self {
+                item_id: Some(container_id.clone()),
+                code: InventoryItemUserErrorCode::ContainerCapacityExceeded,
+                message: err.to_string(),
+            }),
+            AppError::NoUsesRemaining { ref item_id } => Ok(Self {
+                item_id: Some(item_id.clone()),
+                code: InventoryItemUserErrorCode::NoUsesRemaining,
+                message: err.to_string(),
+            }),
+            AppError::InsufficientFunds { .. } => Ok(Self {
+                item_id: None,
+                code: InventoryItemUserErrorCode::InsufficientFunds,
+                message: err.to_string(),
+            }),
+            AppError::HandsExceeded { .. } => Ok(Self {
+                item_id: None,
+                code: InventoryItemUserErrorCode::HandsExceeded,
+                message: err.to_string(),
+            }),
+            AppError::InvestmentLimitExceeded { .. } => Ok(Self {
+                item_id: None,
+                code: InventoryItemUserErrorCode::InvestmentLimitExceeded,
+                message: err.to_string(),
+            }),
+            err => Err(err),
+        }
+    }
+}
+
+/// Reported when a change leaves an inventory over its capacity without
+/// being refused, either because the inventory only warns or because its
+/// capacity was overridden.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct CapacityWarning {
+    pub capacity: u32,
+    pub bulk: u32,
+    pub message: String,
+}
+
+impl CapacityWarning {
+    pub fn for_inventory(inventory: &Inventory) -> Option<Self> {
+        if inventory.capacity_enforcement == CapacityEnforcement::Ignore
+            || !inventory.is_over_capacity()
+        {
+            return None;
+        }
+        Some(Self {
+            capacity: inventory.capacity as u32,
+            bulk: inventory.bulk_carried(),
+            message: format!(
+                "inventory {} carries {} bulk, over its capacity of {}",
+                inventory.uuid.as_str(),
+                inventory.bulk_carried(),
+                inventory.capacity
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct InventoryItemsPayload {
+    /// The adjusted rows after the change; a quantity of zero means the item
+    /// is no longer held.
+    pub inventory_items: Vec<InventoryItem>,
+    pub inventory: Inventory,
+    pub capacity_warning: Option<CapacityWarning>,
+    pub user_errors: Vec<InventoryItemUserError>,
+}
+
+/// What daily preparations changed. Expired rows have a quantity of zero.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct DailyPreparationsPayload {
+    pub recharged: Vec<InventoryItem>,
+    pub expired: Vec<InventoryItem>,
+    pub inventory: Inventory,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct SellItemsPayload {
+    pub inventory_items: Vec<InventoryItem>,
+    pub inventory: Inventory,
+    /// What was credited to the inventory for the sale.
+    pub proceeds: Money,
+    pub user_errors: Vec<InventoryItemUserError>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct TransferItemsPayload {
+    /// The moved rows as left in the source inventory.
+    pub source_items: Vec<InventoryItem>,
+    /// The moved rows as now held by the target inventory.
+    pub target_items: Vec<InventoryItem>,
+    pub source: Inventory,
+    pub target: Inventory,
+    /// Reported for the target inventory.
+    pub capacity_warning: Option<CapacityWarning>,
+    pub user_errors: Vec<InventoryItemUserError>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ReceiptLine {
+    pub item_id: String,
+    pub name: Option<String>,
+    pub quantity: u32,
+    pub unit_price: Money,
+    pub line_total: Money,
+}
+
+/// What was bought and what it cost. The change made from the purse shows in
+/// the payload's inventory.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct PurchaseReceipt {
+    pub lines: Vec<ReceiptLine>,
+    pub total: Money,
+    pub display_total: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct BuyItemsPayload {
+    pub inventory_items: Vec<InventoryItem>,
+    pub inventory: Inventory,
+    /// Present only when the purchase went through.
+    pub receipt: Option<PurchaseReceipt>,
+    pub capacity_warning: Option<CapacityWarning>,
+    pub user_errors: Vec<InventoryItemUserError>,
+}
+
+/// What one line of a sale fetches.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct SaleLine {
+    pub item_id: String,
+    pub name: Option<String>,
+    pub quantity: u32,
+    pub unit_value: Money,
+    /// The percentage of the value paid for this line.
+    pub sell_percent: u32,
+    pub proceeds: Money,
+}
+
+/// A preview of a sale. Nothing is sold; `userErrors` lists what would stop
+/// the sale from going through.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct SaleQuote {
+    pub lines: Vec<SaleLine>,
+    pub total: Money,
+    pub display_total: String,
+    /// The coins the total would be paid in.
+    pub coins: Purse,
+    pub user_errors: Vec<InventoryItemUserError>,
 }
@@ -1,16 +1,153 @@
+use std::collections::HashMap;
+
+use async_graphql::Enum;
 use async_graphql::InputObject;
 use async_graphql::Object;
+use async_graphql::SimpleObject;
 use async_graphql::ID;
 
+use crate::graphql::schemas::{
+    bulk_schema::Bulk,
+    container_schema::{ContentTree, InventoryContent},
+    money_schema::{Money, Purse},
+    owner_schema::OwnerType,
+    shop_schema::ShopSettings,
+};
+
 #[derive(Debug, Clone)]
 pub struct Inventory {
     pub uuid: ID,
     pub name: String,
     pub capacity: u16,
-    pub cp: u32,
-    pub sp: u32,
-    pub gp: u32,
-    pub pp: u32,
+    pub purse: Purse,
+    pub capacity_enforcement: CapacityEnforcement,
+    /// Present when the inventory is a shop.
+    pub shop: Option<ShopSettings>,
+    /// What the inventory's items weigh; filled in by the repository.
+    pub load: InventoryLoad,
+    /// Every stack the inventory holds; filled in by the repository.
+    pub contents: Vec<InventoryContent>,
+}
+
+/// The bulk of an inventory's items, with light items kept apart so they can
+/// be totalled the PF2e way. Negligible items are not counted at all.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InventoryLoad {
+    /// Combined bulk of every item of at least 1 bulk.
+    pub bulk: u32,
+    /// Number of light items; every 10 of them count as 1 bulk.
+    pub light_items: u32,
+}
+
+/// Bulk and quantities come from clients, so the arithmetic saturates: a load
+/// too large to count stays at the maximum, far over any capacity.
+impl InventoryLoad {
+    pub fn add(&mut self, bulk: Bulk, quantity: u32) {
+        match bulk {
+            Bulk::Negligible => {}
+            Bulk::Light => self.light_items = self.light_items.saturating_add(quantity),
+            Bulk::Units(units) => {
+                self.bulk = self.bulk.saturating_add(units.saturating_mul(quantity))
+            }
+        }
+    }
+
+    /// Adds the load of another set of items, such as a container's contents.
+    pub fn absorb(&mut self, other: InventoryLoad) {
+        self.bulk = self.bulk.saturating_add(other.bulk);
+        self.light_items = self.light_items.saturating_add(other.light_items);
+    }
+
+    /// The load left once the first `bulk` of it is ignored, light items
+    /// included.
+    pub fn without_first(&self, bulk: u32) -> InventoryLoad {
+        let tenths = (u64::from(self.bulk) * 10 + u64::from(self.light_items))
+            .saturating_sub(u64::from(bulk) * 10);
+        InventoryLoad {
+            bulk: u32::try_from(tenths / 10).unwrap_or(u32::MAX),
+            light_items: (tenths % 10) as u32,
+        }
+    }
+
+    /// The load in whole bulk, counting every 10 light items as 1 bulk.
+    pub fn total_bulk(&self) -> u32 {
+        self.bulk.saturating_add(self.light_items / 10)
+    }
+}
+
+/// What happens when adding items takes an inventory over its capacity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Enum)]
+pub enum CapacityEnforcement {
+    /// Allow it silently.
+    #[default]
+    Ignore,
+    /// Allow it, but report a capacity warning.
+    Warn,
+    /// Refuse the whole change unless the capacity is overridden.
+    Reject,
+}
+
+impl CapacityEnforcement {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CapacityEnforcement::Ignore => "IGNORE",
+            CapacityEnforcement::Warn => "WARN",
+            CapacityEnforcement::Reject => "REJECT",
+        }
+    }
+
+    /// Reads a stored mode, treating anything unknown as `Ignore`.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "WARN" => CapacityEnforcement::Warn,
+            "REJECT" => CapacityEnforcement::Reject,
+            _ => CapacityEnforcement::Ignore,
+        }
+    }
+}
+
+/// How weighed down the inventory is. Following PF2e, an inventory is
+/// encumbered once it carries more than its capacity minus 5 bulk and
+/// overloaded once it carries more than its capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum EncumbranceStatus {
+    Unencumbered,
+    Encumbered,
+    Overloaded,
+}
+
+impl Inventory {
+    /// Sets the stacks the inventory holds and works out its load from them.
+    pub fn set_contents(&mut self, contents: Vec<InventoryContent>) {
+        self.load = ContentTree::new(&contents).load();
+        self.contents = contents;
+    }
+
+    /// Total bulk carried, counting 10 light items or 1000 coins as 1 bulk.
+    pub fn bulk_carried(&self) -> u32 {
+        self.load
+            .total_bulk()
+            .saturating_add(self.purse.coin_count() / 1000)
+    }
+
+    pub fn bulk_remaining(&self) -> u32 {
+        (self.capacity as u32).saturating_sub(self.bulk_carried())
+    }
+
+    pub fn is_over_capacity(&self) -> bool {
+        self.bulk_carried() > self.capacity as u32
+    }
+
+    pub fn encumbrance_status(&self) -> EncumbranceStatus {
+        let total_bulk = self.bulk_carried();
+        if total_bulk > self.capacity as u32 {
+            EncumbranceStatus::Overloaded
+        } else if total_bulk > (self.capacity as u32).saturating_sub(5) {
+            EncumbranceStatus::Encumbered
+        } else {
+            EncumbranceStatus::Unencumbered
+        }
+    }
 }
 
 #[derive(Debug, Clone, InputObject)]
@@ -21,6 +158,134 @@ pub struct InventoryCurrencyChangeInput {
     pub pp: i32,
 }
 
+/// Divides coins between inventories. The coins come out of the purse of
+/// `sourceInventoryId` when given, otherwise they are new, such as a haul of
+/// loot. `amount` defaults to the source's whole purse.
+#[derive(Debug, Clone, InputObject)]
+pub struct SplitCurrencyInput {
+    pub target_inventory_ids: Vec<String>,
+    pub source_inventory_id: Option<String>,
+    pub amount: Option<Money>,
+}
+
+/// What one inventory received from a split.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct CurrencyShare {
+    pub inventory_id: ID,
+    pub amount: Money,
+    pub coins: Purse,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct CurrencySplitPayload {
+    pub total: Money,
+    /// One share per target, in the order the targets were given.
+    pub shares: Vec<CurrencyShare>,
+    pub source: Option<Inventory>,
+    pub targets: Vec<Inventory>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct CurrencyTransferPayload {
+    pub source: Inventory,
+    pub target: Inventory,
+    /// The coins the target received.
+    pub coins: Purse,
+}
+
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct InventoryQueryFilter {
+    pub search_value: Option<String>,
+    /// Only inventories owned by one of these owner types.
+    pub owner_types: Option<Vec<OwnerType>>,
+}
+
+impl InventoryQueryFilter {
+    pub fn to_cypher_query(&self, base_query: &str) -> (String, HashMap<String, String>) {
+        let mut query_conditions = Vec::<String>::new();
+        let mut params = HashMap::new();
+
+        if let Some(ref search_value) = self.search_value {
+            query_conditions.push("toLower(inv.name) CONTAINS toLower($search_value)".to_string());
+            params.insert("search_value".to_string(), search_value.clone());
+        }
+
+        if let Some(ref owner_types) = self.owner_types {
+            let owned_by = owner_types
+                .iter()
+                .map(|owner_type| format!("(inv)<-[:OWNS]-(:{})", owner_type.label()))
+                .collect::<Vec<_>>();
+            if owned_by.is_empty() {
+                query_conditions.push("false".to_string());
+            } else {
+                query_conditions.push(format!("({})", owned_by.join(" OR ")));
+            }
+        }
+
+        let full_query = if query_conditions.is_empty() {
+            base_query.to_string().replace("<FILTER>", "")
+        } else {
+            base_query.to_string().replace(
+                "<FILTER>",
+                &("WHERE ".to_string() + &query_conditions.join(" AND ")),
+            )
+        };
+
+        (full_query, params)
+    }
+
+    /// Evaluates the filter against an already loaded inventory and the type
+    /// of its owner, mirroring the conditions produced by `to_cypher_query`.
+    pub fn matches(&self, inventory: &Inventory, owner_type: Option<OwnerType>) -> bool {
+        if let Some(ref search_value) = self.search_value {
+            if !inventory
+                .name
+                .to_lowercase()
+                .contains(&search_value.to_lowercase())
+            {
+                return false;
+            }
+        }
+
+        if let Some(ref owner_types) = self.owner_types {
+            if !owner_type.is_some_and(|owner_type| owner_types.contains(&owner_type)) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct CreateInventoryInput {
+    pub name: String,
+    pub capacity: u16,
+    pub capacity_enforcement: Option<CapacityEnforcement>,
+    pub owner_id: Option<String>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct UpdateInventoryInput {
+    pub name: Option<String>,
+    pub capacity: Option<u16>,
+    pub capacity_enforcement: Option<CapacityEnforcement>,
+}
+
+/// What happens to the items and coins of an inventory being deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum InventoryContentsDisposition {
+    Discard,
+    Transfer,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct DeleteInventoryInput {
+    pub contents: InventoryContentsDisposition,
+    /// Required when `contents` is `TRANSFER`.
+    pub transfer_to_inventory_id: Option<String>,
+}
+
 #[Object]
 impl Inventory {
     async fn uuid(&self) -> &str {
@@ -35,19 +300,98 @@ impl Inventory {
         self.capacity
     }
 
+    async fn capacity_enforcement(&self) -> CapacityEnforcement {
+        self.capacity_enforcement
+    }
+
     async fn cp(&self) -> u32 {
-        self.cp
+        self.purse.cp
     }
 
     async fn sp(&self) -> u32 {
-        self.sp
+        self.purse.sp
     }
 
     async fn gp(&self) -> u32 {
-        self.gp
+        self.purse.gp
     }
 
     async fn pp(&self) -> u32 {
-        self.pp
+        self.purse.pp
+    }
+
+    /// The combined worth of every coin held.
+    async fn wealth(&self) -> Money {
+        self.purse.total()
+    }
+
+    /// The coins held, e.g. `1 pp 15 gp 7 cp`.
+    async fn display_purse(&self) -> String {
+        self.purse.to_string()
+    }
+
+    async fn total_bulk(&self) -> u32 {
+        self.bulk_carried()
+    }
+
+    async fn remaining_capacity(&self) -> u32 {
+        self.bulk_remaining()
+    }
+
+    async fn encumbrance(&self) -> EncumbranceStatus {
+        self.encumbrance_status()
+    }
+
+    async fn shop(&self) -> Option<&ShopSettings> {
+        self.shop.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(items: &[(Bulk, u32)]) -> InventoryLoad {
+        let mut load = InventoryLoad::default();
+        for (bulk, quantity) in items {
+            load.add(*bulk, *quantity);
+        }
+        load
+    }
+
+    #[test]
+    fn counts_ten_light_items_as_one_bulk() {
+        let load = load(&[
+            (Bulk::Light, 19),
+            (Bulk::Units(2), 3),
+            (Bulk::Negligible, 50),
+        ]);
+        assert_eq!((load.bulk, load.light_items), (6, 19));
+        assert_eq!(load.total_bulk(), 7);
+    }
+
+    #[test]
+    fn ignores_bulk_light_items_included() {
+        let load = load(&[(Bulk::Units(1), 2), (Bulk::Light, 5)]);
+        let reduced = load.without_first(2);
+        assert_eq!((reduced.bulk, reduced.light_items), (0, 5));
+        assert_eq!(load.without_first(3).total_bulk(), 0);
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        let mut load = load(&[(Bulk::Units(u32::MAX), 2), (Bulk::Light, u32::MAX)]);
+        load.absorb(InventoryLoad {
+            bulk: 1,
+            light_items: 1,
+        });
+        assert_eq!(load.total_bulk(), u32::MAX);
+        assert_eq!(load.without_first(1).bulk, u32::MAX);
+
+        let heavy = InventoryLoad {
+            bulk: u32::MAX,
+            light_items: 0,
+        };
+        assert_eq!(heavy.without_first(1).bulk, u32::MAX - 1);
     }
 }
@@ -1,11 +1,29 @@
-use async_graphql::SimpleObject;
+use async_graphql::{ComplexObject, SimpleObject};
 
 use crate::graphql::schemas::{
-    inventory_item_schema::InventoryItem, inventory_schema::Inventory,
+    inventory_item_schema::InventoryItem,
+    inventory_schema::{EncumbranceStatus, Inventory},
     paginated_response_schema::PaginatedResponse,
 };
 #[derive(SimpleObject)]
+#[graphql(complex)]
 pub struct InventoryWithItems {
     pub inventory: Inventory,
     pub items: PaginatedResponse<InventoryItem>,
 }
+
+/// Load figures cover the whole inventory, not just the returned page of items.
+#[ComplexObject]
+impl InventoryWithItems {
+    async fn total_bulk(&self) -> u32 {
+        self.inventory.bulk_carried()
+    }
+
+    async fn remaining_capacity(&self) -> u32 {
+        self.inventory.bulk_remaining()
+    }
+
+    async fn encumbrance(&self) -> EncumbranceStatus {
+        self.inventory.encumbrance_status()
+    }
+}
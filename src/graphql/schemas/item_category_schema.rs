@@ -0,0 +1,119 @@
+use async_graphql::{Enum, InputObject, OneofObject, SimpleObject, Union};
+
+/// What kind of item something is. Weapons, armor and shields carry a stat
+/// block of their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Enum)]
+pub enum ItemCategory {
+    Weapon,
+    Armor,
+    Shield,
+    Consumable,
+    Worn,
+    Held,
+    Treasure,
+    #[default]
+    Other,
+}
+
+impl ItemCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemCategory::Weapon => "WEAPON",
+            ItemCategory::Armor => "ARMOR",
+            ItemCategory::Shield => "SHIELD",
+            ItemCategory::Consumable => "CONSUMABLE",
+            ItemCategory::Worn => "WORN",
+            ItemCategory::Held => "HELD",
+            ItemCategory::Treasure => "TREASURE",
+            ItemCategory::Other => "OTHER",
+        }
+    }
+
+    /// Reads a stored category, treating anything unknown as `Other`.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "WEAPON" => ItemCategory::Weapon,
+            "ARMOR" => ItemCategory::Armor,
+            "SHIELD" => ItemCategory::Shield,
+            "CONSUMABLE" => ItemCategory::Consumable,
+            "WORN" => ItemCategory::Worn,
+            "HELD" => ItemCategory::Held,
+            "TREASURE" => ItemCategory::Treasure,
+            _ => ItemCategory::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, SimpleObject, InputObject)]
+#[graphql(input_name = "WeaponStatsInput")]
+pub struct WeaponStats {
+    /// The damage dice, e.g. "1d8".
+    pub damage: String,
+    /// e.g. "slashing".
+    pub damage_type: String,
+    /// The weapon group, e.g. "sword".
+    pub group: Option<String>,
+    /// The range increment in feet of ranged weapons.
+    pub range: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SimpleObject, InputObject)]
+#[graphql(input_name = "ArmorStatsInput")]
+pub struct ArmorStats {
+    pub ac_bonus: u32,
+    pub dex_cap: Option<u32>,
+    /// Subtracted from Strength- and Dexterity-based skill checks.
+    #[graphql(default)]
+    pub check_penalty: u32,
+    /// Subtracted from Speed, in feet.
+    #[graphql(default)]
+    pub speed_penalty: u32,
+    /// The Strength modifier that waives the penalties.
+    pub strength: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SimpleObject, InputObject)]
+#[graphql(input_name = "ShieldStatsInput")]
+pub struct ShieldStats {
+    pub ac_bonus: u32,
+    pub hardness: u32,
+    pub hit_points: u32,
+    pub broken_threshold: u32,
+}
+
+/// The stat block of an item, matching its category.
+#[derive(Debug, Clone, PartialEq, Eq, Union)]
+pub enum ItemStats {
+    Weapon(WeaponStats),
+    Armor(ArmorStats),
+    Shield(ShieldStats),
+}
+
+/// Sets the stat block of an item; exactly one may be given.
+#[derive(Debug, Clone, PartialEq, Eq, OneofObject)]
+pub enum ItemStatsInput {
+    Weapon(WeaponStats),
+    Armor(ArmorStats),
+    Shield(ShieldStats),
+}
+
+impl ItemStatsInput {
+    /// The only category these stats belong to.
+    pub fn category(&self) -> ItemCategory {
+        match self {
+            ItemStatsInput::Weapon(_) => ItemCategory::Weapon,
+            ItemStatsInput::Armor(_) => ItemCategory::Armor,
+            ItemStatsInput::Shield(_) => ItemCategory::Shield,
+        }
+    }
+}
+
+impl From<ItemStatsInput> for ItemStats {
+    fn from(stats: ItemStatsInput) -> Self {
+        match stats {
+            ItemStatsInput::Weapon(stats) => ItemStats::Weapon(stats),
+            ItemStatsInput::Armor(stats) => ItemStats::Armor(stats),
+            ItemStatsInput::Shield(stats) => ItemStats::Shield(stats),
+        }
+    }
+}
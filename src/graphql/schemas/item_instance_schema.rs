@@ -0,0 +1,46 @@
+use async_graphql::{InputObject, SimpleObject, ID};
+
+/// One particular copy of a catalog item with state of its own, such as a
+/// named +1 striking longsword or a wand with two charges left. An instance
+/// is held like a stack of one and keeps its own carry state.
+#[derive(Debug, Clone, PartialEq, Eq, SimpleObject)]
+pub struct ItemInstance {
+    pub uuid: ID,
+    /// Shown instead of the catalog name when set.
+    pub custom_name: Option<String>,
+    pub notes: Option<String>,
+    pub charges: Option<u32>,
+    pub hit_points: Option<u32>,
+    /// The runes etched onto this copy, e.g. "+1 Weapon Potency".
+    pub runes: Vec<String>,
+    /// Expires at the next daily preparations, like an infused elixir.
+    pub temporary: bool,
+}
+
+/// The overrides of an instance. Updating an instance replaces all of them,
+/// so fields left out are cleared.
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct ItemInstanceInput {
+    pub custom_name: Option<String>,
+    pub notes: Option<String>,
+    pub charges: Option<u32>,
+    pub hit_points: Option<u32>,
+    #[graphql(default)]
+    pub runes: Vec<String>,
+    #[graphql(default)]
+    pub temporary: bool,
+}
+
+impl ItemInstanceInput {
+    pub fn into_instance(self, uuid: ID) -> ItemInstance {
+        ItemInstance {
+            uuid,
+            custom_name: self.custom_name,
+            notes: self.notes,
+            charges: self.charges,
+            hit_points: self.hit_points,
+            runes: self.runes,
+            temporary: self.temporary,
+        }
+    }
+}
@@ -1,18 +1,70 @@
 use async_graphql::ID;
-use async_graphql::{InputObject, Object};
+use async_graphql::{Enum, InputObject, Object};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, InputObject)]
+use crate::graphql::schemas::{
+    activation_schema::{ActivationCost, ActivationKind},
+    bulk_schema::Bulk,
+    container_schema::ContainerProperties,
+    item_category_schema::{ItemCategory, ItemStats, ItemStatsInput},
+    money_schema::Money,
+};
+
+#[derive(Debug, Clone, Default, InputObject)]
 pub struct ItemProperties {
     pub name: Option<String>,
     pub level: Option<u16>,
     pub traits: Option<Vec<String>>,
-    pub activation_cost: Option<String>,
-    pub bulk: Option<f32>,
+    /// Empty for items that are not activated.
+    pub activation_cost: Option<ActivationCost>,
+    pub bulk: Option<Bulk>,
     pub description: Option<String>,
     pub usage_requirements: Option<String>,
-    pub value: Option<u64>,
+    pub value: Option<Money>,
     pub effect: Option<String>,
+    /// Makes the item a container that other items can be stored in.
+    pub container: Option<ContainerProperties>,
+    /// The charges each instance of the item, such as a wand or staff, is
+    /// restored to at daily preparations.
+    pub charges_per_day: Option<u32>,
+    pub rarity: Option<Rarity>,
+    pub category: Option<ItemCategory>,
+    /// The stat block of a weapon, armor or shield. Giving stats sets the
+    /// category to match; stats of another category than the item's are
+    /// not shown.
+    pub stats: Option<ItemStatsInput>,
+}
+
+/// How hard an item is to come by. Variants are declared from most to least
+/// available, so ordering them sorts by rarity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Enum)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Unique,
+}
+
+impl Rarity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rarity::Common => "COMMON",
+            Rarity::Uncommon => "UNCOMMON",
+            Rarity::Rare => "RARE",
+            Rarity::Unique => "UNIQUE",
+        }
+    }
+
+    /// Reads a stored rarity, treating anything unknown as `Common`.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "UNCOMMON" => Rarity::Uncommon,
+            "RARE" => Rarity::Rare,
+            "UNIQUE" => Rarity::Unique,
+            _ => Rarity::Common,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -23,6 +75,51 @@ pub struct Item {
     pub properties: ItemProperties,
 }
 
+impl Item {
+    pub fn new(uuid: ID, properties: ItemProperties) -> Self {
+        Self {
+            uuid,
+            display_bulk: Self::calc_display_bulk(properties.bulk.unwrap_or_default()),
+            display_value: Self::calc_display_value(properties.value.unwrap_or_default()),
+            properties,
+        }
+    }
+
+    /// Consumables, by category or trait, are used up one at a time when
+    /// used.
+    pub fn has_consumable_trait(&self) -> bool {
+        self.properties.category == Some(ItemCategory::Consumable)
+            || self
+                .properties
+                .traits
+                .as_ref()
+                .is_some_and(|traits| traits.iter().any(|name| name == "Consumable"))
+    }
+
+    /// Treasure such as gems and art objects, by category or trait, sells at
+    /// its full value.
+    pub fn is_treasure(&self) -> bool {
+        self.properties.category == Some(ItemCategory::Treasure)
+            || self.properties.traits.as_ref().is_some_and(|traits| {
+                traits
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case("Treasure"))
+            })
+    }
+
+    fn calc_display_value(value: Money) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    fn calc_display_bulk(bulk: Bulk) -> Option<String> {
+        match bulk {
+            Bulk::Negligible => Some("Negligible".to_string()),
+            Bulk::Light => Some("Light".to_string()),
+            Bulk::Units(units) => Some(format!("{} bulk", units)),
+        }
+    }
+}
+
 #[Object]
 impl Item {
     async fn uuid(&self) -> &ID {
@@ -41,11 +138,11 @@ impl Item {
         self.properties.traits.as_ref()
     }
 
-    async fn activation_cost(&self) -> Option<&String> {
-        self.properties.activation_cost.as_ref()
+    async fn activation_cost(&self) -> Option<ActivationCost> {
+        self.properties.activation_cost
     }
 
-    async fn bulk(&self) -> Option<f32> {
+    async fn bulk(&self) -> Option<Bulk> {
         self.properties.bulk
     }
 
@@ -61,7 +158,7 @@ impl Item {
         self.properties.usage_requirements.as_ref()
     }
 
-    async fn value(&self) -> Option<u64> {
+    async fn value(&self) -> Option<Money> {
         self.properties.value
     }
 
@@ -73,20 +170,42 @@ impl Item {
         self.properties.effect.as_ref()
     }
 
+    async fn container(&self) -> Option<ContainerProperties> {
+        self.properties.container
+    }
+
+    async fn charges_per_day(&self) -> Option<u32> {
+        self.properties.charges_per_day
+    }
+
+    async fn rarity(&self) -> Option<Rarity> {
+        self.properties.rarity
+    }
+
+    async fn category(&self) -> Option<ItemCategory> {
+        self.properties.category
+    }
+
+    async fn stats(&self) -> Option<ItemStats> {
+        self.properties.stats.clone().map(ItemStats::from)
+    }
+
     pub async fn is_consumable(&self) -> bool {
-        if let Some(ref traits) = self.properties.traits {
-            traits.contains(&"Consumable".to_string())
-        } else {
-            false
-        }
+        self.has_consumable_trait()
     }
 }
 
-#[derive(Debug, Clone, InputObject)]
+#[derive(Debug, Clone, Default, InputObject)]
 pub struct ItemQueryFilter {
     pub search_value: Option<String>,
     pub included_traits: Option<Vec<String>>,
     pub excluded_traits: Option<Vec<String>>,
+    /// Only items of one of these rarities, e.g. common and uncommon.
+    pub rarities: Option<Vec<Rarity>>,
+    /// Only items of one of these categories.
+    pub categories: Option<Vec<ItemCategory>>,
+    /// Only items activated in one of these ways.
+    pub activation_kinds: Option<Vec<ActivationKind>>,
 }
 
 impl ItemQueryFilter {
@@ -120,6 +239,36 @@ impl ItemQueryFilter {
             }
         }
 
+        if let Some(ref rarities) = self.rarities {
+            let values = rarities.iter().map(Rarity::as_str);
+            query_conditions.push(one_of_condition(
+                "COALESCE(item.rarity, 'COMMON')",
+                "r",
+                values,
+                &mut params,
+            ));
+        }
+
+        if let Some(ref categories) = self.categories {
+            let values = categories.iter().map(ItemCategory::as_str);
+            query_conditions.push(one_of_condition(
+                "COALESCE(item.category, 'OTHER')",
+                "c",
+                values,
+                &mut params,
+            ));
+        }
+
+        if let Some(ref activation_kinds) = self.activation_kinds {
+            let values = activation_kinds.iter().map(ActivationKind::as_str);
+            query_conditions.push(one_of_condition(
+                "item.activation_kind",
+                "a",
+                values,
+                &mut params,
+            ));
+        }
+
         let full_query = if query_conditions.is_empty() {
             base_query.to_string().replace("<FILTER>", "")
         } else {
@@ -131,4 +280,66 @@ impl ItemQueryFilter {
 
         (full_query, params)
     }
+
+    /// Evaluates the filter against an already loaded item, mirroring the
+    /// conditions produced by `to_cypher_query`.
+    pub fn matches(&self, item: &Item) -> bool {
+        let name = item.properties.name.as_deref().unwrap_or_default();
+        let traits = item.properties.traits.as_deref().unwrap_or_default();
+
+        if let Some(ref search_value) = self.search_value {
+            if !name.to_lowercase().contains(&search_value.to_lowercase()) {
+                return false;
+            }
+        }
+
+        if let Some(ref included_traits) = self.included_traits {
+            if !included_traits.iter().all(|t| traits.contains(t)) {
+                return false;
+            }
+        }
+
+        if let Some(ref excluded_traits) = self.excluded_traits {
+            if excluded_traits.iter().any(|t| traits.contains(t)) {
+                return false;
+            }
+        }
+
+        if let Some(ref rarities) = self.rarities {
+            if !rarities.contains(&item.properties.rarity.unwrap_or_default()) {
+                return false;
+            }
+        }
+
+        if let Some(ref categories) = self.categories {
+            if !categories.contains(&item.properties.category.unwrap_or_default()) {
+                return false;
+            }
+        }
+
+        if let Some(ref activation_kinds) = self.activation_kinds {
+            match item.properties.activation_cost {
+                Some(cost) if activation_kinds.contains(&cost.kind) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// A condition that the expression is one of the values, passed as the
+/// parameters `<prefix>0`, `<prefix>1`, ...
+fn one_of_condition<'a>(
+    expression: &str,
+    prefix: &str,
+    values: impl Iterator<Item = &'a str>,
+    params: &mut HashMap<String, String>,
+) -> String {
+    let mut placeholders = Vec::new();
+    for (idx, value) in values.enumerate() {
+        placeholders.push(format!("${}{}", prefix, idx));
+        params.insert(format!("{}{}", prefix, idx), value.to_string());
+    }
+    format!("{} IN [{}]", expression, placeholders.join(", "))
 }
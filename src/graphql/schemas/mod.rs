@@ -1,6 +1,14 @@
+pub mod activation_schema;
+pub mod bulk_schema;
+pub mod container_schema;
 pub mod inventory_item_schema;
 pub mod inventory_schema;
 pub mod inventory_with_items_schema;
+pub mod item_category_schema;
+pub mod item_instance_schema;
 pub mod item_schema;
+pub mod money_schema;
+pub mod owner_schema;
 pub mod paginated_response_schema;
+pub mod shop_schema;
 pub mod trait_schema;
@@ -0,0 +1,350 @@
+use std::fmt;
+use std::str::FromStr;
+
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, SimpleObject, Value};
+
+use crate::error::{AppError, AppResult};
+
+/// An exact amount of money, held in copper pieces. Exposed as the `Money`
+/// scalar, which is written as a copper integer and read from either a copper
+/// integer or a string of coins such as `"3 gp 5 sp"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(u64);
+
+/// Coin denominations with their worth in copper, largest first.
+pub const DENOMINATIONS: [(&str, u64); 4] = [("pp", 1000), ("gp", 100), ("sp", 10), ("cp", 1)];
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_copper(copper: u64) -> Self {
+        Money(copper)
+    }
+
+    pub fn copper(&self) -> u64 {
+        self.0
+    }
+
+    /// Scales the amount by `numerator / denominator`, rounding down to the
+    /// copper.
+    pub fn scale(self, numerator: u64, denominator: u64) -> Money {
+        Money((self.0 as u128 * numerator as u128 / denominator as u128) as u64)
+    }
+
+    /// Adds two amounts, refusing totals too large to hold. Amounts come
+    /// from clients, so overflowing is a validation error, not a bug.
+    pub fn checked_add(self, other: Money) -> AppResult<Money> {
+        self.0.checked_add(other.0).map(Money).ok_or_else(too_large)
+    }
+
+    /// The price of `quantity` items at this amount each.
+    pub fn checked_mul(self, quantity: u32) -> AppResult<Money> {
+        self.0
+            .checked_mul(u64::from(quantity))
+            .map(Money)
+            .ok_or_else(too_large)
+    }
+
+    /// Totals the amounts, refusing totals too large to hold.
+    pub fn checked_sum(amounts: impl IntoIterator<Item = Money>) -> AppResult<Money> {
+        amounts
+            .into_iter()
+            .try_fold(Money::ZERO, |total, amount| total.checked_add(amount))
+    }
+}
+
+fn too_large() -> AppError {
+    AppError::Validation("the amount of money is too large".to_string())
+}
+
+/// Prices are written the way PF2e prints them: gold, silver and copper,
+/// leaving out empty denominations, e.g. `12 gp 5 sp`.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == 0 {
+            return write!(f, "0 cp");
+        }
+        let gp = self.0 / 100;
+        let sp = self.0 % 100 / 10;
+        let cp = self.0 % 10;
+        let parts: Vec<String> = [(gp, "gp"), (sp, "sp"), (cp, "cp")]
+            .into_iter()
+            .filter(|(amount, _)| *amount > 0)
+            .map(|(amount, denomination)| format!("{} {}", amount, denomination))
+            .collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    /// Reads amounts such as `"3 gp 5 sp"`, `"3gp 5sp"` or `"1,200 gp"`. A
+    /// bare number is taken as copper.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("\"{}\" is not a valid amount of money", value);
+        let normalized = value.replace(',', "").to_lowercase();
+        let mut tokens = Vec::new();
+        for word in normalized.split_whitespace() {
+            // Split "3gp" into "3" and "gp"
+            let digits = word
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(word.len());
+            let (amount, denomination) = word.split_at(digits);
+            tokens.extend([amount, denomination].into_iter().filter(|t| !t.is_empty()));
+        }
+        if tokens.is_empty() {
+            return Err(invalid());
+        }
+        if let [amount] = tokens.as_slice() {
+            return amount.parse().map(Money).map_err(|_| invalid());
+        }
+
+        let mut total: u64 = 0;
+        for pair in tokens.chunks(2) {
+            let [amount, denomination] = pair else {
+                return Err(invalid());
+            };
+            let amount: u64 = amount.parse().map_err(|_| invalid())?;
+            let (_, copper) = DENOMINATIONS
+                .iter()
+                .find(|(name, _)| name == denomination)
+                .ok_or_else(invalid)?;
+            total = amount
+                .checked_mul(*copper)
+                .and_then(|copper| total.checked_add(copper))
+                .ok_or_else(invalid)?;
+        }
+        Ok(Money(total))
+    }
+}
+
+#[Scalar]
+impl ScalarType for Money {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        match &value {
+            Value::Number(number) => number.as_u64().map(Money).ok_or_else(|| {
+                InputValueError::custom(format!("{} is not a valid amount of money", number))
+            }),
+            Value::String(value) => value.parse().map_err(InputValueError::custom),
+            _ => Err(InputValueError::expected_type(value)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Number(self.0.into())
+    }
+}
+
+/// The coins an inventory actually holds. Unlike `Money` the denominations
+/// matter here, both for making change and for the bulk of the coins.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, SimpleObject)]
+pub struct Purse {
+    pub pp: u32,
+    pub gp: u32,
+    pub sp: u32,
+    pub cp: u32,
+}
+
+impl Purse {
+    pub fn total(&self) -> Money {
+        Money(self.pp as u64 * 1000 + self.gp as u64 * 100 + self.sp as u64 * 10 + self.cp as u64)
+    }
+
+    pub fn coin_count(&self) -> u32 {
+        [self.pp, self.gp, self.sp, self.cp]
+            .into_iter()
+            .fold(0, u32::saturating_add)
+    }
+
+    /// The purse left after paying `amount`, failing with insufficient funds
+    /// when the purse is worth less than that. Coins are paid largest first; if that leaves a
+    /// remainder, the smallest coin that covers it is broken and the change
+    /// is returned in the largest lower denominations.
+    pub fn spend(&self, amount: Money) -> AppResult<Purse> {
+        if self.total() < amount {
+            return Err(AppError::InsufficientFunds {
+                required: amount,
+                available: self.total(),
+            });
+        }
+        // Indexed like DENOMINATIONS
+        let mut coins = [self.pp, self.gp, self.sp, self.cp].map(u64::from);
+        let mut owed = amount.0;
+        for (count, (_, value)) in coins.iter_mut().zip(DENOMINATIONS) {
+            let paid = (*count).min(owed / value);
+            *count -= paid;
+            owed -= paid * value;
+        }
+        if owed > 0 {
+            // Every coin still held is worth more than what is owed
+            let broken = (0..DENOMINATIONS.len())
+                .rev()
+                .find(|&index| coins[index] > 0)
+                .expect("a purse worth the amount holds a coin covering the rest");
+            coins[broken] -= 1;
+            let mut change = DENOMINATIONS[broken].1 - owed;
+            for (count, (_, value)) in coins.iter_mut().zip(DENOMINATIONS).skip(broken + 1) {
+                *count += change / value;
+                change %= value;
+            }
+        }
+        let mut counted = [0; 4];
+        for (counted, count) in counted.iter_mut().zip(coins) {
+            *counted = u32::try_from(count).map_err(|_| too_large())?;
+        }
+        let [pp, gp, sp, cp] = counted;
+        Ok(Purse { pp, gp, sp, cp })
+    }
+}
+
+/// Pays an amount in as few coins as possible, refusing amounts needing more
+/// platinum coins than a purse can count.
+impl TryFrom<Money> for Purse {
+    type Error = AppError;
+
+    fn try_from(money: Money) -> AppResult<Self> {
+        let mut remaining = money.0;
+        let mut coins = [0; 4];
+        for (count, (_, value)) in coins.iter_mut().zip(DENOMINATIONS) {
+            *count = u32::try_from(remaining / value).map_err(|_| too_large())?;
+            remaining %= value;
+        }
+        let [pp, gp, sp, cp] = coins;
+        Ok(Purse { pp, gp, sp, cp })
+    }
+}
+
+/// Lists the coins held, largest first, e.g. `1 pp 15 gp 7 cp`.
+impl fmt::Display for Purse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            (self.pp, "pp"),
+            (self.gp, "gp"),
+            (self.sp, "sp"),
+            (self.cp, "cp"),
+        ]
+        .into_iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, denomination)| format!("{} {}", amount, denomination))
+        .collect();
+        if parts.is_empty() {
+            write!(f, "0 cp")
+        } else {
+            write!(f, "{}", parts.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_coin_strings() {
+        assert_eq!("3 gp 5 sp".parse(), Ok(Money(350)));
+        assert_eq!("3gp 5sp".parse(), Ok(Money(350)));
+        assert_eq!("1,200 gp".parse(), Ok(Money(120_000)));
+        assert_eq!("2 PP 7 cp".parse(), Ok(Money(2007)));
+    }
+
+    #[test]
+    fn reads_a_bare_number_as_copper() {
+        assert_eq!("125".parse(), Ok(Money(125)));
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for value in ["", "gp", "3 gp 5", "3 ep", "-3 gp", "3.5 gp"] {
+            assert!(value.parse::<Money>().is_err(), "{:?} parsed", value);
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_that_overflow() {
+        assert!(format!("{} pp", u64::MAX).parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn displays_gold_silver_and_copper() {
+        assert_eq!(Money(0).to_string(), "0 cp");
+        assert_eq!(Money(1205).to_string(), "12 gp 5 cp");
+    }
+
+    fn purse(pp: u32, gp: u32, sp: u32, cp: u32) -> Purse {
+        Purse { pp, gp, sp, cp }
+    }
+
+    #[test]
+    fn spends_exact_coins_largest_first() {
+        let after = purse(1, 3, 5, 5).spend(Money(1205)).unwrap();
+        assert_eq!(after, purse(0, 1, 5, 0));
+    }
+
+    #[test]
+    fn breaks_the_smallest_covering_coin_for_change() {
+        // 7 cp is owed and only gold is held: one gp becomes 9 sp 3 cp
+        let after = purse(0, 2, 0, 0).spend(Money(7)).unwrap();
+        assert_eq!(after, purse(0, 1, 9, 3));
+    }
+
+    #[test]
+    fn makes_change_from_platinum() {
+        let after = purse(1, 0, 0, 0).spend(Money(150)).unwrap();
+        assert_eq!(after, purse(0, 8, 5, 0));
+        assert_eq!(after.total(), Money(850));
+    }
+
+    #[test]
+    fn refuses_to_spend_more_than_the_purse_holds() {
+        assert!(matches!(
+            purse(0, 1, 0, 0).spend(Money(101)),
+            Err(AppError::InsufficientFunds { .. })
+        ));
+        assert_eq!(
+            Purse::default().spend(Money::ZERO).unwrap(),
+            Purse::default()
+        );
+    }
+
+    #[test]
+    fn spends_from_a_purse_near_the_coin_limit() {
+        let full = purse(u32::MAX, 0, 0, u32::MAX);
+        let after = full.spend(Money(1007)).unwrap();
+        assert_eq!(after, purse(u32::MAX - 1, 0, 0, u32::MAX - 7));
+        let after = purse(1, 0, 0, u32::MAX)
+            .spend(Money(u64::from(u32::MAX)))
+            .unwrap();
+        assert_eq!(after, purse(0, 0, 0, 1000));
+    }
+
+    #[test]
+    fn pays_amounts_in_the_fewest_coins() {
+        assert_eq!(Purse::try_from(Money(12_345)).unwrap(), purse(12, 3, 4, 5));
+        assert_eq!(Purse::try_from(Money::ZERO).unwrap(), Purse::default());
+    }
+
+    #[test]
+    fn refuses_amounts_with_too_many_coins_to_count() {
+        let most = Money(u32::MAX as u64 * 1000 + 999);
+        assert_eq!(Purse::try_from(most).unwrap().pp, u32::MAX);
+        assert!(matches!(
+            Purse::try_from(Money(most.0 + 1)),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn checked_arithmetic_refuses_overflow() {
+        assert_eq!(Money(5).checked_mul(3).unwrap(), Money(15));
+        assert_eq!(Money(5).checked_add(Money(3)).unwrap(), Money(8));
+        assert!(matches!(
+            Money(u64::MAX).checked_mul(2),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            Money::checked_sum([Money(u64::MAX), Money(1)]),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
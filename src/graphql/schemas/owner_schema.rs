@@ -0,0 +1,74 @@
+use async_graphql::{Enum, InputObject, Object, ID};
+
+/// The kind of node that owns inventories, stored as the node's label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum OwnerType {
+    Character,
+    Npc,
+    Party,
+}
+
+impl OwnerType {
+    pub const ALL: [OwnerType; 3] = [OwnerType::Character, OwnerType::Npc, OwnerType::Party];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OwnerType::Character => "Character",
+            OwnerType::Npc => "Npc",
+            OwnerType::Party => "Party",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|owner_type| owner_type.label() == label)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Owner {
+    pub uuid: ID,
+    pub name: String,
+    pub owner_type: OwnerType,
+    pub inventory_ids: Vec<ID>,
+    /// The percentage of an item's value this owner pays when buying it from
+    /// a seller, overriding the campaign default.
+    pub sell_percent: Option<u32>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct CreateOwnerInput {
+    pub name: String,
+    pub owner_type: OwnerType,
+    pub sell_percent: Option<u32>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct UpdateOwnerInput {
+    pub name: Option<String>,
+    pub sell_percent: Option<u32>,
+}
+
+#[Object]
+impl Owner {
+    async fn uuid(&self) -> &ID {
+        &self.uuid
+    }
+
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn owner_type(&self) -> OwnerType {
+        self.owner_type
+    }
+
+    async fn inventory_ids(&self) -> &Vec<ID> {
+        &self.inventory_ids
+    }
+
+    async fn sell_percent(&self) -> Option<u32> {
+        self.sell_percent
+    }
+}
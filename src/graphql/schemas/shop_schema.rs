@@ -0,0 +1,66 @@
+use async_graphql::{InputObject, SimpleObject, ID};
+
+use crate::graphql::schemas::{item_schema::Item, money_schema::Money};
+
+/// What makes an inventory a shop. Items sell at `markupPercent` of their
+/// value unless their listing sets a price of its own.
+#[derive(Debug, Clone, PartialEq, Eq, SimpleObject)]
+pub struct ShopSettings {
+    pub markup_percent: u32,
+    /// Items above this level are not sold or restocked.
+    pub max_item_level: Option<u16>,
+    /// The inventory whose contents the shop is restocked from.
+    pub restock_template_id: Option<ID>,
+}
+
+/// A line of a purchase from a shop. Shops always charge their own price,
+/// so unlike `BuyItemParams` there is no price to pass.
+#[derive(Debug, Clone, InputObject)]
+pub struct ShopPurchaseParams {
+    pub item_id: String,
+    pub quantity: u32,
+}
+
+impl ShopSettings {
+    pub fn sells_level(&self, level: u16) -> bool {
+        self.max_item_level
+            .is_none_or(|max_level| level <= max_level)
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct ShopSettingsInput {
+    #[graphql(default = 100)]
+    pub markup_percent: u32,
+    pub max_item_level: Option<u16>,
+    pub restock_template_id: Option<String>,
+}
+
+/// How a shop sells one item. The listing outlives the stock, so a price
+/// still applies once the item has sold out and been restocked.
+#[derive(Debug, Clone, PartialEq, Eq, SimpleObject)]
+pub struct ShopListing {
+    pub item_id: ID,
+    /// Overrides the shop's markup for this item.
+    pub price_percent: Option<u32>,
+    /// The shop never runs out of this item.
+    pub unlimited: bool,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct ShopListingInput {
+    pub item_id: String,
+    pub price_percent: Option<u32>,
+    #[graphql(default)]
+    pub unlimited: bool,
+}
+
+/// An item a shop has for sale and what it costs there.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ShopOffer {
+    pub item: Item,
+    /// How many are in stock; empty when the stock is unlimited.
+    pub quantity: Option<u32>,
+    pub unit_price: Money,
+    pub display_price: String,
+}
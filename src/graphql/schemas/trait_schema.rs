@@ -1,9 +1,41 @@
-use async_graphql::Object;
+use async_graphql::{Enum, InputObject, Object};
 
 #[derive(Debug, Clone)]
 pub struct Trait {
     pub name: String,
     pub description: Option<String>,
+    pub category: Option<TraitCategory>,
+    /// How many catalog items have the trait; worked out when read.
+    pub usage_count: u32,
+}
+
+/// Groups traits that describe the same aspect of an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum TraitCategory {
+    Rarity,
+    DamageType,
+    MagicTradition,
+}
+
+impl TraitCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraitCategory::Rarity => "RARITY",
+            TraitCategory::DamageType => "DAMAGE_TYPE",
+            TraitCategory::MagicTradition => "MAGIC_TRADITION",
+        }
+    }
+
+    /// Reads a stored category; anything unknown leaves the trait
+    /// uncategorized.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "RARITY" => Some(TraitCategory::Rarity),
+            "DAMAGE_TYPE" => Some(TraitCategory::DamageType),
+            "MAGIC_TRADITION" => Some(TraitCategory::MagicTradition),
+            _ => None,
+        }
+    }
 }
 
 #[Object]
@@ -15,4 +47,28 @@ impl Trait {
     async fn description(&self) -> Option<&String> {
         self.description.as_ref()
     }
+
+    async fn category(&self) -> Option<TraitCategory> {
+        self.category
+    }
+
+    async fn usage_count(&self) -> u32 {
+        self.usage_count
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct TraitInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<TraitCategory>,
+}
+
+/// Changes to a trait; fields left out are kept. Renaming keeps the trait on
+/// every item that has it.
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct TraitUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<TraitCategory>,
 }
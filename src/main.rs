@@ -1,12 +1,10 @@
 // use async_graphql::parser::types::DirectiveLocation::Schema;
 
-use async_graphql::EmptySubscription;
-use async_graphql::Schema;
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use axum::routing::post;
 use axum::Router;
 use dotenv::dotenv;
-use graphql::resolvers::root_resolver::MutationRoot;
+use graphql::{build_schema, AppSchema};
 use neo4rs::Graph;
 use std::env;
 use std::sync::Arc;
@@ -17,21 +15,17 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
-use crate::graphql::resolvers::root_resolver::QueryRoot;
-use crate::models::{
-    inventory_item_model::InventoryItemModelManager, inventory_model::InventoryModelManager,
-    inventory_with_items_model::InventoryWithItemsModelManager, item_model::ItemModelManager,
-};
+use crate::models::inventory_item_model::{validate_sell_percent, DEFAULT_SELL_PERCENT};
+use crate::storage::{InMemoryRepository, Neo4jRepository, Repository};
 
 use tower_http::cors::{Any, CorsLayer};
+mod error;
 mod graphql;
 mod models;
+mod storage;
 
 #[instrument(skip(schema, graph_glrequest))]
-async fn graphql_handler(
-    schema: Schema<QueryRoot, MutationRoot, EmptySubscription>,
-    graph_glrequest: GraphQLRequest,
-) -> GraphQLResponse {
+async fn graphql_handler(schema: AppSchema, graph_glrequest: GraphQLRequest) -> GraphQLResponse {
     let inner_request = graph_glrequest.into_inner();
     let res = schema.execute(inner_request).await;
 
@@ -60,42 +54,48 @@ async fn main() {
         .with_span_events(FmtSpan::CLOSE)
         .with_target(is_debug)
         .with_thread_ids(is_debug);
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(formatting_layer)
+        .try_init()
+        .expect("Failed to initialize logging");
 
     let create_db_connection_pool = || async {
         println!("Creating connection pool for Neo4j");
         let uri = env::var("NEO4J_URI").unwrap_or_else(|_| "neo4j://localhost:7687".to_string());
         let user = env::var("NEO4J_USER").unwrap_or_else(|_| "neo4j".to_string());
         let pass = env::var("NEO4J_PASSWORD").unwrap_or_else(|_| "neo4j".to_string());
-        let graph = Graph::new(uri, user, pass).await.unwrap();
-        graph
+        Graph::new(uri, user, pass).await.unwrap()
     };
 
-    let graph = Arc::new(create_db_connection_pool().await);
+    // STORAGE_BACKEND=memory runs against a seeded in-process store instead of Neo4j
+    let repository: Arc<dyn Repository> = match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("memory") => {
+            println!("Using in-memory storage");
+            Arc::new(InMemoryRepository::with_demo_data())
+        }
+        _ => {
+            let repository = Neo4jRepository::new(Arc::new(create_db_connection_pool().await));
+            repository
+                .run_migrations()
+                .await
+                .expect("Failed to migrate the database");
+            Arc::new(repository)
+        }
+    };
 
-    let schema = Schema::build(
-        QueryRoot::new(
-            InventoryModelManager::new(graph.clone()),
-            InventoryItemModelManager::new(graph.clone(), ItemModelManager::new(graph.clone())),
-            InventoryWithItemsModelManager::new(
-                InventoryItemModelManager::new(graph.clone(), ItemModelManager::new(graph.clone())),
-                InventoryModelManager::new(graph.clone()),
-            ),
-            ItemModelManager::new(graph.clone()),
-        ),
-        MutationRoot::new(
-            InventoryItemModelManager::new(graph.clone(), ItemModelManager::new(graph.clone())),
-            ItemModelManager::new(graph.clone()),
-            InventoryModelManager::new(graph),
-        ),
-        EmptySubscription,
-    )
-    .finish();
+    // SELL_PERCENT sets the campaign's share of value paid for sold items
+    let sell_percent = match env::var("SELL_PERCENT") {
+        Ok(percent) => percent
+            .parse()
+            .ok()
+            .and_then(|percent| validate_sell_percent(percent).ok())
+            .expect("SELL_PERCENT must be a whole number between 0 and 100"),
+        Err(_) => DEFAULT_SELL_PERCENT,
+    };
+
+    let schema = build_schema(repository, sell_percent);
 
-    tracing_subscriber::registry()
-        .with(env_filter)
-        .with(formatting_layer)
-        .try_init()
-        .expect("Failed to initialize logging");
     let app = Router::new()
         .route(
             "/graphql",
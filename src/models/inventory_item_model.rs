@@ -1,27 +1,56 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::graphql::schemas::inventory_item_schema::InventoryItemQuantityAdjustmentParams;
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::inventory_item_schema::{
+    BuyItemParams, BuyItemsPayload, CapacityWarning, CarryState, CarryStateParams,
+    DailyPreparationsPayload, InventoryItemQuantityAdjustmentParams, InventoryItemUserError,
+    InventoryItemUserErrorCode, InventoryItemsPayload, PurchaseReceipt, ReceiptLine, SaleLine,
+    SaleQuote, SellItemParams, SellItemsPayload, TransferItemParams, TransferItemsPayload,
+    UseItemParams,
+};
 use crate::graphql::schemas::{
-    inventory_item_schema::InventoryItem, item_schema::ItemQueryFilter,
+    inventory_item_schema::{InventoryItem, ItemLocationFilter},
+    item_instance_schema::ItemInstanceInput,
+    item_schema::ItemQueryFilter,
+    money_schema::{Money, Purse},
     paginated_response_schema::PaginatedResponse,
+    shop_schema::{ShopListing, ShopPurchaseParams},
+};
+use crate::models::shop_model::{shop_price, shop_settings};
+use crate::storage::{
+    CarryStateChange, CurrencyChange, CurrencySpend, InventoryChangeSet, ItemQuantityChange,
+    Repository,
 };
-use crate::models::item_model::ItemModelManager;
-use neo4rs::{query, BoltMap, Graph, Query, Row};
+
+/// The share of an item's value paid when selling it, unless the campaign
+/// configures another.
+pub const DEFAULT_SELL_PERCENT: u32 = 50;
+
+/// A sale checked and priced line by line, ready to apply or quote.
+struct PricedSale {
+    adjustments: Vec<InventoryItemQuantityAdjustmentParams>,
+    lines: Vec<SaleLine>,
+    total: Money,
+    user_errors: Vec<InventoryItemUserError>,
+}
 
 pub struct InventoryItemModelManager {
-    graph: Arc<Graph>,
-    item_model_manager: ItemModelManager,
+    repository: Arc<dyn Repository>,
+    /// The campaign-wide sell percentage, used when neither the line nor the
+    /// merchant sets one.
+    sell_percent: u32,
 }
 
 impl InventoryItemModelManager {
-    pub fn new(graph: Arc<Graph>, item_model_manager: ItemModelManager) -> Self {
+    pub fn new(repository: Arc<dyn Repository>, sell_percent: u32) -> Self {
         Self {
-            graph,
-            item_model_manager,
+            repository,
+            sell_percent,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_inventory_items(
         &self,
         inventory_uuid: String,
@@ -30,304 +59,1177 @@ impl InventoryItemModelManager {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<PaginatedResponse<InventoryItem>> {
-        let skip = page_index * page_size;
-        let (query, params) = filter.to_cypher_query(
-            &"
-                        MATCH(inv:Inventory{uuid: $uuid})
-                        Match(inv)-[c:CONTAINS]->(item:Item)
-                        <FILTER>
-                        OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait)
-                        WITH item,  c, COLLECT(trait.name) as item_traits
-
-                        RETURN
-                        item.uuid as uuid,
-                        c.quantity as quantity,
-                        COALESCE(item.effect, 'No effect') as effect,
-                        COALESCE(item.level, 0) as level,
-                        item.value as value,
-                        toInteger(COALESCE(item.value, '0')) AS numeric_value,
-                        item_traits as traits,
-                        toFloat(COALESCE(item.bulk, 0)) as bulk,
-                        item.name as name,
-                        COALESCE(item.description,  'No description') as description,
-                        COALESCE(item.activation_cost,'n/a') as activation_cost,
-                        COALESCE(item.usage_requirements, 'Not usable') as usage_requirements
-                        ORDER BY <ORDER_FIELD> <ORDER_DIR>, uuid DESC
-                        SKIP $skip LIMIT $limit"
-                .replace("<ORDER_FIELD>", self.map_sort_field(&order_by))
-                .replace(
-                    "<ORDER_DIR>",
-                    if order_direction == "ASC" {
-                        "ASC"
-                    } else {
-                        "DESC"
-                    },
-                ),
-        );
-        print!("{}", query.clone());
-
-        let (count_query, count_params) = filter.to_cypher_query("MATCH(inv:Inventory{uuid: $uuid}) Match(inv)-[c:CONTAINS]->(item:Item) <FILTER> RETURN count(item) as total");
-
-        let mut result = self
-            .graph
-            .execute(
-                neo4rs::query(&query)
-                    .params(params)
-                    .params([("uuid", inventory_uuid.clone()), ("order_by", order_by)])
-                    .params([("skip", skip), ("limit", page_size)]),
-            )
-            .await
-            .unwrap();
-
-        let mut count_result = self
-            .graph
-            .execute(
-                neo4rs::query(&count_query)
-                    .params(count_params)
-                    .param("uuid", inventory_uuid),
-            )
-            .await
-            .unwrap();
-        let mut items = Vec::new();
-        while let Ok(Some(row)) = result.next().await {
-            items.push(self.parse_inventory_item(&row).unwrap());
-        }
-        if let Ok(Some(row)) = count_result.next().await {
-            let total_entities = row.get("total").unwrap();
-            let total_pages = (total_entities as f32 / page_size as f32).ceil() as u32;
-            return Some(PaginatedResponse {
-                entities: items,
+        location: Option<ItemLocationFilter>,
+        equipped_only: bool,
+    ) -> AppResult<PaginatedResponse<InventoryItem>> {
+        self.repository
+            .get_inventory_items(
+                &inventory_uuid,
                 page_index,
                 page_size,
-                total_entities,
-                total_pages,
-            });
-        }
-        None
+                &order_by,
+                &order_direction,
+                &filter,
+                location.as_ref(),
+                equipped_only,
+            )
+            .await
     }
 
     pub async fn add_or_remove_items_from_inventory(
         &self,
         inventory_uuid: String,
         items: Vec<InventoryItemQuantityAdjustmentParams>,
-    ) -> bool {
-        // Create a new session
-        let mut txn = self.graph.start_txn().await.unwrap();
+        override_capacity: bool,
+    ) -> AppResult<InventoryItemsPayload> {
+        let (_, mut user_errors) = self.check_adjustments(&inventory_uuid, &items).await?;
 
-        //for all decrement operations, ensure we have enough quantity to decrement without going negative
-        for item in &items {
-            if item.quantity_change < 0 {
-                let result =
-                    self.graph
-                        .execute(self.get_current_item_quantities_query(
-                            inventory_uuid.clone(),
-                            item.clone(),
-                        ))
-                        .await;
-                if result.is_err() {
-                    txn.rollback().await.unwrap();
-                    return false;
-                }
-                let row = result.unwrap().next().await.unwrap().unwrap();
-                let current_quantity: i32 = row.get("quantity").unwrap();
-                if current_quantity < item.quantity_change.abs() {
-                    txn.rollback().await.unwrap();
-                    return false;
-                }
+        if user_errors.is_empty() {
+            let changes = InventoryChangeSet {
+                item_changes: Self::to_quantity_changes(&inventory_uuid, &items),
+                override_capacity,
+                ..Default::default()
+            };
+            if let Err(err) = self.repository.apply_inventory_changes(changes).await {
+                user_errors.push(InventoryItemUserError::from_app_error(err)?);
             }
         }
 
-        let result = txn
-            .run_queries(
-                items
-                    .into_iter()
-                    .map(|item| self.get_item_adjustment_query(inventory_uuid.clone(), item)),
-            )
-            .await;
+        let inventory = self
+            .repository
+            .get_inventory_by_uuid(&inventory_uuid)
+            .await?;
+        Ok(InventoryItemsPayload {
+            inventory_items: self
+                .adjusted_rows(&inventory_uuid, &items, &user_errors)
+                .await?,
+            capacity_warning: if user_errors.is_empty() {
+                CapacityWarning::for_inventory(&inventory)
+            } else {
+                None
+            },
+            inventory,
+            user_errors,
+        })
+    }
 
-        if result.is_ok() {
-            txn.commit().await.unwrap();
-            true
-        } else {
-            txn.rollback().await.unwrap();
-            false
+    /// Sells the items, crediting each line at the first percentage of its
+    /// value that applies: the line's own, full value for treasure, the
+    /// merchant's, then the campaign's.
+    pub async fn sell_items(
+        &self,
+        inventory_uuid: String,
+        lines: Vec<SellItemParams>,
+        merchant_uuid: Option<String>,
+    ) -> AppResult<SellItemsPayload> {
+        let mut sale = self
+            .price_sale(&inventory_uuid, &lines, merchant_uuid)
+            .await?;
+
+        if sale.user_errors.is_empty() {
+            let changes = InventoryChangeSet {
+                item_changes: Self::to_quantity_changes(&inventory_uuid, &sale.adjustments),
+                currency_changes: vec![CurrencyChange::deposit(
+                    &inventory_uuid,
+                    Purse::try_from(sale.total)?,
+                )],
+                ..Default::default()
+            };
+            if let Err(err) = self.repository.apply_inventory_changes(changes).await {
+                sale.user_errors
+                    .push(InventoryItemUserError::from_app_error(err)?);
+            }
         }
+
+        Ok(SellItemsPayload {
+            inventory_items: self
+                .adjusted_rows(&inventory_uuid, &sale.adjustments, &sale.user_errors)
+                .await?,
+            inventory: self
+                .repository
+                .get_inventory_by_uuid(&inventory_uuid)
+                .await?,
+            proceeds: if sale.user_errors.is_empty() {
+                sale.total
+            } else {
+                Money::ZERO
+            },
+            user_errors: sale.user_errors,
+        })
     }
 
-    pub async fn sell_items(
+    /// Prices a sale exactly as `sell_items` would, without applying it.
+    pub async fn quote_sale(
         &self,
         inventory_uuid: String,
-        items: Vec<InventoryItemQuantityAdjustmentParams>,
-    ) -> bool {
-        // Create lookup of item_id -> quantity_change
-        let item_quantities: HashMap<String, i32> = items
+        lines: Vec<SellItemParams>,
+        merchant_uuid: Option<String>,
+    ) -> AppResult<SaleQuote> {
+        let sale = self
+            .price_sale(&inventory_uuid, &lines, merchant_uuid)
+            .await?;
+        Ok(SaleQuote {
+            lines: sale.lines,
+            total: sale.total,
+            display_total: sale.total.to_string(),
+            coins: Purse::try_from(sale.total)?,
+            user_errors: sale.user_errors,
+        })
+    }
+
+    /// Checks every line of a sale and works out what each one fetches.
+    async fn price_sale(
+        &self,
+        inventory_uuid: &str,
+        lines: &[SellItemParams],
+        merchant_uuid: Option<String>,
+    ) -> AppResult<PricedSale> {
+        for line in lines {
+            if let Some(percent) = line.sell_percent {
+                validate_sell_percent(percent)?;
+            }
+        }
+        let merchant_percent = match merchant_uuid {
+            Some(uuid) => self.repository.get_owner(&uuid).await?.sell_percent,
+            None => None,
+        };
+        let adjustments: Vec<_> = lines
             .iter()
-            .map(|item| (item.item_id.clone(), item.quantity_change))
+            .map(|line| InventoryItemQuantityAdjustmentParams {
+                item_id: line.item_id.clone(),
+                quantity_change: line.quantity_change,
+                container_id: None,
+            })
             .collect();
+        let (rows, mut user_errors) = self.check_adjustments(inventory_uuid, &adjustments).await?;
 
-        // Create a new session
-        let mut txn = self.graph.start_txn().await.unwrap();
+        //if the quantity change is positive, error since we are selling
+        for item in adjustments.iter().filter(|item| item.quantity_change >= 0) {
+            user_errors.push(InventoryItemUserError {
+                item_id: Some(item.item_id.clone()),
+                code: InventoryItemUserErrorCode::InvalidQuantity,
+                message: format!(
+                    "quantity change for item {} must be negative when selling",
+                    item.item_id
+                ),
+            });
+        }
 
-        //for all sell operations, ensure we have enough quantity to decrement without going negative
-        for item in &items {
-            //if the quantity change is positive, error since we are selling
-            if item.quantity_change >= 0 {
-                txn.rollback().await.unwrap();
-                return false;
+        let lines: Vec<SaleLine> = lines
+            .iter()
+            .zip(&rows)
+            .filter_map(|(line, row)| {
+                let row = row.as_ref()?;
+                let sell_percent = line
+                    .sell_percent
+                    .or(row.item.is_treasure().then_some(100))
+                    .or(merchant_percent)
+                    .unwrap_or(self.sell_percent);
+                let quantity = line.quantity_change.unsigned_abs();
+                let unit_value = row.item.properties.value.unwrap_or_default();
+                let proceeds = match unit_value.checked_mul(quantity) {
+                    Ok(value) => value.scale(sell_percent as u64, 100),
+                    Err(err) => return Some(Err(err)),
+                };
+                Some(Ok(SaleLine {
+                    item_id: line.item_id.clone(),
+                    name: row.item.properties.name.clone(),
+                    quantity,
+                    unit_value,
+                    sell_percent,
+                    proceeds,
+                }))
+            })
+            .collect::<AppResult<_>>()?;
+        Ok(PricedSale {
+            adjustments,
+            total: Money::checked_sum(lines.iter().map(|line| line.proceeds))?,
+            lines,
+            user_errors,
+        })
+    }
+
+    /// Buys the items into the inventory, paying `unitPrice` (or the item's
+    /// value) times the quantity from its purse. The items and the payment
+    /// are applied in one change set, so either both happen or neither does.
+    pub async fn buy_items(
+        &self,
+        inventory_uuid: String,
+        items: Vec<BuyItemParams>,
+        override_capacity: bool,
+    ) -> AppResult<BuyItemsPayload> {
+        let (adjustments, mut user_errors) = Self::quantity_adjustments(
+            items
+                .iter()
+                .map(|item| (item.item_id.as_str(), item.quantity)),
+            1,
+        );
+        let (rows, line_errors) = self
+            .check_adjustments(&inventory_uuid, &adjustments)
+            .await?;
+        user_errors.extend(line_errors);
+
+        let lines = Self::receipt_lines(&items, &rows, |item, row| {
+            item.unit_price
+                .or(row.item.properties.value)
+                .unwrap_or_default()
+        })?;
+        let total = Money::checked_sum(lines.iter().map(|line| line.line_total))?;
+        let changes = InventoryChangeSet {
+            item_changes: Self::to_quantity_changes(&inventory_uuid, &adjustments),
+            spends: vec![CurrencySpend {
+                inventory_uuid: inventory_uuid.clone(),
+                amount: total,
+            }],
+            override_capacity,
+            ..Default::default()
+        };
+        self.complete_purchase(&inventory_uuid, &adjustments, lines, changes, user_errors)
+            .await
+    }
+
+    /// Buys items from a shop: the items move from the shop's stock to the
+    /// buyer and the price moves from the buyer's purse to the shop's, all
+    /// in one change set. Items the shop lists as unlimited are never taken
+    /// from its stock, and items above its level cap are not sold.
+    pub async fn buy_from_shop(
+        &self,
+        shop_uuid: String,
+        buyer_uuid: String,
+        items: Vec<ShopPurchaseParams>,
+        override_capacity: bool,
+    ) -> AppResult<BuyItemsPayload> {
+        if shop_uuid == buyer_uuid {
+            return Err(AppError::Validation(
+                "a shop cannot buy from itself".to_string(),
+            ));
+        }
+        let settings = shop_settings(&self.repository.get_inventory_by_uuid(&shop_uuid).await?)?;
+        self.repository.get_inventory_by_uuid(&buyer_uuid).await?;
+        let listings: HashMap<String, ShopListing> = self
+            .repository
+            .get_shop_listings(&shop_uuid)
+            .await?
+            .into_iter()
+            .map(|listing| (listing.item_id.to_string(), listing))
+            .collect();
+
+        let (purchases, mut user_errors) = Self::quantity_adjustments(
+            items
+                .iter()
+                .map(|item| (item.item_id.as_str(), item.quantity)),
+            1,
+        );
+        let withdrawals: Vec<_> = purchases
+            .iter()
+            .map(|purchase| InventoryItemQuantityAdjustmentParams {
+                item_id: purchase.item_id.clone(),
+                quantity_change: match listings.get(&purchase.item_id) {
+                    Some(listing) if listing.unlimited => 0,
+                    _ => -purchase.quantity_change,
+                },
+                container_id: None,
+            })
+            .collect();
+        let (rows, line_errors) = self.check_adjustments(&shop_uuid, &withdrawals).await?;
+        user_errors.extend(line_errors);
+        for row in rows.iter().flatten() {
+            let level = row.item.properties.level.unwrap_or_default();
+            if !settings.sells_level(level) {
+                user_errors.push(InventoryItemUserError {
+                    item_id: Some(row.item.uuid.to_string()),
+                    code: InventoryItemUserErrorCode::NotForSale,
+                    message: format!(
+                        "item {} is level {}, above what shop {} sells",
+                        row.item.uuid.as_str(),
+                        level,
+                        shop_uuid
+                    ),
+                });
             }
-            let result = self
-                .graph
-                .execute(
-                    self.get_current_item_quantities_query(inventory_uuid.clone(), item.clone()),
+        }
+
+        let items: Vec<BuyItemParams> = items
+            .into_iter()
+            .map(|item| BuyItemParams {
+                item_id: item.item_id,
+                quantity: item.quantity,
+                unit_price: None,
+            })
+            .collect();
+        let lines = Self::receipt_lines(&items, &rows, |item, row| {
+            shop_price(&settings, listings.get(&item.item_id), &row.item)
+        })?;
+        let total = Money::checked_sum(lines.iter().map(|line| line.line_total))?;
+        let mut item_changes = Self::to_quantity_changes(
+            &shop_uuid,
+            &withdrawals
+                .into_iter()
+                .filter(|withdrawal| withdrawal.quantity_change != 0)
+                .collect::<Vec<_>>(),
+        );
+        item_changes.extend(Self::to_quantity_changes(&buyer_uuid, &purchases));
+        let changes = InventoryChangeSet {
+            item_changes,
+            currency_changes: vec![CurrencyChange::deposit(&shop_uuid, Purse::try_from(total)?)],
+            spends: vec![CurrencySpend {
+                inventory_uuid: buyer_uuid.clone(),
+                amount: total,
+            }],
+            override_capacity,
+            ..Default::default()
+        };
+        self.complete_purchase(&buyer_uuid, &purchases, lines, changes, user_errors)
+            .await
+    }
+
+    /// Prices every line that passed its checks.
+    fn receipt_lines(
+        items: &[BuyItemParams],
+        rows: &[Option<InventoryItem>],
+        unit_price: impl Fn(&BuyItemParams, &InventoryItem) -> Money,
+    ) -> AppResult<Vec<ReceiptLine>> {
+        items
+            .iter()
+            .zip(rows)
+            .filter_map(|(item, row)| {
+                let row = row.as_ref()?;
+                let unit_price = unit_price(item, row);
+                Some(
+                    unit_price
+                        .checked_mul(item.quantity)
+                        .map(|line_total| ReceiptLine {
+                            item_id: item.item_id.clone(),
+                            name: row.item.properties.name.clone(),
+                            quantity: item.quantity,
+                            unit_price,
+                            line_total,
+                        }),
                 )
-                .await;
-            if result.is_err() {
-                txn.rollback().await.unwrap();
-                return false;
+            })
+            .collect()
+    }
+
+    /// Applies a purchase unless a line was refused, and reports it from the
+    /// buyer's side.
+    async fn complete_purchase(
+        &self,
+        buyer_uuid: &str,
+        purchases: &[InventoryItemQuantityAdjustmentParams],
+        lines: Vec<ReceiptLine>,
+        changes: InventoryChangeSet,
+        mut user_errors: Vec<InventoryItemUserError>,
+    ) -> AppResult<BuyItemsPayload> {
+        if user_errors.is_empty() {
+            if let Err(err) = self.repository.apply_inventory_changes(changes).await {
+                user_errors.push(InventoryItemUserError::from_app_error(err)?);
             }
-            let row = result.unwrap().next().await.unwrap().unwrap();
-            let current_quantity: i32 = row.get("quantity").unwrap();
-            if current_quantity < item.quantity_change.abs() {
-                txn.rollback().await.unwrap();
-                return false;
+        }
+
+        let inventory = self.repository.get_inventory_by_uuid(buyer_uuid).await?;
+        let succeeded = user_errors.is_empty();
+        let total = Money::checked_sum(lines.iter().map(|line| line.line_total))?;
+        Ok(BuyItemsPayload {
+            inventory_items: self
+                .adjusted_rows(buyer_uuid, purchases, &user_errors)
+                .await?,
+            receipt: succeeded.then(|| PurchaseReceipt {
+                lines,
+                total,
+                display_total: total.to_string(),
+            }),
+            capacity_warning: if succeeded {
+                CapacityWarning::for_inventory(&inventory)
+            } else {
+                None
+            },
+            inventory,
+            user_errors,
+        })
+    }
+
+    /// Moves the items from one inventory to another in a single change set,
+    /// so they can never be lost or duplicated part way. The source must
+    /// hold enough of each item and the target's capacity rules apply.
+    pub async fn transfer_items(
+        &self,
+        source_uuid: String,
+        target_uuid: String,
+        items: Vec<TransferItemParams>,
+        override_capacity: bool,
+    ) -> AppResult<TransferItemsPayload> {
+        if source_uuid == target_uuid {
+            return Err(AppError::Validation(
+                "items must be transferred to a different inventory".to_string(),
+            ));
+        }
+        self.repository.get_inventory_by_uuid(&target_uuid).await?;
+
+        let (withdrawals, mut user_errors) = Self::quantity_adjustments(
+            items
+                .iter()
+                .map(|item| (item.item_id.as_str(), item.quantity)),
+            -1,
+        );
+        let (_, line_errors) = self.check_adjustments(&source_uuid, &withdrawals).await?;
+        user_errors.extend(line_errors);
+        let deposits: Vec<_> = withdrawals
+            .iter()
+            .map(|withdrawal| InventoryItemQuantityAdjustmentParams {
+                item_id: withdrawal.item_id.clone(),
+                quantity_change: -withdrawal.quantity_change,
+                container_id: None,
+            })
+            .collect();
+
+        if user_errors.is_empty() {
+            let mut item_changes = Self::to_quantity_changes(&source_uuid, &withdrawals);
+            item_changes.extend(Self::to_quantity_changes(&target_uuid, &deposits));
+            let changes = InventoryChangeSet {
+                item_changes,
+                override_capacity,
+                ..Default::default()
+            };
+            if let Err(err) = self.repository.apply_inventory_changes(changes).await {
+                user_errors.push(InventoryItemUserError::from_app_error(err)?);
             }
         }
 
-        let mut total_value: i64 = 0;
+        let target = self.repository.get_inventory_by_uuid(&target_uuid).await?;
+        Ok(TransferItemsPayload {
+            source_items: self
+                .adjusted_rows(&source_uuid, &withdrawals, &user_errors)
+                .await?,
+            target_items: self
+                .adjusted_rows(&target_uuid, &deposits, &user_errors)
+                .await?,
+            source: self.repository.get_inventory_by_uuid(&source_uuid).await?,
+            capacity_warning: if user_errors.is_empty() {
+                CapacityWarning::for_inventory(&target)
+            } else {
+                None
+            },
+            target,
+            user_errors,
+        })
+    }
 
-        for item in items {
-            let result = txn
-                .execute(self.get_item_adjustment_query(inventory_uuid.clone(), item.clone()))
-                .await;
-            if result.is_err() {
-                let _ = txn.rollback().await;
-                return false;
+    /// Moves items between places within one inventory: loose, or inside a
+    /// container item it holds, as given by `from_container_uuid` and
+    /// `to_container_uuid`. Moving changes what the inventory carries when a
+    /// container ignores some of its contents' bulk.
+    pub async fn move_items(
+        &self,
+        inventory_uuid: String,
+        items: Vec<TransferItemParams>,
+        from_container_uuid: Option<String>,
+        to_container_uuid: Option<String>,
+        override_capacity: bool,
+    ) -> AppResult<InventoryItemsPayload> {
+        if from_container_uuid == to_container_uuid {
+            return Err(AppError::Validation(
+                "items must be moved to a different place".to_string(),
+            ));
+        }
+        let (mut withdrawals, mut user_errors) = Self::quantity_adjustments(
+            items
+                .iter()
+                .map(|item| (item.item_id.as_str(), item.quantity)),
+            -1,
+        );
+        for withdrawal in &mut withdrawals {
+            withdrawal.container_id = from_container_uuid.clone();
+        }
+        let (_, line_errors) = self
+            .check_adjustments(&inventory_uuid, &withdrawals)
+            .await?;
+        user_errors.extend(line_errors);
+        let deposits: Vec<_> = withdrawals
+            .iter()
+            .map(|withdrawal| InventoryItemQuantityAdjustmentParams {
+                item_id: withdrawal.item_id.clone(),
+                quantity_change: -withdrawal.quantity_change,
+                container_id: to_container_uuid.clone(),
+            })
+            .collect();
+        let moves: Vec<_> = withdrawals.into_iter().chain(deposits).collect();
+
+        if user_errors.is_empty() {
+            let changes = InventoryChangeSet {
+                item_changes: Self::to_quantity_changes(&inventory_uuid, &moves),
+                override_capacity,
+                ..Default::default()
+            };
+            if let Err(err) = self.repository.apply_inventory_changes(changes).await {
+                user_errors.push(InventoryItemUserError::from_app_error(err)?);
             }
+        }
 
-            let mut stream = result.unwrap();
-            while let Ok(Some(row)) = stream.next(&mut txn).await {
-                let item = row.get::<BoltMap>("item").unwrap();
-                let id_str: &str = item.get("uuid").unwrap();
-                let value_str: &str = item.get("value").unwrap();
-
-                if let (Ok(value), Some(&quantity_change)) =
-                    (value_str.parse::<i64>(), item_quantities.get(id_str))
-                {
-                    println!("Value: {}", value);
-                    println!("Item ID: {}", id_str);
-                    println!("Quantity change: {}", quantity_change);
-                    total_value += value * quantity_change.abs() as i64;
-                } else {
-                    println!("Failed to parse value: {}", value_str);
-                }
+        let inventory = self
+            .repository
+            .get_inventory_by_uuid(&inventory_uuid)
+            .await?;
+        Ok(InventoryItemsPayload {
+            inventory_items: self
+                .adjusted_rows(&inventory_uuid, &moves, &user_errors)
+                .await?,
+            capacity_warning: if user_errors.is_empty() {
+                CapacityWarning::for_inventory(&inventory)
+            } else {
+                None
+            },
+            inventory,
+            user_errors,
+        })
+    }
+
+    /// Sets how loose stacks and instances are carried, all at once so that
+    /// items can be swapped between hands in one request. Refused when the
+    /// result needs more than two hands or more invested items than the
+    /// limit.
+    pub async fn set_carry_states(
+        &self,
+        inventory_uuid: String,
+        items: Vec<CarryStateParams>,
+    ) -> AppResult<InventoryItemsPayload> {
+        if items.is_empty() {
+            return Err(AppError::Validation(
+                "at least one carry state is required".to_string(),
+            ));
+        }
+        let mut user_errors = Vec::new();
+        for item in &items {
+            match self.carried_row(&inventory_uuid, item).await {
+                Ok(row) if row.container_id.is_some() => user_errors.push(InventoryItemUserError {
+                    item_id: item.instance_id.clone(),
+                    code: InventoryItemUserErrorCode::InvalidCarryState,
+                    message: format!(
+                        "item instance {} must be taken out of its container first",
+                        item.instance_id.as_deref().unwrap_or_default()
+                    ),
+                }),
+                Ok(_) => {}
+                Err(err) => user_errors.push(InventoryItemUserError::from_app_error(err)?),
+            }
+            if item.invested && !item.carry.is_equipped() {
+                user_errors.push(InventoryItemUserError {
+                    item_id: Some(item.item_id.clone()),
+                    code: InventoryItemUserErrorCode::InvalidCarryState,
+                    message: format!("item {} must be worn or held to be invested", item.item_id),
+                });
             }
         }
 
-        println!("Total value of sold items: {} copper pieces", total_value);
+        if user_errors.is_empty() {
+            let changes = InventoryChangeSet {
+                carry_changes: items
+                    .iter()
+                    .map(|item| CarryStateChange {
+                        inventory_uuid: inventory_uuid.clone(),
+                        item_uuid: item.item_id.clone(),
+                        instance_uuid: item.instance_id.clone(),
+                        carry: item.carry,
+                        invested: item.invested,
+                    })
+                    .collect(),
+                ..Default::default()
+            };
+            if let Err(err) = self.repository.apply_inventory_changes(changes).await {
+                user_errors.push(InventoryItemUserError::from_app_error(err)?);
+            }
+        }
 
-        let sell_value = total_value / 2; // 50% of the total value
-        let (pp, gp, sp, cp) = self.calculate_coin_distribution(sell_value);
+        let mut rows = Vec::new();
+        if user_errors.is_empty() {
+            let mut seen = HashSet::new();
+            for item in &items {
+                if seen.insert((item.item_id.as_str(), item.instance_id.as_deref())) {
+                    rows.push(self.carried_row(&inventory_uuid, item).await?);
+                }
+            }
+        }
+        Ok(InventoryItemsPayload {
+            inventory_items: rows,
+            inventory: self
+                .repository
+                .get_inventory_by_uuid(&inventory_uuid)
+                .await?,
+            capacity_warning: None,
+            user_errors,
+        })
+    }
 
-        println!("Sell value: {} pp, {} gp, {} sp, {} cp", pp, gp, sp, cp);
+    /// The row a carry state line changes: the named instance of the item, or
+    /// its loose stack, which must hold at least one.
+    async fn carried_row(
+        &self,
+        inventory_uuid: &str,
+        item: &CarryStateParams,
+    ) -> AppResult<InventoryItem> {
+        let Some(instance_id) = &item.instance_id else {
+            return self
+                .check_adjustment(
+                    inventory_uuid,
+                    &InventoryItemQuantityAdjustmentParams {
+                        item_id: item.item_id.clone(),
+                        quantity_change: -1,
+                        container_id: None,
+                    },
+                )
+                .await;
+        };
+        let row = self
+            .repository
+            .get_item_instance(inventory_uuid, instance_id)
+            .await?;
+        if row.item.uuid.as_str() != item.item_id {
+            return Err(AppError::not_found("ItemInstance", instance_id.clone()));
+        }
+        Ok(row)
+    }
 
-        // TODO: Update the inventory with the new coins
-        let currency_result = txn
-            .execute(
-                self.get_adjust_inventory_currency_query(inventory_uuid.clone(), (pp, gp, sp, cp)),
+    /// Adds a single copy of the item with state of its own, loose or inside
+    /// `container_uuid`. Copies of items with daily charges start with all of
+    /// them unless told otherwise.
+    pub async fn create_item_instance(
+        &self,
+        inventory_uuid: String,
+        item_uuid: String,
+        container_uuid: Option<String>,
+        mut instance: ItemInstanceInput,
+        override_capacity: bool,
+    ) -> AppResult<InventoryItemsPayload> {
+        let mut rows = Vec::new();
+        let mut user_errors = Vec::new();
+        let item = self.repository.get_item(&item_uuid).await?;
+        if instance.charges.is_none() {
+            instance.charges = item.properties.charges_per_day;
+        }
+        let created = self
+            .repository
+            .create_item_instance(
+                &inventory_uuid,
+                &item_uuid,
+                container_uuid.as_deref(),
+                &instance,
+                override_capacity,
             )
             .await;
-        if currency_result.is_err() {
-            _ = txn.rollback().await;
-            return false;
+        match created {
+            Ok(instance_uuid) => rows.push(
+                self.repository
+                    .get_item_instance(&inventory_uuid, &instance_uuid)
+                    .await?,
+            ),
+            Err(err) => user_errors.push(InventoryItemUserError::from_app_error(err)?),
         }
-
-        txn.commit().await.unwrap();
-        return true;
+        self.instance_payload(inventory_uuid, rows, user_errors)
+            .await
     }
 
-    fn get_adjust_inventory_currency_query(
+    /// Replaces every override of the instance.
+    pub async fn update_item_instance(
         &self,
         inventory_uuid: String,
-        currency: (i64, i64, i64, i64),
-    ) -> Query {
-        query(
-            "MATCH (inv:Inventory {uuid: $inventory_uuid})
-            SET inv.pp = inv.pp + $pp, inv.gp = inv.gp + $gp, inv.sp = inv.sp + $sp, inv.cp = inv.cp + $cp",
-        )
-        .param("inventory_uuid", inventory_uuid)
-        .param("pp", currency.0)
-        .param("gp", currency.1)
-        .param("sp", currency.2)
-        .param("cp", currency.3)
-    }
-
-    fn get_current_item_quantities_query(
+        instance_uuid: String,
+        instance: ItemInstanceInput,
+    ) -> AppResult<InventoryItemsPayload> {
+        let mut rows = Vec::new();
+        let mut user_errors = Vec::new();
+        let updated = self
+            .repository
+            .update_item_instance(&inventory_uuid, &instance_uuid, &instance)
+            .await;
+        match updated {
+            Ok(()) => rows.push(
+                self.repository
+                    .get_item_instance(&inventory_uuid, &instance_uuid)
+                    .await?,
+            ),
+            Err(err) => user_errors.push(InventoryItemUserError::from_app_error(err)?),
+        }
+        self.instance_payload(inventory_uuid, rows, user_errors)
+            .await
+    }
+
+    /// Removes the instance, returning its last row with a quantity of zero.
+    pub async fn remove_item_instance(
         &self,
         inventory_uuid: String,
-        item: InventoryItemQuantityAdjustmentParams,
-    ) -> Query {
-        // Execute the query and process the results
-        return query(
-            "MATCH (inv:Inventory {uuid: $inventory_uuid}), (item:Item {uuid: $item_uuid})
-             OPTIONAL MATCH (inv)-[rel:CONTAINS]->(item:Item)
-             RETURN item.uuid AS item_uuid, COALESCE(rel.quantity, 0) AS quantity",
-        )
-        .param("inventory_uuid", inventory_uuid.clone())
-        .param("item_uuid", item.item_id.clone());
-    }
-
-    fn get_item_adjustment_query(
+        instance_uuid: String,
+    ) -> AppResult<InventoryItemsPayload> {
+        let mut rows = Vec::new();
+        let mut user_errors = Vec::new();
+        let removed = match self
+            .repository
+            .get_item_instance(&inventory_uuid, &instance_uuid)
+            .await
+        {
+            Ok(row) => self
+                .repository
+                .remove_item_instance(&inventory_uuid, &instance_uuid)
+                .await
+                .map(|()| row),
+            Err(err) => Err(err),
+        };
+        match removed {
+            Ok(row) => rows.push(InventoryItem { quantity: 0, ..row }),
+            Err(err) => user_errors.push(InventoryItemUserError::from_app_error(err)?),
+        }
+        self.instance_payload(inventory_uuid, rows, user_errors)
+            .await
+    }
+
+    /// Uses one of an item. An instance with charges loses a charge; a
+    /// consumable loses one copy, taking an instance away entirely. Refused
+    /// when no uses remain or the item cannot be used up.
+    pub async fn use_item(
         &self,
         inventory_uuid: String,
-        item: InventoryItemQuantityAdjustmentParams,
-    ) -> Query {
-        //todo: remove this
-        println!(
-            "Debug: quantity_change before query: {}",
-            item.quantity_change
-        );
-        //todo: fix this so quantity change does not comeback as an overflow value
-        return query(
-            "MATCH (inv:Inventory {uuid: $inventory_uuid}), (item:Item {uuid: $item_uuid})
-            MERGE (inv)-[rel:CONTAINS]->(item)
-            ON CREATE SET rel.quantity = $quantity_change
-            ON MATCH SET rel.quantity = rel.quantity + $quantity_change
-            WITH inv, item, rel
-            // Check if the new quantity is 0
-            FOREACH (ignoreMe IN CASE WHEN rel.quantity = 0 THEN [1] ELSE [] END |
-              DELETE rel
+        params: UseItemParams,
+    ) -> AppResult<InventoryItemsPayload> {
+        let mut rows = Vec::new();
+        let mut user_errors = Vec::new();
+        let row = match &params.instance_id {
+            Some(instance_id) => {
+                self.carried_row(
+                    &inventory_uuid,
+                    &CarryStateParams {
+                        item_id: params.item_id.clone(),
+                        instance_id: Some(instance_id.clone()),
+                        carry: CarryState::Stowed,
+                        invested: false,
+                    },
+                )
+                .await
+            }
+            None => {
+                self.repository
+                    .get_inventory_item(
+                        &inventory_uuid,
+                        &params.item_id,
+                        params.container_id.as_deref(),
+                    )
+                    .await
+            }
+        };
+        match row {
+            Ok(row) => match Self::unusable_reason(&row) {
+                Some(message) => user_errors.push(InventoryItemUserError {
+                    item_id: Some(params.item_id.clone()),
+                    code: InventoryItemUserErrorCode::NotUsable,
+                    message,
+                }),
+                None => match self.use_row(&inventory_uuid, &params, row).await {
+                    Ok(row) => rows.push(row),
+                    Err(err) => user_errors.push(InventoryItemUserError::from_app_error(err)?),
+                },
+            },
+            Err(err) => user_errors.push(InventoryItemUserError::from_app_error(err)?),
+        }
+        self.instance_payload(inventory_uuid, rows, user_errors)
+            .await
+    }
+
+    /// Why the row cannot be used up, if it cannot. Charges are only kept on
+    /// instances, so a stack of wands has nothing to spend.
+    fn unusable_reason(row: &InventoryItem) -> Option<String> {
+        let uuid = row.item.uuid.as_str();
+        match &row.instance {
+            Some(instance) if instance.charges.is_some() => None,
+            None if row.item.properties.charges_per_day.is_some() => Some(format!(
+                "charges of item {} are kept per instance; use one of its instances",
+                uuid
+            )),
+            _ if row.item.has_consumable_trait() => None,
+            _ => Some(format!(
+                "item {} is neither consumable nor has charges",
+                uuid
+            )),
+        }
+    }
+
+    async fn use_row(
+        &self,
+        inventory_uuid: &str,
+        params: &UseItemParams,
+        row: InventoryItem,
+    ) -> AppResult<InventoryItem> {
+        if let Some(instance) = &row.instance {
+            let instance_uuid = instance.uuid.to_string();
+            if instance.charges.is_some() {
+                self.repository
+                    .spend_instance_charge(inventory_uuid, &instance_uuid)
+                    .await?;
+                return self
+                    .repository
+                    .get_item_instance(inventory_uuid, &instance_uuid)
+                    .await;
+            }
+            self.repository
+                .remove_item_instance(inventory_uuid, &instance_uuid)
+                .await?;
+            return Ok(InventoryItem { quantity: 0, ..row });
+        }
+        if row.quantity == 0 {
+            return Err(AppError::NoUsesRemaining {
+                item_id: params.item_id.clone(),
+            });
+        }
+        let changes = InventoryChangeSet {
+            item_changes: vec![ItemQuantityChange {
+                inventory_uuid: inventory_uuid.to_string(),
+                item_uuid: params.item_id.clone(),
+                container_uuid: params.container_id.clone(),
+                quantity_change: -1,
+            }],
+            ..Default::default()
+        };
+        self.repository.apply_inventory_changes(changes).await?;
+        self.repository
+            .get_inventory_item(
+                inventory_uuid,
+                &params.item_id,
+                params.container_id.as_deref(),
             )
-            RETURN item, inv.uuid as inventory_uuid",
-        )
-        .param("inventory_uuid", inventory_uuid.clone())
-        .param("item_uuid", item.item_id.clone())
-        .param("quantity_change", item.quantity_change);
+            .await
+    }
+
+    /// Restores the charges of every instance of an item with daily charges
+    /// and removes the temporary instances.
+    pub async fn daily_preparations(
+        &self,
+        inventory_uuid: String,
+    ) -> AppResult<DailyPreparationsPayload> {
+        let instances: Vec<_> = self
+            .repository
+            .list_inventory_items(&inventory_uuid, None)
+            .await?
+            .into_iter()
+            .filter(|row| row.instance.is_some())
+            .collect();
+        self.repository.prepare_instances(&inventory_uuid).await?;
+
+        let mut recharged = Vec::new();
+        let mut expired = Vec::new();
+        for row in instances {
+            let Some(instance) = &row.instance else {
+                continue;
+            };
+            if instance.temporary {
+                expired.push(InventoryItem { quantity: 0, ..row });
+            } else if row.item.properties.charges_per_day.is_some() {
+                let instance_uuid = instance.uuid.to_string();
+                recharged.push(
+                    self.repository
+                        .get_item_instance(&inventory_uuid, &instance_uuid)
+                        .await?,
+                );
+            }
+        }
+        Ok(DailyPreparationsPayload {
+            recharged,
+            expired,
+            inventory: self
+                .repository
+                .get_inventory_by_uuid(&inventory_uuid)
+                .await?,
+        })
     }
 
-    fn parse_inventory_item(&self, row: &Row) -> Option<InventoryItem> {
-        Some(InventoryItem {
-            item: self.item_model_manager.parse_item(row).unwrap(),
-            quantity: row.get("quantity").unwrap(),
+    async fn instance_payload(
+        &self,
+        inventory_uuid: String,
+        inventory_items: Vec<InventoryItem>,
+        user_errors: Vec<InventoryItemUserError>,
+    ) -> AppResult<InventoryItemsPayload> {
+        let inventory = self
+            .repository
+            .get_inventory_by_uuid(&inventory_uuid)
+            .await?;
+        Ok(InventoryItemsPayload {
+            inventory_items,
+            capacity_warning: if user_errors.is_empty() {
+                CapacityWarning::for_inventory(&inventory)
+            } else {
+                None
+            },
+            inventory,
+            user_errors,
         })
     }
 
-    fn map_sort_field(&self, field: &str) -> &str {
-        match field {
-            "quantity" => "quantity",
-            _ => self.item_model_manager.map_sort_field(field), // Default field if input does not match
+    /// Turns requested item counts into quantity adjustments with the given
+    /// sign, reporting each count that is zero or too large as a user error.
+    fn quantity_adjustments<'a>(
+        lines: impl Iterator<Item = (&'a str, u32)>,
+        sign: i32,
+    ) -> (
+        Vec<InventoryItemQuantityAdjustmentParams>,
+        Vec<InventoryItemUserError>,
+    ) {
+        let mut adjustments = Vec::new();
+        let mut user_errors = Vec::new();
+        for (item_id, quantity) in lines {
+            if quantity == 0 || quantity > i32::MAX as u32 {
+                user_errors.push(InventoryItemUserError {
+                    item_id: Some(item_id.to_string()),
+                    code: InventoryItemUserErrorCode::InvalidQuantity,
+                    message: format!(
+                        "quantity of item {} must be between 1 and {}",
+                        item_id,
+                        i32::MAX
+                    ),
+                });
+            }
+            adjustments.push(InventoryItemQuantityAdjustmentParams {
+                item_id: item_id.to_string(),
+                quantity_change: sign * quantity.min(i32::MAX as u32) as i32,
+                container_id: None,
+            });
+        }
+        (adjustments, user_errors)
+    }
+
+    /// Looks up the current row for every requested line, collecting a user
+    /// error for each unknown item or decrement that would go negative. The
+    /// repository repeats the stock check inside its transaction; this pass
+    /// only lets every bad line be reported at once.
+    async fn check_adjustments(
+        &self,
+        inventory_uuid: &str,
+        items: &[InventoryItemQuantityAdjustmentParams],
+    ) -> AppResult<(Vec<Option<InventoryItem>>, Vec<InventoryItemUserError>)> {
+        if items.is_empty() {
+            return Err(AppError::Validation(
+                "at least one item adjustment is required".to_string(),
+            ));
+        }
+        let mut rows = Vec::with_capacity(items.len());
+        let mut user_errors = Vec::new();
+        for item in items {
+            match self.check_adjustment(inventory_uuid, item).await {
+                Ok(row) => rows.push(Some(row)),
+                Err(err) => {
+                    user_errors.push(InventoryItemUserError::from_app_error(err)?);
+                    rows.push(None);
+                }
+            }
+        }
+        Ok((rows, user_errors))
+    }
+
+    async fn check_adjustment(
+        &self,
+        inventory_uuid: &str,
+        item: &InventoryItemQuantityAdjustmentParams,
+    ) -> AppResult<InventoryItem> {
+        let row = self
+            .repository
+            .get_inventory_item(inventory_uuid, &item.item_id, item.container_id.as_deref())
+            .await?;
+        //for all decrement operations, ensure we have enough quantity to decrement without going negative
+        let requested = item.quantity_change.unsigned_abs();
+        if item.quantity_change < 0 && row.quantity < requested {
+            return Err(AppError::InsufficientQuantity {
+                item_id: item.item_id.clone(),
+                available: row.quantity,
+                requested,
+            });
+        }
+        Ok(row)
+    }
+
+    /// Reads back each adjusted item once the change has been applied. Nothing
+    /// is returned when the request was refused.
+    async fn adjusted_rows(
+        &self,
+        inventory_uuid: &str,
+        items: &[InventoryItemQuantityAdjustmentParams],
+        user_errors: &[InventoryItemUserError],
+    ) -> AppResult<Vec<InventoryItem>> {
+        let mut rows = Vec::new();
+        if !user_errors.is_empty() {
+            return Ok(rows);
+        }
+        let mut seen = HashSet::new();
+        for item in items {
+            if seen.insert((item.item_id.as_str(), item.container_id.as_deref())) {
+                rows.push(
+                    self.repository
+                        .get_inventory_item(
+                            inventory_uuid,
+                            &item.item_id,
+                            item.container_id.as_deref(),
+                        )
+                        .await?,
+                );
+            }
         }
+        Ok(rows)
+    }
+
+    fn to_quantity_changes(
+        inventory_uuid: &str,
+        items: &[InventoryItemQuantityAdjustmentParams],
+    ) -> Vec<ItemQuantityChange> {
+        items
+            .iter()
+            .map(|item| ItemQuantityChange {
+                inventory_uuid: inventory_uuid.to_string(),
+                item_uuid: item.item_id.clone(),
+                container_uuid: item.container_id.clone(),
+                quantity_change: item.quantity_change,
+            })
+            .collect()
+    }
+}
+
+/// Refuses a sell percentage above full value.
+pub fn validate_sell_percent(percent: u32) -> AppResult<u32> {
+    if percent > 100 {
+        return Err(AppError::Validation(format!(
+            "sell percent must be between 0 and 100, got {}",
+            percent
+        )));
+    }
+    Ok(percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphql::schemas::{
+        bulk_schema::Bulk, item_category_schema::ItemCategory, item_schema::ItemProperties,
+    };
+    use crate::storage::{
+        memory::InMemoryRepository, InventoryItemRepository, InventoryRepository, ItemRepository,
+    };
+
+    const INVENTORY: &str = "00000000-0000-4000-8000-000000000006";
+    const POTION: &str = "00000000-0000-4000-8000-000000000003";
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_sales_cannot_sell_the_same_stack_twice() {
+        let repository = Arc::new(InMemoryRepository::with_demo_data());
+        let items = Arc::new(InventoryItemModelManager::new(repository.clone(), 50));
+        let sell = || {
+            let items = items.clone();
+            tokio::spawn(async move {
+                let line = SellItemParams {
+                    item_id: POTION.to_string(),
+                    quantity_change: -2,
+                    sell_percent: None,
+                };
+                items
+                    .sell_items(INVENTORY.to_string(), vec![line], None)
+                    .await
+                    .unwrap()
+            })
+        };
+
+        let (first, second) = tokio::join!(sell(), sell());
+        let (first, second) = (first.unwrap(), second.unwrap());
+        let refused: Vec<_> = [&first, &second]
+            .into_iter()
+            .filter(|sale| !sale.user_errors.is_empty())
+            .collect();
+        assert_eq!(refused.len(), 1, "exactly one sale goes through");
+        assert_eq!(
+            refused[0].user_errors[0].code,
+            InventoryItemUserErrorCode::InsufficientQuantity
+        );
+        assert_eq!(refused[0].proceeds, Money::ZERO);
+
+        let left = repository
+            .get_inventory_item(INVENTORY, POTION, None)
+            .await
+            .map_or(0, |row| row.quantity);
+        assert_eq!(left, 0);
+        let purse = repository
+            .get_inventory_by_uuid(INVENTORY)
+            .await
+            .unwrap()
+            .purse;
+        assert_eq!(purse.total().copper(), 1547 + 400);
+    }
+
+    /// Adds two of a new 1 gp item of the given category, without any
+    /// traits, to the inventory.
+    async fn stock(repository: &InMemoryRepository, category: ItemCategory) -> String {
+        let item = repository
+            .create_item(ItemProperties {
+                name: Some("Trinket".to_string()),
+                bulk: Some(Bulk::Light),
+                value: Some(Money::from_copper(100)),
+                category: Some(category),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let item_uuid = item.uuid.to_string();
+        repository
+            .apply_inventory_changes(InventoryChangeSet {
+                item_changes: vec![ItemQuantityChange {
+                    inventory_uuid: INVENTORY.to_string(),
+                    item_uuid: item_uuid.clone(),
+                    container_uuid: None,
+                    quantity_change: 2,
+                }],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        item_uuid
     }
 
-    fn calculate_coin_distribution(&self, value: i64) -> (i64, i64, i64, i64) {
-        let mut remaining = value;
-        let pp = remaining / 1000; // 1 pp = 1000 cp
-        remaining %= 1000;
-        let gp = remaining / 100; // 1 gp = 100 cp
-        remaining %= 100;
-        let sp = remaining / 10; // 1 sp = 10 cp
-        let cp = remaining % 10;
+    #[tokio::test]
+    async fn sells_treasure_by_category_at_full_value() {
+        let repository = Arc::new(InMemoryRepository::with_demo_data());
+        let gem = stock(&repository, ItemCategory::Treasure).await;
+        let items = InventoryItemModelManager::new(repository, 50);
+        let line = SellItemParams {
+            item_id: gem,
+            quantity_change: -1,
+            sell_percent: None,
+        };
+        let sale = items
+            .sell_items(INVENTORY.to_string(), vec![line], None)
+            .await
+            .unwrap();
+        assert!(sale.user_errors.is_empty());
+        assert_eq!(sale.proceeds, Money::from_copper(100));
+    }
 
-        (pp, gp, sp, cp)
+    #[tokio::test]
+    async fn uses_up_consumables_by_category() {
+        let repository = Arc::new(InMemoryRepository::with_demo_data());
+        let elixir = stock(&repository, ItemCategory::Consumable).await;
+        let items = InventoryItemModelManager::new(repository, 50);
+        let params = UseItemParams {
+            item_id: elixir,
+            instance_id: None,
+            container_id: None,
+        };
+        let used = items.use_item(INVENTORY.to_string(), params).await.unwrap();
+        assert!(used.user_errors.is_empty());
+        assert_eq!(used.inventory_items[0].quantity, 1);
+    }
+
+    #[tokio::test]
+    async fn creating_an_instance_of_a_missing_item_fails() {
+        let items =
+            InventoryItemModelManager::new(Arc::new(InMemoryRepository::with_demo_data()), 50);
+        let err = items
+            .create_item_instance(
+                INVENTORY.to_string(),
+                "missing".to_string(),
+                None,
+                ItemInstanceInput::default(),
+                false,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound { .. }));
     }
 }
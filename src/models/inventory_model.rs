@@ -1,124 +1,358 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
+use async_graphql::ID;
+
+use crate::error::{AppError, AppResult};
 use crate::graphql::schemas::{
-    inventory_schema::{Inventory, InventoryCurrencyChangeInput},
+    inventory_schema::{
+        CreateInventoryInput, CurrencyShare, CurrencySplitPayload, CurrencyTransferPayload,
+        DeleteInventoryInput, Inventory, InventoryContentsDisposition,
+        InventoryCurrencyChangeInput, InventoryQueryFilter, SplitCurrencyInput,
+        UpdateInventoryInput,
+    },
+    money_schema::{Money, Purse},
     paginated_response_schema::PaginatedResponse,
 };
-use neo4rs::{query, BoltNode, Graph, Query, Row};
+use crate::models::owner_model::validate_name;
+use crate::storage::{CurrencyChange, CurrencySpend, InventoryChangeSet, Repository};
 
 pub struct InventoryModelManager {
-    graph: Arc<Graph>,
+    repository: Arc<dyn Repository>,
 }
 
 impl InventoryModelManager {
-    pub fn new(graph: Arc<Graph>) -> Self {
-        Self { graph }
-    }
-    pub async fn get_inventory_by_character_uuid(&self, uuid: String) -> Option<Inventory> {
-        let query = "MATCH(char:Character{uuid: $uuid})-[:OWNS]->(inv:Inventory) return (inv)";
-        let parameters = neo4rs::query(query).param("uuid", uuid);
-        let mut result = self.graph.execute(parameters).await.unwrap();
-        if let Ok(Some(row)) = result.next().await {
-            return self.parse_inventory(row);
-        }
-        None
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self { repository }
     }
 
-    pub async fn get_inventories(&self) -> PaginatedResponse<Inventory> {
-        let query = "MATCH(inv:Inventory) return (inv)";
-        let parameters = neo4rs::query(query);
-        let mut result = self.graph.execute(parameters).await.unwrap();
+    pub async fn get_inventory_by_character_uuid(&self, uuid: String) -> AppResult<Inventory> {
+        self.repository.get_inventory_by_character_uuid(&uuid).await
+    }
 
-        let mut inventories = Vec::<Inventory>::new();
-        while let Ok(Some(row)) = result.next().await {
-            inventories.push(self.parse_inventory(row).unwrap());
-        }
+    pub async fn get_inventories(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: String,
+        order_direction: String,
+        filter: InventoryQueryFilter,
+    ) -> AppResult<PaginatedResponse<Inventory>> {
+        self.repository
+            .get_inventories(page_index, page_size, &order_by, &order_direction, &filter)
+            .await
+    }
 
-        let total_entities = inventories.len();
-        let total_pages = 1;
+    pub async fn get_inventory_by_owner_uuid(&self, uuid: String) -> AppResult<Inventory> {
+        self.repository.get_inventory_by_owner_uuid(&uuid).await
+    }
 
-        return PaginatedResponse {
-            entities: inventories,
-            page_index: 0,
-            page_size: total_entities as u32,
-            total_entities: total_entities as u32,
-            total_pages,
-        };
+    pub async fn get_inventory_by_owner_name(&self, name: String) -> AppResult<Inventory> {
+        self.repository.get_inventory_by_owner_name(&name).await
+    }
+
+    pub async fn get_inventory_by_uuid(&self, uuid: String) -> AppResult<Inventory> {
+        self.repository.get_inventory_by_uuid(&uuid).await
+    }
+
+    pub async fn update_inventory_currency(
+        &self,
+        inventory_id: String,
+        params: InventoryCurrencyChangeInput,
+    ) -> AppResult<Inventory> {
+        self.repository
+            .adjust_inventory_currency(CurrencyChange {
+                inventory_uuid: inventory_id,
+                pp: params.pp.into(),
+                gp: params.gp.into(),
+                sp: params.sp.into(),
+                cp: params.cp.into(),
+            })
+            .await
     }
 
-    pub async fn get_inventory_by_owner_uuid(&self, uuid: String) -> Option<Inventory> {
-        let query = "MATCH(char{uuid: $uuid})-[:OWNS]->(inv:Inventory) return (inv)";
-        let parameters = neo4rs::query(query).param("uuid", uuid);
-        let mut result = self.graph.execute(parameters).await.unwrap();
-        if let Ok(Some(row)) = result.next().await {
-            return self.parse_inventory(row);
+    pub async fn spend_inventory_currency(
+        &self,
+        inventory_id: String,
+        amount: Money,
+    ) -> AppResult<Inventory> {
+        if amount == Money::ZERO {
+            return Err(AppError::Validation(
+                "amount to spend must be greater than zero".to_string(),
+            ));
         }
-        None
+        self.repository
+            .apply_inventory_changes(InventoryChangeSet {
+                spends: vec![CurrencySpend {
+                    inventory_uuid: inventory_id.clone(),
+                    amount,
+                }],
+                ..Default::default()
+            })
+            .await?;
+        self.repository.get_inventory_by_uuid(&inventory_id).await
     }
 
-    pub async fn get_inventory_by_owner_name(&self, name: String) -> Option<Inventory> {
-        let query = "MATCH(onwer)-[:OWNS]->(inv:Inventory) WHERE toLower(onwer.name) CONTAINS toLower($name) return (inv)";
-        let parameters = neo4rs::query(query).param("name", name);
-        let mut result = self.graph.execute(parameters).await.unwrap();
-        if let Ok(Some(row)) = result.next().await {
-            return self.parse_inventory(row);
+    /// Moves `amount` from one purse to another. The source makes change as
+    /// needed and the target receives the amount in the fewest coins.
+    pub async fn transfer_currency(
+        &self,
+        source_uuid: String,
+        target_uuid: String,
+        amount: Money,
+    ) -> AppResult<CurrencyTransferPayload> {
+        if amount == Money::ZERO {
+            return Err(AppError::Validation(
+                "amount to transfer must be greater than zero".to_string(),
+            ));
         }
-        None
+        if source_uuid == target_uuid {
+            return Err(AppError::Validation(
+                "currency must be transferred to a different inventory".to_string(),
+            ));
+        }
+        let coins = Purse::try_from(amount)?;
+        self.repository
+            .apply_inventory_changes(InventoryChangeSet {
+                currency_changes: vec![CurrencyChange::deposit(&target_uuid, coins)],
+                spends: vec![CurrencySpend {
+                    inventory_uuid: source_uuid.clone(),
+                    amount,
+                }],
+                ..Default::default()
+            })
+            .await?;
+        Ok(CurrencyTransferPayload {
+            source: self.repository.get_inventory_by_uuid(&source_uuid).await?,
+            target: self.repository.get_inventory_by_uuid(&target_uuid).await?,
+            coins,
+        })
     }
 
-    pub async fn get_inventory_by_uuid(&self, uuid: String) -> Option<Inventory> {
-        let query = "MATCH(inv:Inventory{uuid: $uuid}) return (inv)";
-        let parameters = neo4rs::query(query).param("uuid", uuid);
-        let mut result = self.graph.execute(parameters).await.unwrap();
-        if let Ok(Some(row)) = result.next().await {
-            return self.parse_inventory(row);
+    /// Splits coins evenly between the targets, all in one change set. Each
+    /// share is the amount divided by the number of targets, rounded down to
+    /// the copper; the copper left over goes one piece each to the first
+    /// targets in the order given.
+    pub async fn split_currency(
+        &self,
+        params: SplitCurrencyInput,
+    ) -> AppResult<CurrencySplitPayload> {
+        let targets = params.target_inventory_ids;
+        if targets.is_empty() {
+            return Err(AppError::Validation(
+                "at least one target inventory is required".to_string(),
+            ));
+        }
+        if targets.iter().collect::<HashSet<_>>().len() != targets.len() {
+            return Err(AppError::Validation(
+                "each target inventory may only be given once".to_string(),
+            ));
+        }
+        if params
+            .source_inventory_id
+            .as_ref()
+            .is_some_and(|source| targets.contains(source))
+        {
+            return Err(AppError::Validation(
+                "currency must be split among inventories other than the source".to_string(),
+            ));
+        }
+        let total = match (&params.source_inventory_id, params.amount) {
+            (_, Some(amount)) => amount,
+            (Some(source), None) => self
+                .repository
+                .get_inventory_by_uuid(source)
+                .await?
+                .purse
+                .total(),
+            (None, None) => {
+                return Err(AppError::Validation(
+                    "amount is required when there is no source inventory".to_string(),
+                ))
+            }
+        };
+        if total == Money::ZERO {
+            return Err(AppError::Validation(
+                "there is no currency to split".to_string(),
+            ));
+        }
+
+        let shares: Vec<CurrencyShare> = targets
+            .iter()
+            .zip(split_evenly(total, targets.len()))
+            .map(|(uuid, amount)| {
+                Ok(CurrencyShare {
+                    inventory_id: ID::from(uuid.as_str()),
+                    amount,
+                    coins: Purse::try_from(amount)?,
+                })
+            })
+            .collect::<AppResult<_>>()?;
+        self.repository
+            .apply_inventory_changes(InventoryChangeSet {
+                currency_changes: shares
+                    .iter()
+                    .map(|share| CurrencyChange::deposit(share.inventory_id.as_str(), share.coins))
+                    .collect(),
+                spends: params
+                    .source_inventory_id
+                    .iter()
+                    .map(|source| CurrencySpend {
+                        inventory_uuid: source.clone(),
+                        amount: total,
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .await?;
+
+        let source = match &params.source_inventory_id {
+            Some(source) => Some(self.repository.get_inventory_by_uuid(source).await?),
+            None => None,
+        };
+        let mut inventories = Vec::with_capacity(targets.len());
+        for uuid in &targets {
+            inventories.push(self.repository.get_inventory_by_uuid(uuid).await?);
         }
-        None
+        Ok(CurrencySplitPayload {
+            total,
+            shares,
+            source,
+            targets: inventories,
+        })
     }
 
-    pub async fn update_inventory_currency(
+    pub async fn create_inventory(&self, params: CreateInventoryInput) -> AppResult<Inventory> {
+        let name = validate_name(&params.name)?;
+        self.repository
+            .create_inventory(
+                name,
+                params.capacity,
+                params.capacity_enforcement.unwrap_or_default(),
+                params.owner_id.as_deref(),
+            )
+            .await
+    }
+
+    pub async fn update_inventory(
         &self,
         inventory_id: String,
-        params: InventoryCurrencyChangeInput,
-    ) -> Option<Inventory> {
-        let query = self.get_adjust_inventory_currency_query(
-            inventory_id,
-            (params.pp, params.gp, params.sp, params.cp),
+        params: UpdateInventoryInput,
+    ) -> AppResult<Inventory> {
+        let name = params.name.as_deref().map(validate_name).transpose()?;
+        self.repository
+            .update_inventory(
+                &inventory_id,
+                name,
+                params.capacity,
+                params.capacity_enforcement,
+            )
+            .await
+    }
+
+    pub async fn reassign_inventory(
+        &self,
+        inventory_id: String,
+        owner_id: Option<String>,
+    ) -> AppResult<Inventory> {
+        self.repository
+            .set_inventory_owner(&inventory_id, owner_id.as_deref())
+            .await
+    }
+
+    pub async fn delete_inventory(
+        &self,
+        inventory_id: String,
+        params: DeleteInventoryInput,
+    ) -> AppResult<Inventory> {
+        let transfer_to = match (params.contents, params.transfer_to_inventory_id) {
+            (InventoryContentsDisposition::Discard, None) => None,
+            (InventoryContentsDisposition::Discard, Some(_)) => {
+                return Err(AppError::Validation(
+                    "transferToInventoryId is only allowed when contents are transferred"
+                        .to_string(),
+                ))
+            }
+            (InventoryContentsDisposition::Transfer, None) => {
+                return Err(AppError::Validation(
+                    "transferToInventoryId is required to transfer contents".to_string(),
+                ))
+            }
+            (InventoryContentsDisposition::Transfer, Some(target)) if target == inventory_id => {
+                return Err(AppError::Validation(
+                    "contents cannot be transferred to the inventory being deleted".to_string(),
+                ))
+            }
+            (InventoryContentsDisposition::Transfer, Some(target)) => Some(target),
+        };
+        self.repository
+            .delete_inventory(&inventory_id, transfer_to.as_deref())
+            .await
+    }
+}
+
+/// Divides the amount into `count` shares that differ by at most a copper,
+/// the first shares taking the leftover coppers.
+fn split_evenly(total: Money, count: usize) -> Vec<Money> {
+    let count = count as u64;
+    let (each, remainder) = (total.copper() / count, total.copper() % count);
+    (0..count)
+        .map(|index| Money::from_copper(each + u64::from(index < remainder)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryRepository;
+
+    fn copper(amounts: Vec<Money>) -> Vec<u64> {
+        amounts.iter().map(Money::copper).collect()
+    }
+
+    #[test]
+    fn splits_evenly_when_there_is_no_remainder() {
+        assert_eq!(copper(split_evenly(Money::from_copper(900), 3)), [300; 3]);
+    }
+
+    #[test]
+    fn gives_the_remainder_to_the_first_shares() {
+        assert_eq!(
+            copper(split_evenly(Money::from_copper(1001), 4)),
+            [251, 250, 250, 250]
         );
-        let mut result = self.graph.execute(query).await.unwrap();
-        if let Ok(Some(row)) = result.next().await {
-            return self.parse_inventory(row);
+        assert_eq!(
+            copper(split_evenly(Money::from_copper(1003), 4)),
+            [251, 251, 251, 250]
+        );
+    }
+
+    #[test]
+    fn never_loses_a_copper() {
+        for total in [1, 7, 99, 12_345] {
+            for count in 1..=7 {
+                let shares = split_evenly(Money::from_copper(total), count);
+                assert_eq!(shares.len(), count);
+                assert_eq!(shares.iter().map(Money::copper).sum::<u64>(), total);
+            }
         }
-        None
     }
 
-    fn get_adjust_inventory_currency_query(
-        &self,
-        inventory_uuid: String,
-        currency: (i32, i32, i32, i32),
-    ) -> Query {
-        query(
-            "MATCH (inv:Inventory {uuid: $inventory_uuid})
-            SET inv.pp = inv.pp + $pp, inv.gp = inv.gp + $gp, inv.sp = inv.sp + $sp, inv.cp = inv.cp + $cp
-            RETURN inv",
-        )
-        .param("inventory_uuid", inventory_uuid)
-        .param("pp", currency.0)
-        .param("gp", currency.1)
-        .param("sp", currency.2)
-        .param("cp", currency.3)
-    }
-
-    fn parse_inventory(&self, row: Row) -> Option<Inventory> {
-        let node_properties = row.get::<BoltNode>("inv").unwrap().properties;
-        Some(Inventory {
-            uuid: node_properties.get("uuid").unwrap(),
-            name: node_properties.get("name").unwrap(),
-            capacity: node_properties.get("capacity").unwrap(),
-            cp: node_properties.get("cp").unwrap(),
-            sp: node_properties.get("sp").unwrap(),
-            gp: node_properties.get("gp").unwrap(),
-            pp: node_properties.get("pp").unwrap(),
-        })
+    #[tokio::test]
+    async fn refuses_to_split_into_the_source_inventory() {
+        let inventories =
+            InventoryModelManager::new(Arc::new(InMemoryRepository::with_demo_data()));
+        let pack = "00000000-0000-4000-8000-000000000006".to_string();
+        let err = inventories
+            .split_currency(SplitCurrencyInput {
+                target_inventory_ids: vec![pack.clone()],
+                source_inventory_id: Some(pack.clone()),
+                amount: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+        let purse = inventories.get_inventory_by_uuid(pack).await.unwrap().purse;
+        assert_eq!(purse.total(), Money::from_copper(1547));
     }
 }
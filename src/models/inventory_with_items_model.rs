@@ -1,3 +1,4 @@
+use crate::error::AppResult;
 use crate::graphql::schemas::{
     inventory_with_items_schema::InventoryWithItems, item_schema::ItemQueryFilter,
 };
@@ -30,29 +31,25 @@ impl InventoryWithItemsModelManager {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<InventoryWithItems> {
+    ) -> AppResult<InventoryWithItems> {
         let inventory = self
             .inventory_model_manager
             .get_inventory_by_owner_name(name_term)
-            .await;
-        if inventory.is_none() {
-            return None;
-        }
+            .await?;
         let items = self
             .inventory_items_model_manager
             .get_inventory_items(
-                inventory.as_ref().unwrap().uuid.clone().to_string(),
+                inventory.uuid.to_string(),
                 page_index,
                 page_size,
                 order_by,
                 order_direction,
                 filter,
+                None,
+                false,
             )
-            .await;
-        return Some(InventoryWithItems {
-            inventory: inventory.unwrap(),
-            items: items.unwrap(),
-        });
+            .await?;
+        Ok(InventoryWithItems { inventory, items })
     }
     pub async fn get_inventory_with_items_by_id(
         &self,
@@ -62,28 +59,24 @@ impl InventoryWithItemsModelManager {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<InventoryWithItems> {
+    ) -> AppResult<InventoryWithItems> {
         let inventory = self
             .inventory_model_manager
             .get_inventory_by_uuid(uuid)
-            .await;
-        if inventory.is_none() {
-            return None;
-        }
+            .await?;
         let items = self
             .inventory_items_model_manager
             .get_inventory_items(
-                inventory.as_ref().unwrap().uuid.clone().to_string(),
+                inventory.uuid.to_string(),
                 page_index,
                 page_size,
                 order_by,
                 order_direction,
                 filter,
+                None,
+                false,
             )
-            .await;
-        return Some(InventoryWithItems {
-            inventory: inventory.unwrap(),
-            items: items.unwrap(),
-        });
+            .await?;
+        Ok(InventoryWithItems { inventory, items })
     }
 }
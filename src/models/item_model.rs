@@ -1,32 +1,19 @@
+use crate::error::{AppError, AppResult};
 use crate::graphql::schemas::{
     item_schema::{Item, ItemProperties, ItemQueryFilter},
     paginated_response_schema::PaginatedResponse,
-    trait_schema::Trait,
+    trait_schema::{Trait, TraitCategory, TraitInput, TraitUpdate},
 };
-use async_graphql::ID;
-use neo4rs::{BoltType, Graph, Row};
-use std::collections::HashMap;
+use crate::storage::Repository;
 use std::sync::Arc;
 
-const ITEM_FIELD_PATTERN: &str = "item.uuid as uuid,
-COALESCE(item.effect, 'No effect') as effect,
-COALESCE(item.level, 0) as level,
-item.value as value,
-toInteger(COALESCE(item.value, '0')) AS numeric_value,
-item_traits as traits,
-toFloat(COALESCE(item.bulk, 0)) as bulk,
-item.name as name,
-COALESCE(item.description,  'No description') as description,
-COALESCE(item.activation_cost,'n/a') as activation_cost,
-COALESCE(item.usage_requirements, 'Not usable') as usage_requirements";
-
 pub struct ItemModelManager {
-    graph: Arc<Graph>,
+    repository: Arc<dyn Repository>,
 }
 
 impl ItemModelManager {
-    pub fn new(graph: Arc<Graph>) -> Self {
-        Self { graph }
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self { repository }
     }
 
     pub async fn get_items(
@@ -36,276 +23,200 @@ impl ItemModelManager {
         order_by: String,
         order_direction: String,
         filter: ItemQueryFilter,
-    ) -> Option<PaginatedResponse<Item>> {
-        let skip = page_index * page_size;
-        let (query, params) = filter.to_cypher_query(
-            &"
-                        MATCH (item:Item)
-                        <FILTER>
-                        OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait)
-                        WITH item, COLLECT(trait.name) as item_traits
-                        RETURN
-                       <ITEM_FIELD_PATTERN>
-                        ORDER BY <ORDER_FIELD> <ORDER_DIR>, uuid DESC
-                        SKIP $skip LIMIT $limit"
-                .replace("<ORDER_FIELD>", self.map_sort_field(&order_by))
-                .replace("<ITEM_FIELD_PATTERN>", ITEM_FIELD_PATTERN)
-                .replace(
-                    "<ORDER_DIR>",
-                    if order_direction == "ASC" {
-                        "ASC"
-                    } else {
-                        "DESC"
-                    },
-                ),
-        );
-        print!("{}", query.clone());
-
-        let (count_query, count_params) =
-            filter.to_cypher_query("MATCH (item:Item) <FILTER> RETURN count(item) as total");
-
-        let mut result = self
-            .graph
-            .execute(
-                neo4rs::query(&query)
-                    .params(params)
-                    .params([("order_by", order_by)])
-                    .params([("skip", skip), ("limit", page_size)]),
-            )
+    ) -> AppResult<PaginatedResponse<Item>> {
+        self.repository
+            .get_items(page_index, page_size, &order_by, &order_direction, &filter)
             .await
-            .unwrap();
-
-        let mut count_result = self
-            .graph
-            .execute(neo4rs::query(&count_query).params(count_params))
-            .await
-            .unwrap();
-        let mut items = Vec::new();
-        while let Ok(Some(row)) = result.next().await {
-            items.push(self.parse_item(&row).unwrap());
-        }
-        if let Ok(Some(row)) = count_result.next().await {
-            let total_entities = row.get("total").unwrap();
-            let total_pages = (total_entities as f32 / page_size as f32).ceil() as u32;
-            return Some(PaginatedResponse {
-                entities: items,
-                page_index,
-                page_size,
-                total_entities,
-                total_pages,
-            });
-        }
-        None
     }
 
-    pub async fn get_item(&self, uuid: &str) -> Option<Item> {
-        let query = format!(
-            "MATCH (item:Item {{uuid: '{}'}}) OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait) WITH item, COLLECT(trait.name) as item_traits RETURN {}",
-            uuid, ITEM_FIELD_PATTERN
-        );
-        let mut result = self.graph.execute(neo4rs::query(&query)).await.unwrap();
-        if let Ok(Some(row)) = result.next().await {
-            return self.parse_item(&row);
-        }
-        None
+    pub async fn get_item(&self, uuid: &str) -> AppResult<Item> {
+        self.repository.get_item(uuid).await
     }
 
-    pub async fn get_traits(&self) -> Vec<Trait> {
-        let query =
-            "MATCH (trait:Trait) RETURN trait.name as name, trait.description as description ORDER BY name";
-        let mut result = self.graph.execute(neo4rs::query(&query)).await.unwrap();
-        let mut traits = Vec::new();
-        while let Ok(Some(row)) = result.next().await {
-            traits.push(Trait {
-                name: row.get("name").unwrap(),
-                description: row.get("description").unwrap_or_default(),
-            });
+    /// Every trait, or only those of the category when one is given.
+    pub async fn get_traits(&self, category: Option<TraitCategory>) -> AppResult<Vec<Trait>> {
+        let mut traits = self.repository.get_traits().await?;
+        if category.is_some() {
+            traits.retain(|t| t.category == category);
         }
-        traits
+        Ok(traits)
     }
 
-    pub async fn create_item(&self, properties: ItemProperties) -> Option<Item> {
-        let mut params: HashMap<&str, BoltType> = HashMap::new();
-        params.insert(
-            "name",
-            properties
-                .name
-                .unwrap_or("Unnamed Item".to_string())
-                .to_string()
-                .into(),
-        );
-        params.insert("level", properties.level.unwrap_or_default().into());
-        params.insert(
-            "activation_cost",
-            properties.activation_cost.unwrap_or_default().into(),
-        );
-        params.insert("bulk", properties.bulk.unwrap_or_default().into());
-        params.insert(
-            "description",
-            properties.description.unwrap_or_default().into(),
-        );
-        params.insert(
-            "usage_requirements",
-            properties.usage_requirements.unwrap_or_default().into(),
-        );
-        params.insert(
-            "value",
-            properties.value.unwrap_or_default().to_string().into(),
-        );
-        params.insert("effect", properties.effect.unwrap_or_default().into());
-
-        // Build the Cypher query using parameterized placeholders
-        let query_string = &"CREATE (item:Item {
-            uuid: apoc.create.uuid(),
-            name: $name,
-            level: $level,
-            activation_cost: $activation_cost,
-            bulk: $bulk,
-            description: $description,
-            usage_requirements: $usage_requirements,
-            value: $value,
-            effect: $effect
-        }) RETURN item.uuid as uuid";
+    pub async fn get_trait(&self, name: &str) -> AppResult<Trait> {
+        self.repository.get_trait(name).await
+    }
 
-        // Execute the query with parameters
-        let mut result = self
-            .graph
-            .execute(neo4rs::query(query_string).params(params))
-            .await
-            .unwrap();
+    pub async fn create_trait(&self, mut params: TraitInput) -> AppResult<Trait> {
+        params.name = trait_name(&params.name)?;
+        self.repository.create_trait(&params).await
+    }
 
-        if let Ok(Some(row)) = result.next().await {
-            let uuid: ID = row.get("uuid").unwrap();
-            return self.get_item(&uuid).await;
+    pub async fn update_trait(&self, name: String, mut params: TraitUpdate) -> AppResult<Trait> {
+        if let Some(new_name) = &params.name {
+            params.name = Some(trait_name(new_name)?);
         }
-        return None;
+        self.repository.update_trait(&name, &params).await
     }
 
-    pub async fn update_item(&self, item_uuid: String, properties: ItemProperties) -> Option<Item> {
-        let mut params: HashMap<&str, BoltType> = HashMap::new();
+    pub async fn delete_trait(&self, name: String) -> AppResult<Trait> {
+        self.repository.delete_trait(&name).await
+    }
 
-        if let Some(name) = properties.name {
-            params.insert("name", name.into());
-        }
-        if let Some(level) = properties.level {
-            params.insert("level", level.into());
-        }
-        if let Some(activation_cost) = properties.activation_cost {
-            params.insert("activation_cost", activation_cost.into());
-        }
-        if let Some(bulk) = properties.bulk {
-            params.insert("bulk", bulk.into());
-        }
-        if let Some(description) = properties.description {
-            params.insert("description", description.into());
-        }
-        if let Some(usage_requirements) = properties.usage_requirements {
-            params.insert("usage_requirements", usage_requirements.into());
-        }
-        if let Some(value) = properties.value {
-            params.insert("value", value.to_string().into());
-        }
-        if let Some(effect) = properties.effect {
-            params.insert("effect", effect.into());
+    pub async fn merge_traits(&self, source: String, target: String) -> AppResult<Trait> {
+        if source == target {
+            return Err(AppError::Validation(
+                "a trait cannot be merged into itself".to_string(),
+            ));
         }
+        self.repository.merge_traits(&source, &target).await
+    }
 
-        // Build the SET clause for regular properties
-        let set_clause: String = params
-            .keys()
-            .map(|key| format!("item.{} = ${}", key, key))
-            .collect::<Vec<_>>()
-            .join(", ");
+    /// Creates the item; unless `reject_unknown_traits` is set, traits not
+    /// yet known are created along with it.
+    pub async fn create_item(
+        &self,
+        mut properties: ItemProperties,
+        reject_unknown_traits: bool,
+    ) -> AppResult<Item> {
+        match_stats_category(&mut properties)?;
+        validate_activation_cost(&properties)?;
+        if reject_unknown_traits {
+            self.ensure_known_traits(&properties).await?;
+        }
+        self.repository.create_item(properties).await
+    }
 
-        // Construct the final query string with trait handling
-        let query_string = format!(
-            "MATCH (item:Item {{uuid: $item_uuid}})
-             SET {}
-             WITH item
-             // Remove all existing trait relationships
-             OPTIONAL MATCH (item)-[r:HAS_TRAIT]->(:Trait)
-             DELETE r
-             WITH item
-             // Create new trait relationships
-             UNWIND $traits as trait_name
-             MERGE (t:Trait {{name: trait_name}})
-             MERGE (item)-[:HAS_TRAIT]->(t)
-             RETURN item.uuid as uuid",
-            set_clause
-        );
+    pub async fn update_item(
+        &self,
+        item_uuid: String,
+        mut properties: ItemProperties,
+        reject_unknown_traits: bool,
+    ) -> AppResult<Item> {
+        match_stats_category(&mut properties)?;
+        validate_activation_cost(&properties)?;
+        if reject_unknown_traits {
+            self.ensure_known_traits(&properties).await?;
+        }
+        self.repository.update_item(&item_uuid, properties).await
+    }
 
-        // Insert item UUID and traits into params
-        params.insert("item_uuid", item_uuid.into());
-        if let Some(traits) = properties.traits {
-            params.insert("traits", traits.into());
+    /// Refuses traits that have not been created, such as a misspelt
+    /// "Consumeable".
+    async fn ensure_known_traits(&self, properties: &ItemProperties) -> AppResult<()> {
+        let Some(traits) = &properties.traits else {
+            return Ok(());
+        };
+        let known = self.repository.get_traits().await?;
+        let unknown: Vec<String> = traits
+            .iter()
+            .filter(|name| !known.iter().any(|t| &t.name == *name))
+            .cloned()
+            .collect();
+        if unknown.is_empty() {
+            Ok(())
         } else {
-            params.insert("traits", Vec::<String>::new().into());
+            Err(AppError::UnknownTraits { names: unknown })
         }
+    }
+}
 
-        // Execute the query with parameters
-        let mut result = self
-            .graph
-            .execute(neo4rs::query(&query_string).params(params))
-            .await
-            .unwrap();
+/// Trims a trait name, refusing an empty one.
+fn trait_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("a trait needs a name".to_string()));
+    }
+    Ok(name.to_string())
+}
 
-        if let Ok(Some(row)) = result.next().await {
-            let uuid: ID = row.get("uuid").unwrap();
-            return self.get_item(&uuid).await;
+/// Stats imply the category they belong to; giving a different category
+/// alongside them is refused.
+fn match_stats_category(properties: &mut ItemProperties) -> AppResult<()> {
+    let Some(stats) = &properties.stats else {
+        return Ok(());
+    };
+    let category = stats.category();
+    match properties.category {
+        Some(given) if given != category => Err(AppError::Validation(format!(
+            "{} stats cannot be given to a {} item",
+            category.as_str().to_lowercase(),
+            given.as_str().to_lowercase()
+        ))),
+        _ => {
+            properties.category = Some(category);
+            Ok(())
         }
-        None
     }
+}
 
-    pub fn parse_item(&self, row: &Row) -> Option<Item> {
-        let node_properties = row;
-        let value = Some(
-            node_properties
-                .get("value")
-                .unwrap_or("0")
-                .to_string()
-                .parse::<u64>()
-                .unwrap_or_default(),
-        );
-
-        Some(Item {
-            uuid: node_properties.get("uuid").unwrap(),
-            display_bulk: Self::calc_display_bulk(node_properties.get("bulk").unwrap_or_default()),
-            display_value: Self::calc_display_value(value.unwrap()),
-            properties: ItemProperties {
-                name: node_properties.get("name").unwrap(),
-                value,
-                bulk: node_properties.get("bulk").unwrap_or_default(),
-                description: node_properties.get("description").unwrap_or_default(),
-                effect: node_properties.get("effect").unwrap_or_default(),
-                level: node_properties.get("level").unwrap_or_default(),
-                traits: node_properties.get("traits").unwrap_or_default(),
-                activation_cost: node_properties.get("activation_cost").unwrap_or_default(),
-                usage_requirements: node_properties
-                    .get("usage_requirements")
-                    .unwrap_or_default(),
-            },
-        })
+fn validate_activation_cost(properties: &ItemProperties) -> AppResult<()> {
+    match properties.activation_cost {
+        Some(cost) => cost.validate().map_err(AppError::Validation),
+        None => Ok(()),
     }
+}
 
-    fn calc_display_value(value: u64) -> Option<String> {
-        let gp_value = value as f32 / 100.0;
-        return Some(format!("{} gp", gp_value.to_string()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryRepository;
+
+    fn manager() -> ItemModelManager {
+        ItemModelManager::new(Arc::new(InMemoryRepository::with_demo_data()))
     }
 
-    fn calc_display_bulk(bulk_value: f32) -> Option<String> {
-        match bulk_value {
-            0.0 => return Some("Negligible".to_string()),
-            0.1 => return Some("Light".to_string()),
-            _ => return Some(format!("{} bulk", bulk_value.to_string())),
+    fn potion(traits: &[&str]) -> ItemProperties {
+        ItemProperties {
+            name: Some("Elixir of Life".to_string()),
+            level: Some(5),
+            traits: Some(traits.iter().map(|t| t.to_string()).collect()),
+            ..Default::default()
         }
     }
 
-    pub fn map_sort_field(&self, field: &str) -> &str {
-        match field {
-            "name" => "name",
-            "value" => "numeric_value",
-            "level" => "level",
-            "bulk" => "bulk",
-            _ => "name", // Default field if input does not match
-        }
+    #[tokio::test]
+    async fn rejects_unknown_traits_when_asked() {
+        let items = manager();
+        let err = items
+            .create_item(potion(&["Consumeable", "Potion"]), true)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "UNKNOWN_TRAITS");
+        assert!(matches!(err, AppError::UnknownTraits { names } if names == ["Consumeable"]));
+        assert!(items.get_trait("Consumeable").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn creates_unknown_traits_by_default() {
+        let items = manager();
+        let item = items
+            .create_item(potion(&["Elixir", "Potion"]), false)
+            .await
+            .unwrap();
+        assert_eq!(
+            item.properties.traits.unwrap_or_default(),
+            ["Elixir", "Potion"]
+        );
+        assert_eq!(items.get_trait("Elixir").await.unwrap().usage_count, 1);
+    }
+
+    #[tokio::test]
+    async fn merging_moves_the_items_to_the_target() {
+        let items = manager();
+        let merged = items
+            .merge_traits("Healing".to_string(), "Potion".to_string())
+            .await
+            .unwrap();
+        assert_eq!(merged.usage_count, 1);
+        assert!(items.get_trait("Healing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_merge_a_trait_into_itself() {
+        let err = manager()
+            .merge_traits("Potion".to_string(), "Potion".to_string())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
     }
 }
@@ -2,3 +2,5 @@ pub mod inventory_item_model;
 pub mod inventory_model;
 pub mod inventory_with_items_model;
 pub mod item_model;
+pub mod owner_model;
+pub mod shop_model;
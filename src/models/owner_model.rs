@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::owner_schema::{CreateOwnerInput, Owner, UpdateOwnerInput};
+use crate::models::inventory_item_model::validate_sell_percent;
+use crate::storage::Repository;
+
+pub struct OwnerModelManager {
+    repository: Arc<dyn Repository>,
+}
+
+impl OwnerModelManager {
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn get_owners(&self) -> AppResult<Vec<Owner>> {
+        self.repository.get_owners().await
+    }
+
+    pub async fn get_owner(&self, uuid: String) -> AppResult<Owner> {
+        self.repository.get_owner(&uuid).await
+    }
+
+    pub async fn create_owner(&self, params: CreateOwnerInput) -> AppResult<Owner> {
+        let name = validate_name(&params.name)?;
+        let sell_percent = params.sell_percent.map(validate_sell_percent).transpose()?;
+        self.repository
+            .create_owner(name, params.owner_type, sell_percent)
+            .await
+    }
+
+    pub async fn update_owner(&self, uuid: String, params: UpdateOwnerInput) -> AppResult<Owner> {
+        let name = params.name.as_deref().map(validate_name).transpose()?;
+        let sell_percent = params.sell_percent.map(validate_sell_percent).transpose()?;
+        self.repository
+            .update_owner(&uuid, name, sell_percent)
+            .await
+    }
+
+    pub async fn delete_owner(&self, uuid: String) -> AppResult<Owner> {
+        self.repository.delete_owner(&uuid).await
+    }
+}
+
+/// Trims a user supplied name, refusing one that is left empty.
+pub fn validate_name(name: &str) -> AppResult<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("name must not be empty".to_string()));
+    }
+    Ok(name)
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::ID;
+
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    inventory_item_schema::ItemLocationFilter,
+    inventory_schema::Inventory,
+    item_schema::Item,
+    money_schema::Money,
+    shop_schema::{ShopListing, ShopListingInput, ShopOffer, ShopSettings, ShopSettingsInput},
+};
+use crate::storage::Repository;
+
+pub struct ShopModelManager {
+    repository: Arc<dyn Repository>,
+}
+
+impl ShopModelManager {
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self { repository }
+    }
+
+    /// Everything the shop sells within its level cap, in stock or listed
+    /// as unlimited, sorted by name.
+    pub async fn get_shop_offers(&self, shop_uuid: String) -> AppResult<Vec<ShopOffer>> {
+        let (_, settings) = self.shop(&shop_uuid).await?;
+        let mut listings = self.listings(&shop_uuid).await?;
+        let stock = self
+            .repository
+            .list_inventory_items(&shop_uuid, Some(&ItemLocationFilter::loose()))
+            .await?;
+
+        let mut offers = Vec::new();
+        for row in stock.into_iter().filter(|row| row.instance.is_none()) {
+            let listing = listings.remove(row.item.uuid.as_str());
+            offers.push(Self::offer(&settings, row.item, listing, row.quantity));
+        }
+        for listing in listings.into_values().filter(|listing| listing.unlimited) {
+            let item = self.repository.get_item(listing.item_id.as_str()).await?;
+            offers.push(Self::offer(&settings, item, Some(listing), 0));
+        }
+        offers
+            .retain(|offer| settings.sells_level(offer.item.properties.level.unwrap_or_default()));
+        offers.sort_by(|a, b| a.item.properties.name.cmp(&b.item.properties.name));
+        Ok(offers)
+    }
+
+    pub async fn get_shop_listings(&self, shop_uuid: String) -> AppResult<Vec<ShopListing>> {
+        self.shop(&shop_uuid).await?;
+        self.repository.get_shop_listings(&shop_uuid).await
+    }
+
+    /// Makes the inventory a shop, or updates its settings when it already
+    /// is one. Passing no settings turns it back into an ordinary inventory.
+    pub async fn set_shop_settings(
+        &self,
+        inventory_uuid: String,
+        params: Option<ShopSettingsInput>,
+    ) -> AppResult<Inventory> {
+        let settings = match params {
+            Some(params) => {
+                if let Some(template_uuid) = &params.restock_template_id {
+                    if *template_uuid == inventory_uuid {
+                        return Err(AppError::Validation(
+                            "a shop cannot be restocked from itself".to_string(),
+                        ));
+                    }
+                    self.repository.get_inventory_by_uuid(template_uuid).await?;
+                }
+                Some(ShopSettings {
+                    markup_percent: params.markup_percent,
+                    max_item_level: params.max_item_level,
+                    restock_template_id: params.restock_template_id.map(ID::from),
+                })
+            }
+            None => None,
+        };
+        self.repository
+            .set_shop_settings(&inventory_uuid, settings.as_ref())
+            .await
+    }
+
+    pub async fn set_shop_listing(
+        &self,
+        shop_uuid: String,
+        params: ShopListingInput,
+    ) -> AppResult<ShopListing> {
+        self.shop(&shop_uuid).await?;
+        self.repository.get_item(&params.item_id).await?;
+        let listing = ShopListing {
+            item_id: ID::from(params.item_id),
+            price_percent: params.price_percent,
+            unlimited: params.unlimited,
+        };
+        self.repository
+            .set_shop_listing(&shop_uuid, &listing)
+            .await?;
+        Ok(listing)
+    }
+
+    pub async fn remove_shop_listing(
+        &self,
+        shop_uuid: String,
+        item_uuid: String,
+    ) -> AppResult<Vec<ShopListing>> {
+        self.shop(&shop_uuid).await?;
+        self.repository
+            .remove_shop_listing(&shop_uuid, &item_uuid)
+            .await?;
+        self.repository.get_shop_listings(&shop_uuid).await
+    }
+
+    pub async fn restock_shop(&self, shop_uuid: String) -> AppResult<Inventory> {
+        let (_, settings) = self.shop(&shop_uuid).await?;
+        if settings.restock_template_id.is_none() {
+            return Err(AppError::Validation(format!(
+                "shop {} has no restock template",
+                shop_uuid
+            )));
+        }
+        self.repository.restock_shop(&shop_uuid).await
+    }
+
+    /// Loads an inventory that must be a shop.
+    async fn shop(&self, uuid: &str) -> AppResult<(Inventory, ShopSettings)> {
+        let inventory = self.repository.get_inventory_by_uuid(uuid).await?;
+        let settings = shop_settings(&inventory)?;
+        Ok((inventory, settings))
+    }
+
+    async fn listings(&self, shop_uuid: &str) -> AppResult<HashMap<String, ShopListing>> {
+        Ok(self
+            .repository
+            .get_shop_listings(shop_uuid)
+            .await?
+            .into_iter()
+            .map(|listing| (listing.item_id.to_string(), listing))
+            .collect())
+    }
+
+    fn offer(
+        settings: &ShopSettings,
+        item: Item,
+        listing: Option<ShopListing>,
+        quantity: u32,
+    ) -> ShopOffer {
+        let unit_price = shop_price(settings, listing.as_ref(), &item);
+        ShopOffer {
+            quantity: match listing {
+                Some(listing) if listing.unlimited => None,
+                _ => Some(quantity),
+            },
+            display_price: unit_price.to_string(),
+            unit_price,
+            item,
+        }
+    }
+}
+
+/// The settings of an inventory that must be a shop.
+pub fn shop_settings(inventory: &Inventory) -> AppResult<ShopSettings> {
+    inventory.shop.clone().ok_or_else(|| {
+        AppError::Validation(format!(
+            "inventory {} is not a shop",
+            inventory.uuid.as_str()
+        ))
+    })
+}
+
+/// What a shop charges for one of the item: its value at the listing's
+/// price percentage, or at the shop's markup when the listing sets none.
+pub fn shop_price(settings: &ShopSettings, listing: Option<&ShopListing>, item: &Item) -> Money {
+    let percent = listing
+        .and_then(|listing| listing.price_percent)
+        .unwrap_or(settings.markup_percent);
+    item.properties
+        .value
+        .unwrap_or_default()
+        .scale(percent as u64, 100)
+}
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+
+use super::{InMemoryRepository, InMemoryState};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::inventory_schema::Inventory;
+use crate::storage::{CurrencyChange, CurrencyRepository};
+
+impl InMemoryState {
+    /// Adds the change to the inventory's purse, failing if the inventory is
+    /// missing or a denomination would drop below zero.
+    pub(super) fn adjust_currency(&mut self, change: &CurrencyChange) -> AppResult<Inventory> {
+        let inventory = self
+            .inventories
+            .get_mut(&change.inventory_uuid)
+            .ok_or_else(|| AppError::not_found("Inventory", change.inventory_uuid.clone()))?;
+        change.apply_to(inventory)?;
+        self.inventory(&change.inventory_uuid)
+    }
+}
+
+#[async_trait]
+impl CurrencyRepository for InMemoryRepository {
+    async fn adjust_inventory_currency(&self, change: CurrencyChange) -> AppResult<Inventory> {
+        self.write(|state| state.adjust_currency(&change))
+    }
+}
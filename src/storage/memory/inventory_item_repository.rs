@@ -0,0 +1,262 @@
+use std::cmp::Ordering;
+
+use async_graphql::ID;
+use async_trait::async_trait;
+
+use super::{
+    item_repository::compare_items, paginate, sort_direction, InMemoryRepository, InMemoryState,
+    Stack,
+};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    inventory_item_schema::{CarryState, InventoryItem, ItemLocationFilter},
+    item_schema::ItemQueryFilter,
+    paginated_response_schema::PaginatedResponse,
+};
+use crate::storage::{
+    ensure_capacity, ensure_carry_limits, ensure_containers, InventoryChangeSet,
+    InventoryItemRepository,
+};
+
+/// Orders rows that sort alike the way Neo4j does, by item uuid and then
+/// instance uuid, both descending. Neo4j orders null above every value, so
+/// stacks come before the instances of the same item.
+fn tiebreak(a: &InventoryItem, b: &InventoryItem) -> Ordering {
+    let instance_uuid = |row: &InventoryItem| row.instance.as_ref().map(|i| i.uuid.to_string());
+    b.item
+        .uuid
+        .cmp(&a.item.uuid)
+        .then_with(|| match (instance_uuid(a), instance_uuid(b)) {
+            (Some(a), Some(b)) => b.cmp(&a),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        })
+}
+
+impl InMemoryState {
+    /// The rows of every stack and instance the inventory holds in
+    /// `location`, or anywhere when not given, in no particular order.
+    fn inventory_rows<'a>(
+        &'a self,
+        inventory_uuid: &'a str,
+        location: Option<&'a ItemLocationFilter>,
+        equipped_only: bool,
+    ) -> impl Iterator<Item = InventoryItem> + 'a {
+        let stacks = self
+            .contents
+            .iter()
+            .filter(move |((inventory, _, _), _)| inventory == inventory_uuid)
+            .filter(move |((_, _, container_uuid), _)| {
+                location.is_none_or(|location| location.matches(container_uuid.as_deref()))
+            })
+            .filter(move |(_, stack)| !equipped_only || stack.carry.is_equipped())
+            .filter_map(|((_, item_uuid, container_uuid), stack)| {
+                Some(InventoryItem {
+                    item: self.item(item_uuid).ok()?,
+                    quantity: stack.quantity,
+                    container_id: container_uuid.clone().map(ID::from),
+                    carry: stack.carry,
+                    invested: stack.invested,
+                    instance: None,
+                })
+            });
+        let instances = self
+            .instances
+            .values()
+            .filter(move |held| held.inventory_uuid == inventory_uuid)
+            .filter(move |held| {
+                location.is_none_or(|location| location.matches(held.container_uuid.as_deref()))
+            })
+            .filter(move |held| !equipped_only || held.carry.is_equipped())
+            .filter_map(|held| self.instance_row(held).ok());
+        stacks.chain(instances)
+    }
+
+    /// The stack of the item kept in the given place, empty when there is
+    /// none.
+    fn stack(
+        &self,
+        inventory_uuid: &str,
+        item_uuid: &str,
+        container_uuid: Option<&str>,
+    ) -> AppResult<Stack> {
+        if !self.inventories.contains_key(inventory_uuid) {
+            return Err(AppError::not_found("Inventory", inventory_uuid));
+        }
+        if !self.items.contains_key(item_uuid) {
+            return Err(AppError::not_found("Item", item_uuid));
+        }
+        let key = (
+            inventory_uuid.to_string(),
+            item_uuid.to_string(),
+            container_uuid.map(str::to_string),
+        );
+        Ok(self.contents.get(&key).cloned().unwrap_or_default())
+    }
+
+    pub(super) fn apply_changes(&mut self, changes: &InventoryChangeSet) -> AppResult<()> {
+        let before = changes
+            .capacity_checked_inventories()
+            .into_iter()
+            .map(|uuid| self.inventory(uuid))
+            .collect::<AppResult<Vec<_>>>()?;
+        for change in &changes.item_changes {
+            let current = self.stack(
+                &change.inventory_uuid,
+                &change.item_uuid,
+                change.container_uuid.as_deref(),
+            )?;
+            let quantity = i64::from(current.quantity) + i64::from(change.quantity_change);
+            let key = (
+                change.inventory_uuid.clone(),
+                change.item_uuid.clone(),
+                change.container_uuid.clone(),
+            );
+            match quantity {
+                0 => {
+                    self.contents.remove(&key);
+                }
+                quantity if quantity > 0 => {
+                    let quantity = u32::try_from(quantity).map_err(|_| {
+                        AppError::Validation(format!(
+                            "a stack cannot hold more than {} items",
+                            u32::MAX
+                        ))
+                    })?;
+                    self.contents.insert(
+                        key,
+                        Stack {
+                            quantity,
+                            ..current
+                        },
+                    );
+                }
+                _ => {
+                    return Err(AppError::InsufficientQuantity {
+                        item_id: change.item_uuid.clone(),
+                        available: current.quantity,
+                        requested: change.quantity_change.unsigned_abs(),
+                    })
+                }
+            }
+        }
+        for instance_move in &changes.instance_moves {
+            self.inventory(&instance_move.target_uuid)?;
+            let held = self
+                .held_instance_mut(&instance_move.inventory_uuid, &instance_move.instance_uuid)?;
+            held.inventory_uuid = instance_move.target_uuid.clone();
+            held.container_uuid = None;
+            held.carry = CarryState::Stowed;
+            held.invested = false;
+        }
+        for change in &changes.carry_changes {
+            self.inventory(&change.inventory_uuid)?;
+            if let Some(instance_uuid) = &change.instance_uuid {
+                let held = self.held_instance_mut(&change.inventory_uuid, instance_uuid)?;
+                if held.item_uuid != change.item_uuid {
+                    return Err(AppError::not_found("ItemInstance", instance_uuid.clone()));
+                }
+                held.carry = change.carry;
+                held.invested = change.invested;
+                continue;
+            }
+            let key = (
+                change.inventory_uuid.clone(),
+                change.item_uuid.clone(),
+                None,
+            );
+            let stack =
+                self.contents
+                    .get_mut(&key)
+                    .ok_or_else(|| AppError::InsufficientQuantity {
+                        item_id: change.item_uuid.clone(),
+                        available: 0,
+                        requested: 1,
+                    })?;
+            stack.carry = change.carry;
+            stack.invested = change.invested;
+        }
+        for change in &changes.currency_changes {
+            self.adjust_currency(change)?;
+        }
+        for spend in &changes.spends {
+            let change = spend.to_change(&self.inventory(&spend.inventory_uuid)?)?;
+            self.adjust_currency(&change)?;
+        }
+        for uuid in changes.rearranged_inventories() {
+            let inventory = self.inventory(uuid)?;
+            ensure_containers(&inventory)?;
+            ensure_carry_limits(&inventory)?;
+        }
+        for before in &before {
+            ensure_capacity(before, &self.inventory(&before.uuid)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl InventoryItemRepository for InMemoryRepository {
+    async fn get_inventory_items(
+        &self,
+        inventory_uuid: &str,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &ItemQueryFilter,
+        location: Option<&ItemLocationFilter>,
+        equipped_only: bool,
+    ) -> AppResult<PaginatedResponse<InventoryItem>> {
+        let mut items: Vec<InventoryItem> = self.read(|state| {
+            state
+                .inventory_rows(inventory_uuid, location, equipped_only)
+                .filter(|inventory_item| filter.matches(&inventory_item.item))
+                .collect()
+        });
+        items.sort_by(|a, b| {
+            let ordering = match order_by {
+                "quantity" => a.quantity.cmp(&b.quantity),
+                _ => compare_items(&a.item, &b.item, order_by),
+            };
+            sort_direction(order_direction, ordering).then_with(|| tiebreak(a, b))
+        });
+        Ok(paginate(items, page_index, page_size))
+    }
+
+    async fn list_inventory_items(
+        &self,
+        inventory_uuid: &str,
+        location: Option<&ItemLocationFilter>,
+    ) -> AppResult<Vec<InventoryItem>> {
+        let mut items: Vec<InventoryItem> = self.read(|state| {
+            state
+                .inventory_rows(inventory_uuid, location, false)
+                .collect()
+        });
+        items.sort_by(|a, b| compare_items(&a.item, &b.item, "name").then_with(|| tiebreak(a, b)));
+        Ok(items)
+    }
+
+    async fn get_inventory_item(
+        &self,
+        inventory_uuid: &str,
+        item_uuid: &str,
+        container_uuid: Option<&str>,
+    ) -> AppResult<InventoryItem> {
+        self.read(|state| {
+            let stack = state.stack(inventory_uuid, item_uuid, container_uuid)?;
+            Ok(InventoryItem {
+                quantity: stack.quantity,
+                item: state.item(item_uuid)?,
+                container_id: container_uuid.map(ID::from),
+                carry: stack.carry,
+                invested: stack.invested,
+                instance: None,
+            })
+        })
+    }
+
+    async fn apply_inventory_changes(&self, changes: InventoryChangeSet) -> AppResult<()> {
+        self.write(|state| state.apply_changes(&changes))
+    }
+}
@@ -0,0 +1,243 @@
+use async_graphql::ID;
+use async_trait::async_trait;
+
+use super::{paginate, sort_direction, InMemoryRepository, InMemoryState};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    container_schema::InventoryContent,
+    inventory_item_schema::CarryState,
+    inventory_schema::{CapacityEnforcement, Inventory, InventoryLoad, InventoryQueryFilter},
+    money_schema::Purse,
+    owner_schema::OwnerType,
+    paginated_response_schema::PaginatedResponse,
+};
+use crate::storage::{InventoryChangeSet, InventoryRepository};
+
+impl InMemoryState {
+    pub(super) fn inventory(&self, uuid: &str) -> AppResult<Inventory> {
+        self.inventories
+            .get(uuid)
+            .map(|inventory| self.with_contents(inventory))
+            .ok_or_else(|| AppError::not_found("Inventory", uuid))
+    }
+
+    /// Copies a stored inventory, filling in what it holds and its load.
+    pub(super) fn with_contents(&self, inventory: &Inventory) -> Inventory {
+        let content = |item_uuid: &String, container_uuid: &Option<String>, quantity| {
+            let item = self.items.get(item_uuid);
+            InventoryContent {
+                item_uuid: item_uuid.clone(),
+                container_uuid: container_uuid.clone(),
+                quantity,
+                bulk: item.and_then(|item| item.bulk).unwrap_or_default(),
+                container: item.and_then(|item| item.container),
+                carry: CarryState::Stowed,
+                invested: false,
+                instance_uuid: None,
+            }
+        };
+        let stacks = self
+            .contents
+            .iter()
+            .filter(|((inventory_uuid, _, _), _)| {
+                inventory_uuid.as_str() == inventory.uuid.as_str()
+            })
+            .map(|((_, item_uuid, container_uuid), stack)| InventoryContent {
+                carry: stack.carry,
+                invested: stack.invested,
+                ..content(item_uuid, container_uuid, stack.quantity)
+            });
+        let instances = self
+            .instances
+            .iter()
+            .filter(|(_, held)| held.inventory_uuid.as_str() == inventory.uuid.as_str())
+            .map(|(instance_uuid, held)| InventoryContent {
+                carry: held.carry,
+                invested: held.invested,
+                instance_uuid: Some(instance_uuid.clone()),
+                ..content(&held.item_uuid, &held.container_uuid, 1)
+            });
+        let contents = stacks.chain(instances).collect();
+        let mut inventory = inventory.clone();
+        inventory.set_contents(contents);
+        inventory
+    }
+
+    pub(super) fn inventory_mut(&mut self, uuid: &str) -> AppResult<&mut Inventory> {
+        self.inventories
+            .get_mut(uuid)
+            .ok_or_else(|| AppError::not_found("Inventory", uuid))
+    }
+
+    fn set_owner(&mut self, inventory_uuid: &str, owner_uuid: Option<&str>) -> AppResult<()> {
+        match owner_uuid {
+            Some(owner_uuid) => {
+                self.owner(owner_uuid)?;
+                self.ownership
+                    .insert(inventory_uuid.to_string(), owner_uuid.to_string());
+            }
+            None => {
+                self.ownership.remove(inventory_uuid);
+            }
+        }
+        Ok(())
+    }
+
+    fn owner_type_of(&self, inventory_uuid: &str) -> Option<OwnerType> {
+        let owner_uuid = self.ownership.get(inventory_uuid)?;
+        self.owners.get(owner_uuid).map(|owner| owner.owner_type)
+    }
+
+    fn find_owned_inventory(
+        &self,
+        key: &str,
+        owner: impl Fn(&str, &super::Owner) -> bool,
+    ) -> AppResult<Inventory> {
+        self.ownership
+            .iter()
+            .find(|(_, owner_uuid)| {
+                self.owners
+                    .get(owner_uuid.as_str())
+                    .is_some_and(|o| owner(owner_uuid, o))
+            })
+            .and_then(|(inventory_uuid, _)| self.inventories.get(inventory_uuid))
+            .map(|inventory| self.with_contents(inventory))
+            .ok_or_else(|| AppError::not_found("Inventory", key))
+    }
+}
+
+#[async_trait]
+impl InventoryRepository for InMemoryRepository {
+    async fn get_inventories(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &InventoryQueryFilter,
+    ) -> AppResult<PaginatedResponse<Inventory>> {
+        let mut inventories: Vec<Inventory> = self.read(|state| {
+            state
+                .inventories
+                .values()
+                .filter(|inventory| filter.matches(inventory, state.owner_type_of(&inventory.uuid)))
+                .map(|inventory| state.with_contents(inventory))
+                .collect()
+        });
+        inventories.sort_by(|a, b| {
+            let ordering = match order_by {
+                "capacity" => a.capacity.cmp(&b.capacity),
+                "wealth" => a.purse.total().cmp(&b.purse.total()),
+                _ => a.name.cmp(&b.name),
+            };
+            sort_direction(order_direction, ordering).then_with(|| b.uuid.cmp(&a.uuid))
+        });
+        Ok(paginate(inventories, page_index, page_size))
+    }
+
+    async fn get_inventory_by_uuid(&self, uuid: &str) -> AppResult<Inventory> {
+        self.read(|state| state.inventory(uuid))
+    }
+
+    async fn get_inventory_by_character_uuid(&self, uuid: &str) -> AppResult<Inventory> {
+        self.read(|state| {
+            state.find_owned_inventory(uuid, |owner_uuid, owner| {
+                owner_uuid == uuid && owner.owner_type == OwnerType::Character
+            })
+        })
+    }
+
+    async fn get_inventory_by_owner_uuid(&self, uuid: &str) -> AppResult<Inventory> {
+        self.read(|state| state.find_owned_inventory(uuid, |owner_uuid, _| owner_uuid == uuid))
+    }
+
+    async fn get_inventory_by_owner_name(&self, name: &str) -> AppResult<Inventory> {
+        let term = name.to_lowercase();
+        self.read(|state| {
+            state.find_owned_inventory(name, |_, owner| owner.name.to_lowercase().contains(&term))
+        })
+    }
+
+    async fn create_inventory(
+        &self,
+        name: &str,
+        capacity: u16,
+        capacity_enforcement: CapacityEnforcement,
+        owner_uuid: Option<&str>,
+    ) -> AppResult<Inventory> {
+        self.write(|state| {
+            let uuid = state.next_uuid();
+            let inventory = Inventory {
+                uuid: ID::from(uuid.clone()),
+                name: name.to_string(),
+                capacity,
+                capacity_enforcement,
+                shop: None,
+                purse: Purse::default(),
+                load: InventoryLoad::default(),
+                contents: Vec::new(),
+            };
+            state.inventories.insert(uuid.clone(), inventory.clone());
+            state.set_owner(&uuid, owner_uuid)?;
+            Ok(inventory)
+        })
+    }
+
+    async fn update_inventory(
+        &self,
+        uuid: &str,
+        name: Option<&str>,
+        capacity: Option<u16>,
+        capacity_enforcement: Option<CapacityEnforcement>,
+    ) -> AppResult<Inventory> {
+        self.write(|state| {
+            let inventory = state.inventory_mut(uuid)?;
+            if let Some(name) = name {
+                inventory.name = name.to_string();
+            }
+            if let Some(capacity) = capacity {
+                inventory.capacity = capacity;
+            }
+            if let Some(capacity_enforcement) = capacity_enforcement {
+                inventory.capacity_enforcement = capacity_enforcement;
+            }
+            state.inventory(uuid)
+        })
+    }
+
+    async fn set_inventory_owner(
+        &self,
+        inventory_uuid: &str,
+        owner_uuid: Option<&str>,
+    ) -> AppResult<Inventory> {
+        self.write(|state| {
+            let inventory = state.inventory(inventory_uuid)?;
+            state.set_owner(inventory_uuid, owner_uuid)?;
+            Ok(inventory)
+        })
+    }
+
+    async fn delete_inventory(
+        &self,
+        uuid: &str,
+        transfer_to: Option<&str>,
+    ) -> AppResult<Inventory> {
+        self.write(|state| {
+            let inventory = state.inventory(uuid)?;
+            if let Some(target_uuid) = transfer_to {
+                state.inventory(target_uuid)?;
+                state.apply_changes(&InventoryChangeSet::transfer_all(&inventory, target_uuid)?)?;
+            }
+            state
+                .contents
+                .retain(|(inventory_uuid, _, _), _| inventory_uuid != uuid);
+            state
+                .instances
+                .retain(|_, held| held.inventory_uuid != uuid);
+            state.listings.retain(|(shop_uuid, _), _| shop_uuid != uuid);
+            state.ownership.remove(uuid);
+            state.inventories.remove(uuid);
+            Ok(inventory)
+        })
+    }
+}
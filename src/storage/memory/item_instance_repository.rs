@@ -0,0 +1,154 @@
+use async_graphql::ID;
+use async_trait::async_trait;
+
+use super::{HeldInstance, InMemoryRepository, InMemoryState};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    inventory_item_schema::{CarryState, InventoryItem},
+    item_instance_schema::ItemInstanceInput,
+};
+use crate::storage::{ensure_capacity, ensure_containers, ItemInstanceRepository};
+
+impl InMemoryState {
+    fn held_instance(&self, inventory_uuid: &str, instance_uuid: &str) -> AppResult<&HeldInstance> {
+        self.inventory(inventory_uuid)?;
+        self.instances
+            .get(instance_uuid)
+            .filter(|held| held.inventory_uuid == inventory_uuid)
+            .ok_or_else(|| AppError::not_found("ItemInstance", instance_uuid))
+    }
+
+    pub(super) fn held_instance_mut(
+        &mut self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<&mut HeldInstance> {
+        self.inventory(inventory_uuid)?;
+        self.instances
+            .get_mut(instance_uuid)
+            .filter(|held| held.inventory_uuid == inventory_uuid)
+            .ok_or_else(|| AppError::not_found("ItemInstance", instance_uuid))
+    }
+
+    pub(super) fn instance_row(&self, held: &HeldInstance) -> AppResult<InventoryItem> {
+        Ok(InventoryItem {
+            item: self.item(&held.item_uuid)?,
+            quantity: 1,
+            container_id: held.container_uuid.clone().map(ID::from),
+            carry: held.carry,
+            invested: held.invested,
+            instance: Some(held.instance.clone()),
+        })
+    }
+}
+
+#[async_trait]
+impl ItemInstanceRepository for InMemoryRepository {
+    async fn get_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<InventoryItem> {
+        self.read(|state| state.instance_row(state.held_instance(inventory_uuid, instance_uuid)?))
+    }
+
+    async fn create_item_instance(
+        &self,
+        inventory_uuid: &str,
+        item_uuid: &str,
+        container_uuid: Option<&str>,
+        instance: &ItemInstanceInput,
+        override_capacity: bool,
+    ) -> AppResult<String> {
+        self.write(|state| {
+            let before = state.inventory(inventory_uuid)?;
+            state.item(item_uuid)?;
+            let uuid = state.next_uuid();
+            state.instances.insert(
+                uuid.clone(),
+                HeldInstance {
+                    inventory_uuid: inventory_uuid.to_string(),
+                    item_uuid: item_uuid.to_string(),
+                    container_uuid: container_uuid.map(str::to_string),
+                    carry: CarryState::Stowed,
+                    invested: false,
+                    instance: instance.clone().into_instance(ID::from(uuid.clone())),
+                },
+            );
+            let after = state.inventory(inventory_uuid)?;
+            ensure_containers(&after)?;
+            if !override_capacity {
+                ensure_capacity(&before, &after)?;
+            }
+            Ok(uuid)
+        })
+    }
+
+    async fn update_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+        instance: &ItemInstanceInput,
+    ) -> AppResult<()> {
+        self.write(|state| {
+            let held = state.held_instance_mut(inventory_uuid, instance_uuid)?;
+            held.instance = instance.clone().into_instance(ID::from(instance_uuid));
+            Ok(())
+        })
+    }
+
+    async fn remove_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<()> {
+        self.write(|state| {
+            state.held_instance(inventory_uuid, instance_uuid)?;
+            state.instances.remove(instance_uuid);
+            ensure_containers(&state.inventory(inventory_uuid)?)
+        })
+    }
+
+    async fn spend_instance_charge(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<()> {
+        self.write(|state| {
+            let held = state.held_instance_mut(inventory_uuid, instance_uuid)?;
+            match &mut held.instance.charges {
+                Some(charges) if *charges > 0 => {
+                    *charges -= 1;
+                    Ok(())
+                }
+                _ => Err(AppError::NoUsesRemaining {
+                    item_id: instance_uuid.to_string(),
+                }),
+            }
+        })
+    }
+
+    async fn prepare_instances(&self, inventory_uuid: &str) -> AppResult<()> {
+        self.write(|state| {
+            state.inventory(inventory_uuid)?;
+            state.instances.retain(|_, held| {
+                held.inventory_uuid != inventory_uuid || !held.instance.temporary
+            });
+            let InMemoryState {
+                items, instances, ..
+            } = state;
+            for held in instances
+                .values_mut()
+                .filter(|held| held.inventory_uuid == inventory_uuid)
+            {
+                if let Some(charges) = items
+                    .get(&held.item_uuid)
+                    .and_then(|item| item.charges_per_day)
+                {
+                    held.instance.charges = Some(charges);
+                }
+            }
+            ensure_containers(&state.inventory(inventory_uuid)?)
+        })
+    }
+}
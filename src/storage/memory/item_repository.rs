@@ -0,0 +1,148 @@
+use std::cmp::Ordering;
+
+use async_graphql::ID;
+use async_trait::async_trait;
+
+use super::{paginate, sort_direction, InMemoryRepository, InMemoryState};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    bulk_schema::Bulk,
+    item_schema::{Item, ItemProperties, ItemQueryFilter, Rarity},
+    paginated_response_schema::PaginatedResponse,
+};
+use crate::storage::ItemRepository;
+
+impl InMemoryState {
+    /// Builds the item the same way the Neo4j backend reads it back,
+    /// including the defaults it substitutes for missing properties.
+    pub(super) fn item(&self, uuid: &str) -> AppResult<Item> {
+        let properties = self
+            .items
+            .get(uuid)
+            .ok_or_else(|| AppError::not_found("Item", uuid))?
+            .clone();
+        let category = properties.category.unwrap_or_default();
+        Ok(Item::new(
+            ID::from(uuid),
+            ItemProperties {
+                effect: properties.effect.or(Some("No effect".to_string())),
+                level: properties.level.or(Some(0)),
+                traits: properties.traits.or(Some(Vec::new())),
+                bulk: properties.bulk.or(Some(Bulk::Negligible)),
+                description: properties
+                    .description
+                    .or(Some("No description".to_string())),
+                usage_requirements: properties
+                    .usage_requirements
+                    .or(Some("Not usable".to_string())),
+                rarity: properties.rarity.or(Some(Rarity::Common)),
+                category: Some(category),
+                stats: properties
+                    .stats
+                    .filter(|stats| stats.category() == category),
+                ..properties
+            },
+        ))
+    }
+}
+
+#[async_trait]
+impl ItemRepository for InMemoryRepository {
+    async fn get_items(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &ItemQueryFilter,
+    ) -> AppResult<PaginatedResponse<Item>> {
+        let mut items: Vec<Item> = self.read(|state| {
+            state
+                .items
+                .keys()
+                .filter_map(|uuid| state.item(uuid).ok())
+                .filter(|item| filter.matches(item))
+                .collect()
+        });
+        items.sort_by(|a, b| {
+            sort_direction(order_direction, compare_items(a, b, order_by))
+                .then_with(|| b.uuid.cmp(&a.uuid))
+        });
+        Ok(paginate(items, page_index, page_size))
+    }
+
+    async fn get_item(&self, uuid: &str) -> AppResult<Item> {
+        self.read(|state| state.item(uuid))
+    }
+
+    async fn create_item(&self, properties: ItemProperties) -> AppResult<Item> {
+        self.write(|state| {
+            let traits = properties.traits.unwrap_or_default();
+            for trait_name in &traits {
+                state.ensure_trait(trait_name);
+            }
+            let uuid = state.next_uuid();
+            state.items.insert(
+                uuid.clone(),
+                ItemProperties {
+                    name: Some(properties.name.unwrap_or("Unnamed Item".to_string())),
+                    level: Some(properties.level.unwrap_or_default()),
+                    traits: Some(traits),
+                    activation_cost: properties.activation_cost,
+                    bulk: Some(properties.bulk.unwrap_or_default()),
+                    description: Some(properties.description.unwrap_or_default()),
+                    usage_requirements: Some(properties.usage_requirements.unwrap_or_default()),
+                    value: Some(properties.value.unwrap_or_default()),
+                    effect: Some(properties.effect.unwrap_or_default()),
+                    container: properties.container,
+                    charges_per_day: properties.charges_per_day,
+                    rarity: Some(properties.rarity.unwrap_or_default()),
+                    category: Some(properties.category.unwrap_or_default()),
+                    stats: properties.stats,
+                },
+            );
+            state.item(&uuid)
+        })
+    }
+
+    async fn update_item(&self, uuid: &str, properties: ItemProperties) -> AppResult<Item> {
+        self.write(|state| {
+            let traits = properties.traits.unwrap_or_default();
+            for trait_name in &traits {
+                state.ensure_trait(trait_name);
+            }
+            let stored = state
+                .items
+                .get_mut(uuid)
+                .ok_or_else(|| AppError::not_found("Item", uuid))?;
+            stored.name = properties.name.or(stored.name.take());
+            stored.level = properties.level.or(stored.level);
+            stored.activation_cost = properties.activation_cost.or(stored.activation_cost);
+            stored.bulk = properties.bulk.or(stored.bulk);
+            stored.description = properties.description.or(stored.description.take());
+            stored.usage_requirements = properties
+                .usage_requirements
+                .or(stored.usage_requirements.take());
+            stored.value = properties.value.or(stored.value);
+            stored.effect = properties.effect.or(stored.effect.take());
+            stored.container = properties.container.or(stored.container);
+            stored.charges_per_day = properties.charges_per_day.or(stored.charges_per_day);
+            stored.rarity = properties.rarity.or(stored.rarity);
+            stored.category = properties.category.or(stored.category);
+            stored.stats = properties.stats.or(stored.stats.take());
+            stored.traits = Some(traits);
+            state.item(uuid)
+        })
+    }
+}
+
+pub(super) fn compare_items(a: &Item, b: &Item, order_by: &str) -> Ordering {
+    let (a, b) = (&a.properties, &b.properties);
+    match order_by {
+        "value" => a.value.cmp(&b.value),
+        "level" => a.level.cmp(&b.level),
+        "bulk" => a.bulk.cmp(&b.bulk),
+        "rarity" => a.rarity.cmp(&b.rarity),
+        _ => a.name.cmp(&b.name),
+    }
+}
@@ -0,0 +1,273 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use async_graphql::{OutputType, ID};
+
+use crate::error::AppResult;
+use crate::graphql::schemas::{
+    activation_schema::{ActivationCost, ActivationKind},
+    bulk_schema::Bulk,
+    container_schema::ContainerProperties,
+    inventory_item_schema::CarryState,
+    inventory_schema::{CapacityEnforcement, Inventory, InventoryLoad},
+    item_category_schema::{ItemCategory, ItemStatsInput, WeaponStats},
+    item_instance_schema::ItemInstance,
+    item_schema::ItemProperties,
+    money_schema::{Money, Purse},
+    owner_schema::OwnerType,
+    paginated_response_schema::PaginatedResponse,
+    shop_schema::ShopListing,
+    trait_schema::Trait,
+};
+
+mod currency_repository;
+mod inventory_item_repository;
+mod inventory_repository;
+mod item_instance_repository;
+mod item_repository;
+mod owner_repository;
+mod shop_repository;
+mod trait_repository;
+
+/// A storage backend that keeps everything in process memory. Every write
+/// works on a copy of the state which replaces the original only once the
+/// whole operation has succeeded, so batches are applied atomically.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: RwLock<InMemoryState>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct InMemoryState {
+    items: BTreeMap<String, ItemProperties>,
+    traits: BTreeMap<String, Trait>,
+    owners: BTreeMap<String, Owner>,
+    inventories: BTreeMap<String, Inventory>,
+    /// Inventory uuid to the uuid of the owner holding it.
+    ownership: BTreeMap<String, String>,
+    /// (inventory uuid, item uuid, container item uuid) to the stack held;
+    /// the container is `None` for loose stacks.
+    contents: BTreeMap<ContentKey, Stack>,
+    /// Instance uuid to the instance and where it is held.
+    instances: BTreeMap<String, HeldInstance>,
+    /// (shop uuid, item uuid) to how the shop sells the item.
+    listings: BTreeMap<(String, String), ShopListing>,
+    next_id: u64,
+}
+
+type ContentKey = (String, String, Option<String>);
+
+#[derive(Debug, Clone, Default)]
+struct Stack {
+    quantity: u32,
+    carry: CarryState,
+    invested: bool,
+}
+
+#[derive(Debug, Clone)]
+struct HeldInstance {
+    inventory_uuid: String,
+    item_uuid: String,
+    container_uuid: Option<String>,
+    carry: CarryState,
+    invested: bool,
+    instance: ItemInstance,
+}
+
+#[derive(Debug, Clone)]
+struct Owner {
+    name: String,
+    owner_type: OwnerType,
+    sell_percent: Option<u32>,
+}
+
+impl InMemoryRepository {
+    /// Creates a repository holding a handful of items and a single character
+    /// inventory, enough to poke at the API without a database.
+    pub fn with_demo_data() -> Self {
+        let mut state = InMemoryState::default();
+
+        let rope = state.insert_item("Rope", 0, Bulk::Light, Money::from_copper(5), &[]);
+        let torch = state.insert_item("Torch", 0, Bulk::Light, Money::from_copper(1), &[]);
+        let potion = state.insert_item(
+            "Minor Healing Potion",
+            1,
+            Bulk::Light,
+            Money::from_copper(400),
+            &["Consumable", "Healing", "Magical", "Potion"],
+        );
+        let longsword =
+            state.insert_item("Longsword", 0, Bulk::Units(1), Money::from_copper(100), &[]);
+        let healing_potion = state.items.get_mut(&potion).unwrap();
+        healing_potion.category = Some(ItemCategory::Consumable);
+        healing_potion.activation_cost = Some(ActivationCost {
+            kind: ActivationKind::OneAction,
+            minutes: None,
+        });
+        let sword = state.items.get_mut(&longsword).unwrap();
+        sword.category = Some(ItemCategory::Weapon);
+        sword.stats = Some(ItemStatsInput::Weapon(WeaponStats {
+            damage: "1d8".to_string(),
+            damage_type: "slashing".to_string(),
+            group: Some("sword".to_string()),
+            range: None,
+        }));
+
+        let owner = state.next_uuid();
+        state.owners.insert(
+            owner.clone(),
+            Owner {
+                name: "Valeros".to_string(),
+                owner_type: OwnerType::Character,
+                sell_percent: None,
+            },
+        );
+        let inventory = state.next_uuid();
+        state.inventories.insert(
+            inventory.clone(),
+            Inventory {
+                uuid: ID::from(inventory.clone()),
+                name: "Valeros's Pack".to_string(),
+                capacity: 10,
+                capacity_enforcement: CapacityEnforcement::Warn,
+                shop: None,
+                purse: Purse {
+                    pp: 0,
+                    gp: 15,
+                    sp: 4,
+                    cp: 7,
+                },
+                load: InventoryLoad::default(),
+                contents: Vec::new(),
+            },
+        );
+        state.ownership.insert(inventory.clone(), owner);
+        let backpack = state.insert_item("Backpack", 0, Bulk::Light, Money::from_copper(10), &[]);
+        state.items.get_mut(&backpack).unwrap().container = Some(ContainerProperties {
+            capacity: 4,
+            ignored_bulk: 2,
+            ignored_only_when_worn: true,
+        });
+        for (item, container, quantity, carry) in [
+            (rope, Some(&backpack), 1, CarryState::Stowed),
+            (torch, Some(&backpack), 5, CarryState::Stowed),
+            (potion, None, 2, CarryState::Stowed),
+            (longsword, None, 1, CarryState::HeldInOneHand),
+            (backpack.clone(), None, 1, CarryState::Worn),
+        ] {
+            state.contents.insert(
+                (inventory.clone(), item, container.cloned()),
+                Stack {
+                    quantity,
+                    carry,
+                    invested: false,
+                },
+            );
+        }
+
+        Self {
+            state: RwLock::new(state),
+        }
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&InMemoryState) -> T) -> T {
+        f(&self.state.read().unwrap())
+    }
+
+    /// Runs `f` against a copy of the state and keeps the copy only when `f`
+    /// succeeds.
+    fn write<T>(&self, f: impl FnOnce(&mut InMemoryState) -> AppResult<T>) -> AppResult<T> {
+        let mut state = self.state.write().unwrap();
+        let mut next = state.clone();
+        let result = f(&mut next)?;
+        *state = next;
+        Ok(result)
+    }
+}
+
+impl InMemoryState {
+    fn next_uuid(&mut self) -> String {
+        self.next_id += 1;
+        format!("00000000-0000-4000-8000-{:012x}", self.next_id)
+    }
+
+    fn insert_item(
+        &mut self,
+        name: &str,
+        level: u16,
+        bulk: Bulk,
+        value: Money,
+        traits: &[&str],
+    ) -> String {
+        let uuid = self.next_uuid();
+        for trait_name in traits {
+            self.ensure_trait(trait_name);
+        }
+        self.items.insert(
+            uuid.clone(),
+            ItemProperties {
+                name: Some(name.to_string()),
+                level: Some(level),
+                traits: Some(traits.iter().map(|t| t.to_string()).collect()),
+                activation_cost: None,
+                bulk: Some(bulk),
+                description: None,
+                usage_requirements: None,
+                value: Some(value),
+                effect: None,
+                container: None,
+                charges_per_day: None,
+                rarity: None,
+                category: None,
+                stats: None,
+            },
+        );
+        uuid
+    }
+
+    fn ensure_trait(&mut self, name: &str) {
+        self.traits
+            .entry(name.to_string())
+            .or_insert_with(|| Trait {
+                name: name.to_string(),
+                description: None,
+                category: None,
+                usage_count: 0,
+            });
+    }
+}
+
+fn sort_direction(order_direction: &str, ordering: Ordering) -> Ordering {
+    if order_direction == "ASC" {
+        ordering
+    } else {
+        ordering.reverse()
+    }
+}
+
+fn paginate<T: OutputType>(
+    mut entities: Vec<T>,
+    page_index: u32,
+    page_size: u32,
+) -> PaginatedResponse<T> {
+    let total_entities = entities.len() as u32;
+    let total_pages = if page_size == 0 {
+        0
+    } else {
+        total_entities.div_ceil(page_size)
+    };
+    let skip = usize::try_from(u64::from(page_index) * u64::from(page_size)).unwrap_or(usize::MAX);
+    let entities = if skip < entities.len() {
+        entities.drain(skip..).take(page_size as usize).collect()
+    } else {
+        Vec::new()
+    };
+    PaginatedResponse {
+        entities,
+        page_index,
+        page_size,
+        total_entities,
+        total_pages,
+    }
+}
@@ -0,0 +1,102 @@
+use async_graphql::ID;
+use async_trait::async_trait;
+
+use super::{InMemoryRepository, InMemoryState};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::owner_schema::{Owner, OwnerType};
+use crate::storage::OwnerRepository;
+
+impl InMemoryState {
+    pub(super) fn owner(&self, uuid: &str) -> AppResult<Owner> {
+        let owner = self
+            .owners
+            .get(uuid)
+            .ok_or_else(|| AppError::not_found("Owner", uuid))?;
+        Ok(Owner {
+            uuid: ID::from(uuid),
+            name: owner.name.clone(),
+            owner_type: owner.owner_type,
+            sell_percent: owner.sell_percent,
+            inventory_ids: self
+                .ownership
+                .iter()
+                .filter(|(_, owner_uuid)| owner_uuid.as_str() == uuid)
+                .map(|(inventory_uuid, _)| ID::from(inventory_uuid.as_str()))
+                .collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl OwnerRepository for InMemoryRepository {
+    async fn get_owners(&self) -> AppResult<Vec<Owner>> {
+        let mut owners = self.read(|state| {
+            state
+                .owners
+                .keys()
+                .map(|uuid| state.owner(uuid))
+                .collect::<AppResult<Vec<_>>>()
+        })?;
+        owners.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(owners)
+    }
+
+    async fn get_owner(&self, uuid: &str) -> AppResult<Owner> {
+        self.read(|state| state.owner(uuid))
+    }
+
+    async fn create_owner(
+        &self,
+        name: &str,
+        owner_type: OwnerType,
+        sell_percent: Option<u32>,
+    ) -> AppResult<Owner> {
+        self.write(|state| {
+            let uuid = state.next_uuid();
+            state.owners.insert(
+                uuid.clone(),
+                super::Owner {
+                    name: name.to_string(),
+                    owner_type,
+                    sell_percent,
+                },
+            );
+            state.owner(&uuid)
+        })
+    }
+
+    async fn update_owner(
+        &self,
+        uuid: &str,
+        name: Option<&str>,
+        sell_percent: Option<u32>,
+    ) -> AppResult<Owner> {
+        self.write(|state| {
+            let owner = state
+                .owners
+                .get_mut(uuid)
+                .ok_or_else(|| AppError::not_found("Owner", uuid))?;
+            if let Some(name) = name {
+                owner.name = name.to_string();
+            }
+            if sell_percent.is_some() {
+                owner.sell_percent = sell_percent;
+            }
+            state.owner(uuid)
+        })
+    }
+
+    async fn delete_owner(&self, uuid: &str) -> AppResult<Owner> {
+        self.write(|state| {
+            let owner = state.owner(uuid)?;
+            if !owner.inventory_ids.is_empty() {
+                return Err(AppError::Conflict(format!(
+                    "owner {} still owns inventories",
+                    uuid
+                )));
+            }
+            state.owners.remove(uuid);
+            Ok(owner)
+        })
+    }
+}
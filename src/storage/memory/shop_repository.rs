@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+
+use super::{InMemoryRepository, InMemoryState};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    inventory_schema::Inventory,
+    shop_schema::{ShopListing, ShopSettings},
+};
+use crate::storage::ShopRepository;
+
+impl InMemoryState {
+    fn restock(&mut self, shop_uuid: &str) -> AppResult<Inventory> {
+        let shop = self.inventory(shop_uuid)?;
+        let Some(settings) = shop.shop.clone() else {
+            return Ok(shop);
+        };
+        let Some(template_uuid) = settings.restock_template_id.clone() else {
+            return Ok(shop);
+        };
+        // The template's stacks are totalled wherever they are kept, and
+        // only the shop's loose stack is raised
+        let mut stock: BTreeMap<String, u32> = BTreeMap::new();
+        for ((_, item_uuid, _), stack) in self
+            .contents
+            .iter()
+            .filter(|((inventory_uuid, _, _), _)| inventory_uuid.as_str() == template_uuid.as_str())
+        {
+            let level = self
+                .items
+                .get(item_uuid)
+                .and_then(|item| item.level)
+                .unwrap_or_default();
+            if settings.sells_level(level) {
+                let total = stock.entry(item_uuid.clone()).or_default();
+                *total = total.saturating_add(stack.quantity);
+            }
+        }
+        for (item_uuid, quantity) in stock {
+            let held = self
+                .contents
+                .entry((shop_uuid.to_string(), item_uuid, None))
+                .or_default();
+            held.quantity = held.quantity.max(quantity);
+        }
+        self.inventory(shop_uuid)
+    }
+}
+
+#[async_trait]
+impl ShopRepository for InMemoryRepository {
+    async fn set_shop_settings(
+        &self,
+        inventory_uuid: &str,
+        settings: Option<&ShopSettings>,
+    ) -> AppResult<Inventory> {
+        self.write(|state| {
+            state.inventory_mut(inventory_uuid)?.shop = settings.cloned();
+            state.inventory(inventory_uuid)
+        })
+    }
+
+    async fn get_shop_listings(&self, shop_uuid: &str) -> AppResult<Vec<ShopListing>> {
+        Ok(self.read(|state| {
+            state
+                .listings
+                .iter()
+                .filter(|((uuid, _), _)| uuid == shop_uuid)
+                .map(|(_, listing)| listing.clone())
+                .collect()
+        }))
+    }
+
+    async fn set_shop_listing(&self, shop_uuid: &str, listing: &ShopListing) -> AppResult<()> {
+        self.write(|state| {
+            state.inventory(shop_uuid)?;
+            if !state.items.contains_key(listing.item_id.as_str()) {
+                return Err(AppError::not_found("Item", listing.item_id.as_str()));
+            }
+            state.listings.insert(
+                (shop_uuid.to_string(), listing.item_id.to_string()),
+                listing.clone(),
+            );
+            Ok(())
+        })
+    }
+
+    async fn remove_shop_listing(&self, shop_uuid: &str, item_uuid: &str) -> AppResult<()> {
+        self.write(|state| {
+            state
+                .listings
+                .remove(&(shop_uuid.to_string(), item_uuid.to_string()));
+            Ok(())
+        })
+    }
+
+    async fn restock_shop(&self, shop_uuid: &str) -> AppResult<Inventory> {
+        self.write(|state| state.restock(shop_uuid))
+    }
+}
@@ -0,0 +1,136 @@
+use async_trait::async_trait;
+
+use super::{InMemoryRepository, InMemoryState};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::trait_schema::{Trait, TraitInput, TraitUpdate};
+use crate::storage::TraitRepository;
+
+impl InMemoryState {
+    /// The trait with the number of items that have it.
+    fn trait_with_usage(&self, name: &str) -> AppResult<Trait> {
+        let stored = self
+            .traits
+            .get(name)
+            .ok_or_else(|| AppError::not_found("Trait", name))?;
+        let usage_count = self
+            .items
+            .values()
+            .filter(|item| {
+                item.traits
+                    .as_ref()
+                    .is_some_and(|traits| traits.iter().any(|t| t == name))
+            })
+            .count() as u32;
+        Ok(Trait {
+            usage_count,
+            ..stored.clone()
+        })
+    }
+
+    /// Renames the trait on every item that has it, dropping it where the
+    /// item already has the new name.
+    fn rename_item_traits(&mut self, from: &str, to: Option<&str>) {
+        for item in self.items.values_mut() {
+            let Some(traits) = item.traits.as_mut() else {
+                continue;
+            };
+            if !traits.iter().any(|t| t == from) {
+                continue;
+            }
+            let has_target = to.is_some_and(|to| traits.iter().any(|t| t == to));
+            match to {
+                Some(to) if !has_target => {
+                    for t in traits.iter_mut().filter(|t| *t == from) {
+                        *t = to.to_string();
+                    }
+                }
+                _ => traits.retain(|t| t != from),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TraitRepository for InMemoryRepository {
+    async fn get_traits(&self) -> AppResult<Vec<Trait>> {
+        self.read(|state| {
+            state
+                .traits
+                .keys()
+                .map(|name| state.trait_with_usage(name))
+                .collect()
+        })
+    }
+
+    async fn get_trait(&self, name: &str) -> AppResult<Trait> {
+        self.read(|state| state.trait_with_usage(name))
+    }
+
+    async fn create_trait(&self, params: &TraitInput) -> AppResult<Trait> {
+        self.write(|state| {
+            if state.traits.contains_key(&params.name) {
+                return Err(AppError::Conflict(format!(
+                    "trait {} already exists",
+                    params.name
+                )));
+            }
+            state.traits.insert(
+                params.name.clone(),
+                Trait {
+                    name: params.name.clone(),
+                    description: params.description.clone(),
+                    category: params.category,
+                    usage_count: 0,
+                },
+            );
+            state.trait_with_usage(&params.name)
+        })
+    }
+
+    async fn update_trait(&self, name: &str, params: &TraitUpdate) -> AppResult<Trait> {
+        self.write(|state| {
+            let mut stored = state
+                .traits
+                .remove(name)
+                .ok_or_else(|| AppError::not_found("Trait", name))?;
+            let new_name = params.name.clone().unwrap_or_else(|| name.to_string());
+            if state.traits.contains_key(&new_name) {
+                return Err(AppError::Conflict(format!(
+                    "trait {} already exists",
+                    new_name
+                )));
+            }
+            if new_name != name {
+                state.rename_item_traits(name, Some(&new_name));
+            }
+            stored.name = new_name.clone();
+            stored.description = params.description.clone().or(stored.description);
+            stored.category = params.category.or(stored.category);
+            state.traits.insert(new_name.clone(), stored);
+            state.trait_with_usage(&new_name)
+        })
+    }
+
+    async fn delete_trait(&self, name: &str) -> AppResult<Trait> {
+        self.write(|state| {
+            let deleted = state.trait_with_usage(name)?;
+            state.rename_item_traits(name, None);
+            state.traits.remove(name);
+            Ok(deleted)
+        })
+    }
+
+    async fn merge_traits(&self, source: &str, target: &str) -> AppResult<Trait> {
+        self.write(|state| {
+            let merged = state.trait_with_usage(source)?;
+            state.trait_with_usage(target)?;
+            state.rename_item_traits(source, Some(target));
+            state.traits.remove(source);
+            if let Some(stored) = state.traits.get_mut(target) {
+                stored.description = stored.description.take().or(merged.description);
+                stored.category = stored.category.or(merged.category);
+            }
+            state.trait_with_usage(target)
+        })
+    }
+}
@@ -0,0 +1,937 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use async_trait::async_trait;
+
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    container_schema::ContentTree,
+    inventory_item_schema::{
+        CarryState, InventoryItem, ItemLocationFilter, MAX_HANDS, MAX_INVESTED_ITEMS,
+    },
+    inventory_schema::{CapacityEnforcement, Inventory, InventoryQueryFilter},
+    item_instance_schema::ItemInstanceInput,
+    item_schema::{Item, ItemProperties, ItemQueryFilter},
+    money_schema::{Money, Purse},
+    owner_schema::{Owner, OwnerType},
+    paginated_response_schema::PaginatedResponse,
+    shop_schema::{ShopListing, ShopSettings},
+    trait_schema::{Trait, TraitInput, TraitUpdate},
+};
+
+pub mod memory;
+pub mod neo4j;
+
+pub use memory::InMemoryRepository;
+pub use neo4j::Neo4jRepository;
+
+/// A change to the quantity of an item held by an inventory.
+#[derive(Debug, Clone)]
+pub struct ItemQuantityChange {
+    pub inventory_uuid: String,
+    pub item_uuid: String,
+    /// The container item the stack is kept in; loose when `None`.
+    pub container_uuid: Option<String>,
+    pub quantity_change: i32,
+}
+
+/// Sets how the loose stack of an item held by an inventory, or one
+/// instance of it, is carried.
+#[derive(Debug, Clone)]
+pub struct CarryStateChange {
+    pub inventory_uuid: String,
+    pub item_uuid: String,
+    pub instance_uuid: Option<String>,
+    pub carry: CarryState,
+    pub invested: bool,
+}
+
+/// Moves an item instance, with its overrides, into another inventory. It
+/// arrives loose and stowed.
+#[derive(Debug, Clone)]
+pub struct InstanceMove {
+    pub inventory_uuid: String,
+    pub instance_uuid: String,
+    pub target_uuid: String,
+}
+
+/// A signed change to each coin denomination of an inventory's purse.
+#[derive(Debug, Clone)]
+pub struct CurrencyChange {
+    pub inventory_uuid: String,
+    pub pp: i64,
+    pub gp: i64,
+    pub sp: i64,
+    pub cp: i64,
+}
+
+impl CurrencyChange {
+    /// Adds the coins to the inventory's purse.
+    pub fn deposit(inventory_uuid: &str, coins: Purse) -> Self {
+        Self {
+            inventory_uuid: inventory_uuid.to_string(),
+            pp: coins.pp.into(),
+            gp: coins.gp.into(),
+            sp: coins.sp.into(),
+            cp: coins.cp.into(),
+        }
+    }
+
+    /// Adds the change to the inventory's purse, refusing to take any
+    /// denomination below zero.
+    pub fn apply_to(&self, inventory: &mut Inventory) -> AppResult<()> {
+        let purse = &mut inventory.purse;
+        let pp = adjust_denomination(purse.pp, self.pp, 1000)?;
+        let gp = adjust_denomination(purse.gp, self.gp, 100)?;
+        let sp = adjust_denomination(purse.sp, self.sp, 10)?;
+        let cp = adjust_denomination(purse.cp, self.cp, 1)?;
+        *purse = Purse { pp, gp, sp, cp };
+        Ok(())
+    }
+}
+
+fn adjust_denomination(current: u32, delta: i64, copper_value: u64) -> AppResult<u32> {
+    u32::try_from(current as i64 + delta).map_err(|_| AppError::InsufficientFunds {
+        required: Money::from_copper(delta.unsigned_abs() * copper_value),
+        available: Money::from_copper(current as u64 * copper_value),
+    })
+}
+
+/// An amount to take from an inventory's purse, making change from larger
+/// coins as needed. Unlike a `CurrencyChange` the coins used are only decided
+/// once the purse has been read inside the transaction.
+#[derive(Debug, Clone)]
+pub struct CurrencySpend {
+    pub inventory_uuid: String,
+    pub amount: Money,
+}
+
+impl CurrencySpend {
+    /// The denomination changes that pay the amount out of the inventory's
+    /// purse.
+    pub fn to_change(&self, inventory: &Inventory) -> AppResult<CurrencyChange> {
+        let before = inventory.purse;
+        let after = before.spend(self.amount)?;
+        let delta = |after: u32, before: u32| after as i64 - before as i64;
+        Ok(CurrencyChange {
+            inventory_uuid: self.inventory_uuid.clone(),
+            pp: delta(after.pp, before.pp),
+            gp: delta(after.gp, before.gp),
+            sp: delta(after.sp, before.sp),
+            cp: delta(after.cp, before.cp),
+        })
+    }
+}
+
+/// A batch of item and currency changes that a repository applies atomically.
+#[derive(Debug, Clone, Default)]
+pub struct InventoryChangeSet {
+    pub item_changes: Vec<ItemQuantityChange>,
+    pub currency_changes: Vec<CurrencyChange>,
+    /// Applied after `currency_changes`, so coins gained in the same batch
+    /// can be spent.
+    pub spends: Vec<CurrencySpend>,
+    /// Applied after `item_changes`.
+    pub instance_moves: Vec<InstanceMove>,
+    /// Applied after `instance_moves`, to stacks that must then exist.
+    pub carry_changes: Vec<CarryStateChange>,
+    /// Skips the capacity check of inventories set to reject going over it.
+    pub override_capacity: bool,
+}
+
+impl InventoryChangeSet {
+    /// The inventories whose capacity must be checked: those gaining items or
+    /// coins, unless the capacity is overridden.
+    pub fn capacity_checked_inventories(&self) -> BTreeSet<&str> {
+        if self.override_capacity {
+            return BTreeSet::new();
+        }
+        let gaining_items = self
+            .item_changes
+            .iter()
+            .filter(|change| change.quantity_change > 0)
+            .map(|change| change.inventory_uuid.as_str());
+        let gaining_instances = self
+            .instance_moves
+            .iter()
+            .map(|instance_move| instance_move.target_uuid.as_str());
+        let gaining_coins = self
+            .currency_changes
+            .iter()
+            .filter(|change| change.pp > 0 || change.gp > 0 || change.sp > 0 || change.cp > 0)
+            .map(|change| change.inventory_uuid.as_str());
+        gaining_items
+            .chain(gaining_instances)
+            .chain(gaining_coins)
+            .collect()
+    }
+
+    /// The inventories whose stacks must be checked against
+    /// `ensure_containers` and `ensure_carry_limits`: every one with an item
+    /// change, instance move or carry state change.
+    pub fn rearranged_inventories(&self) -> BTreeSet<&str> {
+        let item_changes = self
+            .item_changes
+            .iter()
+            .map(|change| change.inventory_uuid.as_str());
+        let instance_moves = self.instance_moves.iter().flat_map(|instance_move| {
+            [
+                instance_move.inventory_uuid.as_str(),
+                instance_move.target_uuid.as_str(),
+            ]
+        });
+        let carry_changes = self
+            .carry_changes
+            .iter()
+            .map(|change| change.inventory_uuid.as_str());
+        item_changes
+            .chain(instance_moves)
+            .chain(carry_changes)
+            .collect()
+    }
+
+    /// Moves everything `source` holds, and its whole purse, into the
+    /// `target_uuid` inventory. Stacks arrive loose; item instances are moved
+    /// along with them and keep their overrides.
+    pub fn transfer_all(source: &Inventory, target_uuid: &str) -> AppResult<Self> {
+        let source_uuid = source.uuid.to_string();
+        let (instances, stacks): (Vec<_>, Vec<_>) = source
+            .contents
+            .iter()
+            .partition(|content| content.instance_uuid.is_some());
+        let mut item_changes = Vec::new();
+        for content in stacks {
+            let quantity = i32::try_from(content.quantity).map_err(|_| {
+                AppError::Validation(format!(
+                    "the stack of item {} is too large to move at once",
+                    content.item_uuid
+                ))
+            })?;
+            item_changes.extend([
+                ItemQuantityChange {
+                    inventory_uuid: source_uuid.clone(),
+                    item_uuid: content.item_uuid.clone(),
+                    container_uuid: content.container_uuid.clone(),
+                    quantity_change: -quantity,
+                },
+                ItemQuantityChange {
+                    inventory_uuid: target_uuid.to_string(),
+                    item_uuid: content.item_uuid.clone(),
+                    container_uuid: None,
+                    quantity_change: quantity,
+                },
+            ]);
+        }
+        let purse = |sign: i64, inventory_uuid: &str| CurrencyChange {
+            inventory_uuid: inventory_uuid.to_string(),
+            pp: sign * source.purse.pp as i64,
+            gp: sign * source.purse.gp as i64,
+            sp: sign * source.purse.sp as i64,
+            cp: sign * source.purse.cp as i64,
+        };
+        Ok(Self {
+            item_changes,
+            instance_moves: instances
+                .into_iter()
+                .filter_map(|content| content.instance_uuid.clone())
+                .map(|instance_uuid| InstanceMove {
+                    inventory_uuid: source_uuid.clone(),
+                    instance_uuid,
+                    target_uuid: target_uuid.to_string(),
+                })
+                .collect(),
+            currency_changes: vec![purse(-1, &source_uuid), purse(1, target_uuid)],
+            ..Default::default()
+        })
+    }
+}
+
+/// Refuses a change that leaves an inventory set to `Reject` over its
+/// capacity and carrying more than it did before, so unloading an inventory
+/// that is already over capacity stays possible.
+pub fn ensure_capacity(before: &Inventory, after: &Inventory) -> AppResult<()> {
+    if after.capacity_enforcement == CapacityEnforcement::Reject
+        && after.is_over_capacity()
+        && after.bulk_carried() > before.bulk_carried()
+    {
+        return Err(AppError::CapacityExceeded {
+            inventory_id: after.uuid.to_string(),
+            capacity: after.capacity as u32,
+            bulk: after.bulk_carried(),
+        });
+    }
+    Ok(())
+}
+
+/// Refuses contents that cannot be arranged as they are stored: a stack in a
+/// container the inventory does not hold, a container split between places
+/// or kept inside itself, or a container holding more than its capacity.
+pub fn ensure_containers(inventory: &Inventory) -> AppResult<()> {
+    let tree = ContentTree::new(&inventory.contents);
+    let mut locations: HashMap<&str, Option<&str>> = HashMap::new();
+    // A stack and instances of the same container share its contents
+    let mut copies: HashMap<&str, u32> = HashMap::new();
+    for content in inventory.contents.iter().filter(|c| c.container.is_some()) {
+        let location = content.container_uuid.as_deref();
+        let count = copies.entry(&content.item_uuid).or_default();
+        *count = count.saturating_add(content.quantity);
+        if locations
+            .insert(&content.item_uuid, location)
+            .is_some_and(|previous| previous != location)
+        {
+            return Err(AppError::Validation(format!(
+                "every copy of container {} must be kept in the same place",
+                content.item_uuid
+            )));
+        }
+    }
+    for content in &inventory.contents {
+        let Some(container_uuid) = content.container_uuid.as_deref() else {
+            continue;
+        };
+        if !locations.contains_key(container_uuid) {
+            return Err(AppError::Validation(format!(
+                "item {} is stored in {}, which inventory {} does not hold as a container",
+                content.item_uuid,
+                container_uuid,
+                inventory.uuid.as_str()
+            )));
+        }
+    }
+    let mut checked = HashSet::new();
+    for content in &inventory.contents {
+        let Some(container) = content.container else {
+            continue;
+        };
+        if !checked.insert(content.item_uuid.as_str()) {
+            continue;
+        }
+        // Walk up to the inventory; more steps than containers means a cycle
+        let mut location = locations[content.item_uuid.as_str()];
+        for _ in 0..=locations.len() {
+            match location {
+                Some(parent) if parent == content.item_uuid => {
+                    return Err(AppError::Validation(format!(
+                        "container {} cannot be stored inside itself",
+                        content.item_uuid
+                    )));
+                }
+                Some(parent) => location = locations[parent],
+                None => break,
+            }
+        }
+        let capacity = container
+            .capacity
+            .saturating_mul(copies[content.item_uuid.as_str()]);
+        let bulk = tree.contents_load(&content.item_uuid).total_bulk();
+        if bulk > capacity {
+            return Err(AppError::ContainerCapacityExceeded {
+                container_id: content.item_uuid.clone(),
+                capacity,
+                bulk,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Refuses stacks that need more hands than a character has, or more
+/// invested items than the investment limit. Every item of a held stack
+/// takes its own hands.
+pub fn ensure_carry_limits(inventory: &Inventory) -> AppResult<()> {
+    let hands = inventory.contents.iter().fold(0u32, |hands, content| {
+        hands.saturating_add(content.carry.hands().saturating_mul(content.quantity))
+    });
+    if hands > MAX_HANDS {
+        return Err(AppError::HandsExceeded {
+            inventory_id: inventory.uuid.to_string(),
+            hands,
+            limit: MAX_HANDS,
+        });
+    }
+    let invested = inventory
+        .contents
+        .iter()
+        .filter(|content| content.invested)
+        .fold(0u32, |invested, content| {
+            invested.saturating_add(content.quantity)
+        });
+    if invested > MAX_INVESTED_ITEMS {
+        return Err(AppError::InvestmentLimitExceeded {
+            inventory_id: inventory.uuid.to_string(),
+            invested,
+            limit: MAX_INVESTED_ITEMS,
+        });
+    }
+    Ok(())
+}
+
+#[async_trait]
+pub trait ItemRepository: Send + Sync {
+    async fn get_items(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &ItemQueryFilter,
+    ) -> AppResult<PaginatedResponse<Item>>;
+
+    async fn get_item(&self, uuid: &str) -> AppResult<Item>;
+
+    async fn create_item(&self, properties: ItemProperties) -> AppResult<Item>;
+
+    async fn update_item(&self, uuid: &str, properties: ItemProperties) -> AppResult<Item>;
+}
+
+#[async_trait]
+pub trait TraitRepository: Send + Sync {
+    async fn get_traits(&self) -> AppResult<Vec<Trait>>;
+
+    async fn get_trait(&self, name: &str) -> AppResult<Trait>;
+
+    /// Fails with a conflict when a trait of that name already exists.
+    async fn create_trait(&self, params: &TraitInput) -> AppResult<Trait>;
+
+    /// Fails with a conflict when renaming onto another existing trait;
+    /// `merge_traits` combines them instead.
+    async fn update_trait(&self, name: &str, params: &TraitUpdate) -> AppResult<Trait>;
+
+    /// Removes the trait from every item that has it and deletes it.
+    async fn delete_trait(&self, name: &str) -> AppResult<Trait>;
+
+    /// Moves `source` onto every item that has it as `target`, then deletes
+    /// `source`, returning the merged trait.
+    async fn merge_traits(&self, source: &str, target: &str) -> AppResult<Trait>;
+}
+
+#[async_trait]
+pub trait InventoryRepository: Send + Sync {
+    async fn get_inventories(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &InventoryQueryFilter,
+    ) -> AppResult<PaginatedResponse<Inventory>>;
+
+    async fn get_inventory_by_uuid(&self, uuid: &str) -> AppResult<Inventory>;
+
+    async fn get_inventory_by_character_uuid(&self, uuid: &str) -> AppResult<Inventory>;
+
+    async fn get_inventory_by_owner_uuid(&self, uuid: &str) -> AppResult<Inventory>;
+
+    async fn get_inventory_by_owner_name(&self, name: &str) -> AppResult<Inventory>;
+
+    /// Creates an empty inventory, owned by `owner_uuid` when one is given.
+    async fn create_inventory(
+        &self,
+        name: &str,
+        capacity: u16,
+        capacity_enforcement: CapacityEnforcement,
+        owner_uuid: Option<&str>,
+    ) -> AppResult<Inventory>;
+
+    async fn update_inventory(
+        &self,
+        uuid: &str,
+        name: Option<&str>,
+        capacity: Option<u16>,
+        capacity_enforcement: Option<CapacityEnforcement>,
+    ) -> AppResult<Inventory>;
+
+    /// Makes `owner_uuid` the sole owner of the inventory, or leaves the
+    /// inventory unowned when `None`.
+    async fn set_inventory_owner(
+        &self,
+        inventory_uuid: &str,
+        owner_uuid: Option<&str>,
+    ) -> AppResult<Inventory>;
+
+    /// Deletes the inventory, first moving all of its items and coins into
+    /// `transfer_to` when given, and returns it as it was before deletion.
+    /// The move and the deletion happen in a single transaction.
+    async fn delete_inventory(&self, uuid: &str, transfer_to: Option<&str>)
+        -> AppResult<Inventory>;
+}
+
+#[async_trait]
+pub trait OwnerRepository: Send + Sync {
+    async fn get_owners(&self) -> AppResult<Vec<Owner>>;
+
+    async fn get_owner(&self, uuid: &str) -> AppResult<Owner>;
+
+    async fn create_owner(
+        &self,
+        name: &str,
+        owner_type: OwnerType,
+        sell_percent: Option<u32>,
+    ) -> AppResult<Owner>;
+
+    async fn update_owner(
+        &self,
+        uuid: &str,
+        name: Option<&str>,
+        sell_percent: Option<u32>,
+    ) -> AppResult<Owner>;
+
+    /// Deletes the owner, failing with `Conflict` while it still owns any
+    /// inventory.
+    async fn delete_owner(&self, uuid: &str) -> AppResult<Owner>;
+}
+
+#[async_trait]
+pub trait InventoryItemRepository: Send + Sync {
+    #[allow(clippy::too_many_arguments)]
+    async fn get_inventory_items(
+        &self,
+        inventory_uuid: &str,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &ItemQueryFilter,
+        location: Option<&ItemLocationFilter>,
+        equipped_only: bool,
+    ) -> AppResult<PaginatedResponse<InventoryItem>>;
+
+    /// Every stack and instance the inventory holds in `location`, or
+    /// anywhere when not given, sorted by name. Unlike
+    /// `get_inventory_items` this is not paginated; it is meant for model
+    /// code that must see a whole inventory.
+    async fn list_inventory_items(
+        &self,
+        inventory_uuid: &str,
+        location: Option<&ItemLocationFilter>,
+    ) -> AppResult<Vec<InventoryItem>>;
+
+    /// Returns the stack of an item kept loose in an inventory, or inside
+    /// `container_uuid` when given, with a quantity of zero when there is
+    /// none. Fails with `NotFound` when either the inventory or the item does
+    /// not exist.
+    async fn get_inventory_item(
+        &self,
+        inventory_uuid: &str,
+        item_uuid: &str,
+        container_uuid: Option<&str>,
+    ) -> AppResult<InventoryItem>;
+
+    /// Applies every change in the set in a single transaction. Nothing is
+    /// written when any change fails, including the checks described by
+    /// `ensure_capacity`, `ensure_containers` and `ensure_carry_limits`.
+    async fn apply_inventory_changes(&self, changes: InventoryChangeSet) -> AppResult<()>;
+}
+
+#[async_trait]
+pub trait ItemInstanceRepository: Send + Sync {
+    /// Returns the instance as a row of the inventory holding it. Fails with
+    /// `NotFound` when the inventory does not hold the instance.
+    async fn get_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<InventoryItem>;
+
+    /// Adds a new instance of the item to the inventory, loose or inside
+    /// `container_uuid`, and returns its uuid. Checked like
+    /// `apply_inventory_changes`.
+    async fn create_item_instance(
+        &self,
+        inventory_uuid: &str,
+        item_uuid: &str,
+        container_uuid: Option<&str>,
+        instance: &ItemInstanceInput,
+        override_capacity: bool,
+    ) -> AppResult<String>;
+
+    /// Replaces every override of the instance.
+    async fn update_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+        instance: &ItemInstanceInput,
+    ) -> AppResult<()>;
+
+    /// Removes the instance, refused by `ensure_containers` while it is a
+    /// container holding other items.
+    async fn remove_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<()>;
+
+    /// Takes one charge from the instance, failing with `NoUsesRemaining`
+    /// when it has none left.
+    async fn spend_instance_charge(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<()>;
+
+    /// Restores every instance of an item with daily charges to that many
+    /// charges and removes the temporary instances, in one transaction
+    /// checked by `ensure_containers`.
+    async fn prepare_instances(&self, inventory_uuid: &str) -> AppResult<()>;
+}
+
+#[async_trait]
+pub trait CurrencyRepository: Send + Sync {
+    async fn adjust_inventory_currency(&self, change: CurrencyChange) -> AppResult<Inventory>;
+}
+
+#[async_trait]
+pub trait ShopRepository: Send + Sync {
+    /// Makes the inventory a shop with these settings, or an ordinary
+    /// inventory again when `None`. Its listings are kept either way.
+    async fn set_shop_settings(
+        &self,
+        inventory_uuid: &str,
+        settings: Option<&ShopSettings>,
+    ) -> AppResult<Inventory>;
+
+    async fn get_shop_listings(&self, shop_uuid: &str) -> AppResult<Vec<ShopListing>>;
+
+    /// Adds the listing, replacing any the shop already has for the item.
+    async fn set_shop_listing(&self, shop_uuid: &str, listing: &ShopListing) -> AppResult<()>;
+
+    async fn remove_shop_listing(&self, shop_uuid: &str, item_uuid: &str) -> AppResult<()>;
+
+    /// Raises the stock of every item in the shop's restock template, up to
+    /// the shop's level cap, to the quantity the template holds. The
+    /// template's stacks of an item are totalled wherever they are kept,
+    /// loose or in any of its containers, and only the shop's loose stack is
+    /// raised. Item instances in the template are not restocked. Items the
+    /// shop holds more of, or that are not in the template, are left alone.
+    async fn restock_shop(&self, shop_uuid: &str) -> AppResult<Inventory>;
+}
+
+/// Everything the model managers need from a storage backend.
+pub trait Repository:
+    ItemRepository
+    + TraitRepository
+    + InventoryRepository
+    + OwnerRepository
+    + InventoryItemRepository
+    + ItemInstanceRepository
+    + CurrencyRepository
+    + ShopRepository
+{
+}
+
+impl<T> Repository for T where
+    T: ItemRepository
+        + TraitRepository
+        + InventoryRepository
+        + OwnerRepository
+        + InventoryItemRepository
+        + ItemInstanceRepository
+        + CurrencyRepository
+        + ShopRepository
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::ID;
+
+    use super::*;
+    use crate::graphql::schemas::{
+        bulk_schema::Bulk,
+        container_schema::{ContainerProperties, InventoryContent},
+    };
+
+    fn inventory(capacity: u16, purse: Purse, contents: Vec<InventoryContent>) -> Inventory {
+        let mut inventory = Inventory {
+            uuid: ID::from("inventory"),
+            name: "Pack".to_string(),
+            capacity,
+            purse,
+            capacity_enforcement: CapacityEnforcement::Reject,
+            shop: None,
+            load: Default::default(),
+            contents: Vec::new(),
+        };
+        inventory.set_contents(contents);
+        inventory
+    }
+
+    fn stack(item_uuid: &str, container_uuid: Option<&str>, quantity: u32) -> InventoryContent {
+        InventoryContent {
+            item_uuid: item_uuid.to_string(),
+            container_uuid: container_uuid.map(str::to_string),
+            quantity,
+            bulk: Bulk::Light,
+            container: None,
+            carry: CarryState::Stowed,
+            invested: false,
+            instance_uuid: None,
+        }
+    }
+
+    #[test]
+    fn transfer_all_moves_stacks_instances_and_coins() {
+        let mut sword = stack("longsword", None, 1);
+        sword.instance_uuid = Some("named-sword".to_string());
+        let source = inventory(
+            10,
+            Purse {
+                pp: 1,
+                gp: 2,
+                sp: 0,
+                cp: 3,
+            },
+            vec![
+                stack("rope", Some("backpack"), 2),
+                stack("backpack", None, 1),
+                sword,
+            ],
+        );
+        let changes = InventoryChangeSet::transfer_all(&source, "target").unwrap();
+
+        let moved: Vec<_> = changes
+            .item_changes
+            .iter()
+            .map(|change| {
+                (
+                    change.inventory_uuid.as_str(),
+                    change.item_uuid.as_str(),
+                    change.container_uuid.as_deref(),
+                    change.quantity_change,
+                )
+            })
+            .collect();
+        assert_eq!(
+            moved,
+            [
+                ("inventory", "rope", Some("backpack"), -2),
+                ("target", "rope", None, 2),
+                ("inventory", "backpack", None, -1),
+                ("target", "backpack", None, 1),
+            ]
+        );
+        assert_eq!(changes.instance_moves.len(), 1);
+        assert_eq!(changes.instance_moves[0].instance_uuid, "named-sword");
+        assert_eq!(changes.instance_moves[0].target_uuid, "target");
+        let coins: Vec<_> = changes
+            .currency_changes
+            .iter()
+            .map(|change| {
+                (
+                    change.inventory_uuid.as_str(),
+                    change.pp,
+                    change.gp,
+                    change.cp,
+                )
+            })
+            .collect();
+        assert_eq!(coins, [("inventory", -1, -2, -3), ("target", 1, 2, 3)]);
+    }
+
+    #[test]
+    fn transfer_all_refuses_stacks_too_large_to_move() {
+        let source = inventory(10, Purse::default(), vec![stack("arrow", None, u32::MAX)]);
+        assert!(matches!(
+            InventoryChangeSet::transfer_all(&source, "target"),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    fn container(item_uuid: &str, container_uuid: Option<&str>, capacity: u32) -> InventoryContent {
+        let mut content = stack(item_uuid, container_uuid, 1);
+        content.container = Some(ContainerProperties {
+            capacity,
+            ignored_bulk: 0,
+            ignored_only_when_worn: false,
+        });
+        content
+    }
+
+    #[test]
+    fn containers_may_hold_up_to_their_capacity() {
+        let mut bedroll = stack("bedroll", Some("backpack"), 1);
+        bedroll.bulk = Bulk::Units(1);
+        let held = inventory(
+            10,
+            Purse::default(),
+            vec![
+                container("backpack", None, 2),
+                bedroll,
+                container("pouch", Some("backpack"), 1),
+                stack("chalk", Some("pouch"), 10),
+            ],
+        );
+        assert!(ensure_containers(&held).is_ok());
+    }
+
+    #[test]
+    fn refuses_stacks_in_containers_the_inventory_does_not_hold() {
+        let held = inventory(
+            10,
+            Purse::default(),
+            vec![stack("rope", Some("backpack"), 1)],
+        );
+        assert!(matches!(
+            ensure_containers(&held),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_containers_kept_inside_themselves() {
+        let held = inventory(
+            10,
+            Purse::default(),
+            vec![
+                container("backpack", Some("sack"), 4),
+                container("sack", Some("backpack"), 4),
+            ],
+        );
+        assert!(matches!(
+            ensure_containers(&held),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_copies_of_a_container_kept_apart() {
+        let held = inventory(
+            10,
+            Purse::default(),
+            vec![
+                container("sack", None, 4),
+                container("backpack", None, 4),
+                container("sack", Some("backpack"), 4),
+            ],
+        );
+        assert!(matches!(
+            ensure_containers(&held),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_containers_holding_more_than_their_capacity() {
+        let mut copies = container("sack", None, 1);
+        copies.quantity = 2;
+        let held = inventory(
+            10,
+            Purse::default(),
+            vec![copies, stack("torch", Some("sack"), 30)],
+        );
+        match ensure_containers(&held) {
+            Err(AppError::ContainerCapacityExceeded {
+                container_id,
+                capacity,
+                bulk,
+            }) => {
+                assert_eq!(container_id, "sack");
+                assert_eq!((capacity, bulk), (2, 3));
+            }
+            other => panic!("expected the sack to be over capacity, got {:?}", other),
+        }
+    }
+
+    fn carried(carry: CarryState, invested: u32) -> Inventory {
+        let mut sword = stack("longsword", None, 1);
+        sword.carry = carry;
+        let mut rings = stack("ring", None, invested);
+        rings.carry = CarryState::Worn;
+        rings.invested = invested > 0;
+        inventory(10, Purse::default(), vec![sword, rings])
+    }
+
+    #[test]
+    fn allows_two_hands_and_ten_invested_items() {
+        assert!(ensure_carry_limits(&carried(CarryState::HeldInTwoHands, 10)).is_ok());
+    }
+
+    #[test]
+    fn refuses_more_hands_than_a_character_has() {
+        let mut held = carried(CarryState::HeldInTwoHands, 0);
+        let mut shield = stack("shield", None, 1);
+        shield.carry = CarryState::HeldInOneHand;
+        held.contents.push(shield);
+        assert!(matches!(
+            ensure_carry_limits(&held),
+            Err(AppError::HandsExceeded { hands: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn counts_the_hands_of_every_item_of_a_held_stack() {
+        let mut daggers = stack("dagger", None, 3);
+        daggers.carry = CarryState::HeldInOneHand;
+        let held = inventory(10, Purse::default(), vec![daggers]);
+        assert!(matches!(
+            ensure_carry_limits(&held),
+            Err(AppError::HandsExceeded { hands: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn refuses_investing_more_than_ten_items() {
+        assert!(matches!(
+            ensure_carry_limits(&carried(CarryState::Stowed, 11)),
+            Err(AppError::InvestmentLimitExceeded { invested: 11, .. })
+        ));
+        let mut hoard = carried(CarryState::Stowed, u32::MAX);
+        let mut amulet = stack("amulet", None, 1);
+        amulet.invested = true;
+        hoard.contents.push(amulet);
+        assert!(matches!(
+            ensure_carry_limits(&hoard),
+            Err(AppError::InvestmentLimitExceeded {
+                invested: u32::MAX,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn spending_works_out_the_denomination_changes() {
+        let held = inventory(
+            10,
+            Purse {
+                pp: 0,
+                gp: 2,
+                sp: 0,
+                cp: 0,
+            },
+            Vec::new(),
+        );
+        let spend = CurrencySpend {
+            inventory_uuid: "inventory".to_string(),
+            amount: Money::from_copper(7),
+        };
+        let change = spend.to_change(&held).unwrap();
+        assert_eq!((change.pp, change.gp, change.sp, change.cp), (0, -1, 9, 3));
+    }
+
+    #[test]
+    fn spending_more_than_the_purse_is_insufficient_funds() {
+        let held = inventory(
+            10,
+            Purse {
+                pp: 0,
+                gp: 1,
+                sp: 0,
+                cp: 0,
+            },
+            Vec::new(),
+        );
+        let spend = CurrencySpend {
+            inventory_uuid: "inventory".to_string(),
+            amount: Money::from_copper(150),
+        };
+        match spend.to_change(&held) {
+            Err(AppError::InsufficientFunds {
+                required,
+                available,
+            }) => {
+                assert_eq!(required, Money::from_copper(150));
+                assert_eq!(available, Money::from_copper(100));
+            }
+            other => panic!("expected insufficient funds, got {:?}", other),
+        }
+    }
+}
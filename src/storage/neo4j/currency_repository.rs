@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+
+use super::{adjust_inventory_currency_query, parse_currency_adjustment, Neo4jRepository};
+use crate::error::AppResult;
+use crate::graphql::schemas::inventory_schema::Inventory;
+use crate::storage::{CurrencyChange, CurrencyRepository};
+
+#[async_trait]
+impl CurrencyRepository for Neo4jRepository {
+    async fn adjust_inventory_currency(&self, change: CurrencyChange) -> AppResult<Inventory> {
+        let mut result = self
+            .graph
+            .execute(adjust_inventory_currency_query(&change))
+            .await?;
+        parse_currency_adjustment(result.next().await?, &change)
+    }
+}
@@ -0,0 +1,460 @@
+use async_graphql::ID;
+use async_trait::async_trait;
+use neo4rs::{query, Query, Row, Txn};
+
+use super::{
+    adjust_inventory_currency_query, finish_transaction,
+    inventory_repository::read_inventory,
+    item_instance_repository::{parse_instance, INSTANCE_FIELD_PATTERN},
+    item_repository::{self, page_offset, parse_item, total_pages, ITEM_FIELD_PATTERN},
+    parse_currency_adjustment, Neo4jRepository, LOCK_INVENTORY,
+};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    inventory_item_schema::{CarryState, InventoryItem, ItemLocationFilter},
+    item_schema::ItemQueryFilter,
+    paginated_response_schema::PaginatedResponse,
+};
+use crate::storage::{
+    ensure_capacity, ensure_carry_limits, ensure_containers, CarryStateChange, InstanceMove,
+    InventoryChangeSet, InventoryItemRepository, ItemQuantityChange,
+};
+
+#[async_trait]
+impl InventoryItemRepository for Neo4jRepository {
+    async fn get_inventory_items(
+        &self,
+        inventory_uuid: &str,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &ItemQueryFilter,
+        location: Option<&ItemLocationFilter>,
+        equipped_only: bool,
+    ) -> AppResult<PaginatedResponse<InventoryItem>> {
+        let skip = page_offset(page_index, page_size);
+        let (query, params) = filter.to_cypher_query(
+            &"
+                        MATCH(inv:Inventory{uuid: $uuid})
+                        Match(inv)-[c:CONTAINS]->(item:Item)
+                        WHERE <STACK>
+                        WITH c, item
+                        <FILTER>
+                        OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait)
+                        WITH item,  c, COLLECT(trait.name) as item_traits
+
+                        RETURN
+                        c.quantity as quantity,
+                        c.container as container_id,
+                        COALESCE(c.carry, 'STOWED') as carry,
+                        COALESCE(c.invested, false) as invested,
+                        <INSTANCE_FIELD_PATTERN>,
+                        <ITEM_FIELD_PATTERN>
+                        ORDER BY <ORDER_FIELD> <ORDER_DIR>, uuid DESC, instance_uuid DESC
+                        SKIP $skip LIMIT $limit"
+                .replace("<ORDER_FIELD>", map_sort_field(order_by))
+                .replace("<ITEM_FIELD_PATTERN>", ITEM_FIELD_PATTERN)
+                .replace("<INSTANCE_FIELD_PATTERN>", INSTANCE_FIELD_PATTERN)
+                .replace("<STACK>", STACK_CONDITION)
+                .replace(
+                    "<ORDER_DIR>",
+                    if order_direction == "ASC" {
+                        "ASC"
+                    } else {
+                        "DESC"
+                    },
+                ),
+        );
+        tracing::debug!("{}", query);
+
+        let (count_query, count_params) = filter.to_cypher_query(&format!("MATCH(inv:Inventory{{uuid: $uuid}}) Match(inv)-[c:CONTAINS]->(item:Item) WHERE {STACK_CONDITION} WITH item <FILTER> RETURN count(item) as total"));
+
+        let mut result = self
+            .graph
+            .execute(
+                stack_params(neo4rs::query(&query), location, equipped_only)
+                    .params(params)
+                    .param("uuid", inventory_uuid)
+                    .param("skip", skip)
+                    .param("limit", page_size),
+            )
+            .await?;
+
+        let mut count_result = self
+            .graph
+            .execute(
+                stack_params(neo4rs::query(&count_query), location, equipped_only)
+                    .params(count_params)
+                    .param("uuid", inventory_uuid),
+            )
+            .await?;
+        let mut items = Vec::new();
+        while let Some(row) = result.next().await? {
+            items.push(parse_inventory_item(&row)?);
+        }
+        let total_entities = match count_result.next().await? {
+            Some(row) => row.get("total")?,
+            None => 0,
+        };
+        Ok(PaginatedResponse {
+            entities: items,
+            page_index,
+            page_size,
+            total_entities,
+            total_pages: total_pages(total_entities, page_size),
+        })
+    }
+
+    async fn list_inventory_items(
+        &self,
+        inventory_uuid: &str,
+        location: Option<&ItemLocationFilter>,
+    ) -> AppResult<Vec<InventoryItem>> {
+        let query_string = format!(
+            "MATCH (inv:Inventory {{uuid: $uuid}})-[c:CONTAINS]->(item:Item)
+             WHERE {STACK_CONDITION}
+             OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait)
+             WITH item, c, COLLECT(trait.name) as item_traits
+             RETURN
+                c.quantity as quantity,
+                c.container as container_id,
+                COALESCE(c.carry, 'STOWED') as carry,
+                COALESCE(c.invested, false) as invested,
+                {INSTANCE_FIELD_PATTERN},
+                {ITEM_FIELD_PATTERN}
+             ORDER BY name ASC, uuid DESC, instance_uuid DESC"
+        );
+        let mut result = self
+            .graph
+            .execute(
+                stack_params(query(&query_string), location, false).param("uuid", inventory_uuid),
+            )
+            .await?;
+        let mut items = Vec::new();
+        while let Some(row) = result.next().await? {
+            items.push(parse_inventory_item(&row)?);
+        }
+        Ok(items)
+    }
+
+    async fn get_inventory_item(
+        &self,
+        inventory_uuid: &str,
+        item_uuid: &str,
+        container_uuid: Option<&str>,
+    ) -> AppResult<InventoryItem> {
+        let query_string = format!(
+            "OPTIONAL MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+             OPTIONAL MATCH (item:Item {{uuid: $item_uuid}})
+             OPTIONAL MATCH (inv)-[c:CONTAINS]->(item)
+                WHERE COALESCE(c.container, '') = COALESCE($container, '')
+                  AND c.instance IS NULL
+             OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait)
+             WITH inv, item, c, COLLECT(trait.name) as item_traits
+             RETURN inv IS NOT NULL AS inventory_exists, item IS NOT NULL AS item_exists,
+                COALESCE(c.quantity, 0) AS quantity, $container AS container_id,
+                COALESCE(c.carry, 'STOWED') AS carry, COALESCE(c.invested, false) AS invested,
+                NULL AS instance_uuid,
+                {}",
+            ITEM_FIELD_PATTERN
+        );
+        let mut result = self
+            .graph
+            .execute(
+                query(&query_string)
+                    .param("inventory_uuid", inventory_uuid)
+                    .param("item_uuid", item_uuid)
+                    .param("container", container_uuid),
+            )
+            .await?;
+        let row = result.next().await?.ok_or_else(|| {
+            AppError::Storage("inventory item lookup returned no rows".to_string())
+        })?;
+        if !row.get::<bool>("inventory_exists")? {
+            return Err(AppError::not_found("Inventory", inventory_uuid));
+        }
+        if !row.get::<bool>("item_exists")? {
+            return Err(AppError::not_found("Item", item_uuid));
+        }
+        parse_inventory_item(&row)
+    }
+
+    async fn apply_inventory_changes(&self, changes: InventoryChangeSet) -> AppResult<()> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = apply_changes(&mut txn, &changes).await;
+        finish_transaction(txn, result).await
+    }
+}
+
+pub(super) async fn apply_changes(txn: &mut Txn, changes: &InventoryChangeSet) -> AppResult<()> {
+    let mut before = Vec::new();
+    for uuid in changes.capacity_checked_inventories() {
+        before.push(read_inventory(txn, uuid).await?);
+    }
+    for change in &changes.item_changes {
+        let mut result = txn.execute(get_item_adjustment_query(change)).await?;
+        let row = result
+            .next(&mut *txn)
+            .await?
+            .ok_or_else(|| AppError::not_found("Inventory", change.inventory_uuid.clone()))?;
+        if !row.get::<bool>("item_exists")? {
+            return Err(AppError::not_found("Item", change.item_uuid.clone()));
+        }
+        if !row.get::<bool>("applied")? {
+            return Err(AppError::InsufficientQuantity {
+                item_id: change.item_uuid.clone(),
+                available: row.get("available")?,
+                requested: change.quantity_change.unsigned_abs(),
+            });
+        }
+    }
+    for instance_move in &changes.instance_moves {
+        let mut result = txn.execute(move_instance_query(instance_move)).await?;
+        if result.next(&mut *txn).await?.is_none() {
+            // Tell a missing target apart from a missing instance
+            read_inventory(txn, &instance_move.target_uuid).await?;
+            return Err(AppError::not_found(
+                "ItemInstance",
+                instance_move.instance_uuid.clone(),
+            ));
+        }
+    }
+    for change in &changes.carry_changes {
+        let mut result = txn.execute(set_carry_state_query(change)).await?;
+        let row = result
+            .next(&mut *txn)
+            .await?
+            .ok_or_else(|| AppError::not_found("Inventory", change.inventory_uuid.clone()))?;
+        if !row.get::<bool>("applied")? {
+            if let Some(instance_uuid) = &change.instance_uuid {
+                return Err(AppError::not_found("ItemInstance", instance_uuid.clone()));
+            }
+            return Err(AppError::InsufficientQuantity {
+                item_id: change.item_uuid.clone(),
+                available: 0,
+                requested: 1,
+            });
+        }
+    }
+    for change in &changes.currency_changes {
+        let mut result = txn.execute(adjust_inventory_currency_query(change)).await?;
+        parse_currency_adjustment(result.next(&mut *txn).await?, change)?;
+    }
+    for spend in &changes.spends {
+        let change = spend.to_change(&read_inventory(txn, &spend.inventory_uuid).await?)?;
+        let mut result = txn
+            .execute(adjust_inventory_currency_query(&change))
+            .await?;
+        parse_currency_adjustment(result.next(&mut *txn).await?, &change)?;
+    }
+    for uuid in changes.rearranged_inventories() {
+        let inventory = read_inventory(txn, uuid).await?;
+        ensure_containers(&inventory)?;
+        ensure_carry_limits(&inventory)?;
+    }
+    for before in &before {
+        ensure_capacity(before, &read_inventory(txn, &before.uuid).await?)?;
+    }
+    Ok(())
+}
+
+/// Applies a quantity change only when the resulting quantity stays at or
+/// above zero. The check reads the quantity after the inventory lock is held,
+/// so two racing decrements cannot both pass it. Each place an item is kept
+/// has its own `CONTAINS` relationship, told apart by its `container`
+/// property, which is absent for loose stacks. `MERGE` cannot match on a
+/// missing property, so the relationship is created explicitly.
+fn get_item_adjustment_query(change: &ItemQuantityChange) -> Query {
+    query(&format!(
+        "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+        {LOCK_INVENTORY}
+        WITH inv
+        OPTIONAL MATCH (item:Item {{uuid: $item_uuid}})
+        OPTIONAL MATCH (inv)-[existing:CONTAINS]->(item)
+          WHERE COALESCE(existing.container, '') = COALESCE($container, '')
+            AND existing.instance IS NULL
+        WITH inv, item, existing, COALESCE(existing.quantity, 0) AS available
+        WITH inv, item, existing, available,
+          item IS NOT NULL AND available + $quantity_change >= 0 AS applied
+        FOREACH (ignoreMe IN CASE WHEN applied AND existing IS NOT NULL THEN [1] ELSE [] END |
+          SET existing.quantity = existing.quantity + $quantity_change
+        )
+        FOREACH (ignoreMe IN CASE WHEN applied AND existing IS NULL AND $quantity_change > 0 THEN [1] ELSE [] END |
+          CREATE (inv)-[:CONTAINS {{quantity: $quantity_change, container: $container}}]->(item)
+        )
+        WITH item, existing, available, applied
+        // Check if the new quantity is 0
+        FOREACH (ignoreMe IN CASE WHEN existing.quantity = 0 THEN [1] ELSE [] END |
+          DELETE existing
+        )
+        RETURN item IS NOT NULL AS item_exists, available, applied"
+    ))
+    .param("inventory_uuid", change.inventory_uuid.clone())
+    .param("item_uuid", change.item_uuid.clone())
+    .param("container", change.container_uuid.clone())
+    .param("quantity_change", change.quantity_change)
+}
+
+/// Sets the state of the loose stack, or of the instance when the change
+/// names one; `applied` is false when there is none.
+fn set_carry_state_query(change: &CarryStateChange) -> Query {
+    query(&format!(
+        "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+        {LOCK_INVENTORY}
+        WITH inv
+        OPTIONAL MATCH (inv)-[c:CONTAINS]->(:Item {{uuid: $item_uuid}})
+          WHERE CASE WHEN $instance IS NULL
+            THEN c.container IS NULL AND c.instance IS NULL
+            ELSE c.instance = $instance END
+        FOREACH (ignoreMe IN CASE WHEN c IS NOT NULL THEN [1] ELSE [] END |
+          SET c.carry = $carry, c.invested = $invested
+        )
+        RETURN c IS NOT NULL AS applied"
+    ))
+    .param("inventory_uuid", change.inventory_uuid.clone())
+    .param("item_uuid", change.item_uuid.clone())
+    .param("instance", change.instance_uuid.clone())
+    .param("carry", change.carry.as_str())
+    .param("invested", change.invested)
+}
+
+/// Recreates the instance's relationship on the target inventory, since a
+/// relationship cannot change its start node. Returns no row when either
+/// inventory does not hold what it should.
+fn move_instance_query(instance_move: &InstanceMove) -> Query {
+    query(&format!(
+        "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+        {LOCK_INVENTORY}
+        WITH inv
+        MATCH (inv)-[c:CONTAINS {{instance: $instance}}]->(item:Item)
+        MATCH (target:Inventory {{uuid: $target_uuid}})
+        SET target.__lock = true REMOVE target.__lock
+        CREATE (target)-[moved:CONTAINS]->(item)
+        SET moved = properties(c)
+        REMOVE moved.container, moved.carry, moved.invested
+        DELETE c
+        RETURN moved.instance AS instance_uuid"
+    ))
+    .param("inventory_uuid", instance_move.inventory_uuid.clone())
+    .param("instance", instance_move.instance_uuid.clone())
+    .param("target_uuid", instance_move.target_uuid.clone())
+}
+
+/// Keeps the `c` stacks asked for by `stack_params`.
+const STACK_CONDITION: &str =
+    "($any_location OR COALESCE(c.container, '') = COALESCE($container, ''))
+    AND (NOT $equipped_only OR COALESCE(c.carry, 'STOWED') <> 'STOWED')";
+
+fn stack_params(query: Query, location: Option<&ItemLocationFilter>, equipped_only: bool) -> Query {
+    query
+        .param("any_location", location.is_none())
+        .param(
+            "container",
+            location.and_then(|location| location.container_id.clone()),
+        )
+        .param("equipped_only", equipped_only)
+}
+
+pub(super) fn parse_inventory_item(row: &Row) -> AppResult<InventoryItem> {
+    Ok(InventoryItem {
+        item: parse_item(row)?,
+        quantity: row.get("quantity")?,
+        container_id: row.get::<Option<String>>("container_id")?.map(ID::from),
+        carry: CarryState::parse(&row.get::<String>("carry")?),
+        invested: row.get("invested")?,
+        instance: parse_instance(row)?,
+    })
+}
+
+fn map_sort_field(field: &str) -> &str {
+    match field {
+        "quantity" => "quantity",
+        _ => item_repository::map_sort_field(field), // Default field if input does not match
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use neo4rs::Graph;
+
+    use super::*;
+    use crate::graphql::schemas::inventory_schema::CapacityEnforcement;
+    use crate::storage::InventoryRepository;
+
+    fn sale(inventory_uuid: &str, item_uuid: &str) -> InventoryChangeSet {
+        InventoryChangeSet {
+            item_changes: vec![ItemQuantityChange {
+                inventory_uuid: inventory_uuid.to_string(),
+                item_uuid: item_uuid.to_string(),
+                container_uuid: None,
+                quantity_change: -2,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Needs a migrated database at `NEO4J_URI`; run with `--ignored`.
+    #[tokio::test]
+    #[ignore]
+    async fn concurrent_changes_cannot_take_the_same_stack_twice() {
+        let env = |name: &str, default: &str| std::env::var(name).unwrap_or(default.to_string());
+        let graph = Graph::new(
+            env("NEO4J_URI", "neo4j://localhost:7687"),
+            env("NEO4J_USER", "neo4j"),
+            env("NEO4J_PASSWORD", "neo4j"),
+        )
+        .await
+        .unwrap();
+        let repository = Neo4jRepository::new(Arc::new(graph));
+        let inventory = repository
+            .create_inventory("Guard Test", 10, CapacityEnforcement::Ignore, None)
+            .await
+            .unwrap();
+        let inventory_uuid = inventory.uuid.to_string();
+        let mut result = repository
+            .graph
+            .execute(query(
+                "CREATE (item:Item {uuid: randomUUID(), name: 'Guard Test Potion', level: 0,
+                    bulk: 'L', value: 400})
+                 RETURN item.uuid AS uuid",
+            ))
+            .await
+            .unwrap();
+        let item_uuid: String = result.next().await.unwrap().unwrap().get("uuid").unwrap();
+        let mut stock = sale(&inventory_uuid, &item_uuid);
+        stock.item_changes[0].quantity_change = 2;
+        repository.apply_inventory_changes(stock).await.unwrap();
+
+        let (first, second) = tokio::join!(
+            repository.apply_inventory_changes(sale(&inventory_uuid, &item_uuid)),
+            repository.apply_inventory_changes(sale(&inventory_uuid, &item_uuid)),
+        );
+        let left = repository
+            .get_inventory_item(&inventory_uuid, &item_uuid, None)
+            .await
+            .map_or(0, |row| row.quantity);
+        repository
+            .delete_inventory(&inventory_uuid, None)
+            .await
+            .unwrap();
+        repository
+            .graph
+            .run(
+                query("MATCH (item:Item {uuid: $uuid}) DETACH DELETE item")
+                    .param("uuid", item_uuid),
+            )
+            .await
+            .unwrap();
+
+        let refused: Vec<_> = [first, second]
+            .into_iter()
+            .filter_map(Result::err)
+            .collect();
+        assert_eq!(refused.len(), 1, "exactly one change goes through");
+        assert!(matches!(refused[0], AppError::InsufficientQuantity { .. }));
+        assert_eq!(left, 0);
+    }
+}
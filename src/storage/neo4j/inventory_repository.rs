@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+
+use async_graphql::ID;
+use async_trait::async_trait;
+use neo4rs::{query, BoltMap, BoltNode, BoltType, Row, Txn};
+use serde::Deserialize;
+
+use super::{
+    finish_transaction,
+    inventory_item_repository::apply_changes,
+    item_repository::{page_offset, parse_bulk, total_pages},
+    owner_repository::owner_label_predicate,
+    Neo4jRepository, LOCK_INVENTORY,
+};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    container_schema::{ContainerProperties, InventoryContent},
+    inventory_item_schema::CarryState,
+    inventory_schema::{CapacityEnforcement, Inventory, InventoryLoad, InventoryQueryFilter},
+    money_schema::Purse,
+    paginated_response_schema::PaginatedResponse,
+    shop_schema::ShopSettings,
+};
+use crate::storage::{InventoryChangeSet, InventoryRepository};
+
+impl Neo4jRepository {
+    pub(super) async fn fetch_inventory(
+        &self,
+        query: neo4rs::Query,
+        key: &str,
+    ) -> AppResult<Inventory> {
+        let mut result = self.graph.execute(query).await?;
+        match result.next().await? {
+            Some(row) => parse_inventory(row),
+            None => Err(AppError::not_found("Inventory", key)),
+        }
+    }
+}
+
+/// Reads an inventory inside a transaction, taking its lock so the value
+/// cannot change before the transaction ends.
+pub(super) async fn read_inventory(txn: &mut Txn, uuid: &str) -> AppResult<Inventory> {
+    let mut result = txn
+        .execute(
+            query(&format!(
+                "MATCH (inv:Inventory {{uuid: $uuid}})
+                 {LOCK_INVENTORY}
+                 WITH inv
+                 {INVENTORY_CONTENTS}
+                 RETURN inv, contents"
+            ))
+            .param("uuid", uuid),
+        )
+        .await?;
+    match result.next(&mut *txn).await? {
+        Some(row) => parse_inventory(row),
+        None => Err(AppError::not_found("Inventory", uuid)),
+    }
+}
+
+/// Replaces whatever currently owns the inventory with `owner_uuid`, or just
+/// detaches the current owner when `None`.
+async fn assign_owner(
+    txn: &mut Txn,
+    inventory_uuid: &str,
+    owner_uuid: Option<&str>,
+) -> AppResult<Inventory> {
+    if let Some(owner_uuid) = owner_uuid {
+        let owner_query = format!(
+            "MATCH (owner {{uuid: $owner_uuid}}) WHERE {} RETURN owner.uuid AS uuid",
+            owner_label_predicate("owner")
+        );
+        let mut result = txn
+            .execute(query(&owner_query).param("owner_uuid", owner_uuid))
+            .await?;
+        if result.next(&mut *txn).await?.is_none() {
+            return Err(AppError::not_found("Owner", owner_uuid));
+        }
+    }
+
+    let mut result = txn
+        .execute(
+            query(&format!(
+                "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+                 OPTIONAL MATCH ()-[old:OWNS]->(inv)
+                 DELETE old
+                 WITH DISTINCT inv
+                 {INVENTORY_CONTENTS}
+                 RETURN inv, contents"
+            ))
+            .param("inventory_uuid", inventory_uuid),
+        )
+        .await?;
+    let inventory = match result.next(&mut *txn).await? {
+        Some(row) => parse_inventory(row)?,
+        None => return Err(AppError::not_found("Inventory", inventory_uuid)),
+    };
+
+    if let Some(owner_uuid) = owner_uuid {
+        let own_query = format!(
+            "MATCH (inv:Inventory {{uuid: $inventory_uuid}}), (owner {{uuid: $owner_uuid}})
+             WHERE {}
+             CREATE (owner)-[:OWNS]->(inv)",
+            owner_label_predicate("owner")
+        );
+        txn.run(
+            query(&own_query)
+                .param("inventory_uuid", inventory_uuid)
+                .param("owner_uuid", owner_uuid),
+        )
+        .await?;
+    }
+    Ok(inventory)
+}
+
+async fn delete_inventory(
+    txn: &mut Txn,
+    uuid: &str,
+    transfer_to: Option<&str>,
+) -> AppResult<Inventory> {
+    let mut result = txn
+        .execute(
+            query(&format!(
+                "MATCH (inv:Inventory {{uuid: $uuid}})
+                 {LOCK_INVENTORY}
+                 WITH inv
+                 {INVENTORY_CONTENTS}
+                 RETURN inv, contents"
+            ))
+            .param("uuid", uuid),
+        )
+        .await?;
+    let inventory = match result.next(&mut *txn).await? {
+        Some(row) => parse_inventory(row)?,
+        None => return Err(AppError::not_found("Inventory", uuid)),
+    };
+
+    if let Some(target_uuid) = transfer_to {
+        let changes = InventoryChangeSet::transfer_all(&inventory, target_uuid)?;
+        apply_changes(txn, &changes).await?;
+    }
+
+    txn.run(query("MATCH (inv:Inventory {uuid: $uuid}) DETACH DELETE inv").param("uuid", uuid))
+        .await?;
+    Ok(inventory)
+}
+
+#[async_trait]
+impl InventoryRepository for Neo4jRepository {
+    async fn get_inventories(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &InventoryQueryFilter,
+    ) -> AppResult<PaginatedResponse<Inventory>> {
+        let skip = page_offset(page_index, page_size);
+        let (query, params) = filter.to_cypher_query(
+            &"
+                        MATCH (inv:Inventory)
+                        <FILTER>
+                        WITH inv
+                        ORDER BY <ORDER_FIELD> <ORDER_DIR>, inv.uuid DESC
+                        SKIP $skip LIMIT $limit
+                        <INVENTORY_CONTENTS>
+                        RETURN inv, contents
+                        ORDER BY <ORDER_FIELD> <ORDER_DIR>, inv.uuid DESC"
+                .replace("<ORDER_FIELD>", map_sort_field(order_by))
+                .replace("<INVENTORY_CONTENTS>", INVENTORY_CONTENTS)
+                .replace(
+                    "<ORDER_DIR>",
+                    if order_direction == "ASC" {
+                        "ASC"
+                    } else {
+                        "DESC"
+                    },
+                ),
+        );
+        tracing::debug!("{}", query);
+
+        let (count_query, count_params) =
+            filter.to_cypher_query("MATCH (inv:Inventory) <FILTER> RETURN count(inv) as total");
+
+        let mut result = self
+            .graph
+            .execute(
+                neo4rs::query(&query)
+                    .params(params)
+                    .param("skip", skip)
+                    .param("limit", page_size),
+            )
+            .await?;
+
+        let mut count_result = self
+            .graph
+            .execute(neo4rs::query(&count_query).params(count_params))
+            .await?;
+        let mut inventories = Vec::new();
+        while let Some(row) = result.next().await? {
+            inventories.push(parse_inventory(row)?);
+        }
+        let total_entities = match count_result.next().await? {
+            Some(row) => row.get("total")?,
+            None => 0,
+        };
+        Ok(PaginatedResponse {
+            entities: inventories,
+            page_index,
+            page_size,
+            total_entities,
+            total_pages: total_pages(total_entities, page_size),
+        })
+    }
+
+    async fn get_inventory_by_uuid(&self, uuid: &str) -> AppResult<Inventory> {
+        let query = format!(
+            "MATCH(inv:Inventory{{uuid: $uuid}}) {INVENTORY_CONTENTS} RETURN inv, contents"
+        );
+        self.fetch_inventory(neo4rs::query(&query).param("uuid", uuid), uuid)
+            .await
+    }
+
+    async fn get_inventory_by_character_uuid(&self, uuid: &str) -> AppResult<Inventory> {
+        let query = format!(
+            "MATCH(char:Character{{uuid: $uuid}})-[:OWNS]->(inv:Inventory) {INVENTORY_CONTENTS} RETURN inv, contents"
+        );
+        self.fetch_inventory(neo4rs::query(&query).param("uuid", uuid), uuid)
+            .await
+    }
+
+    async fn get_inventory_by_owner_uuid(&self, uuid: &str) -> AppResult<Inventory> {
+        let query =
+            format!("MATCH(char{{uuid: $uuid}})-[:OWNS]->(inv:Inventory) {INVENTORY_CONTENTS} RETURN inv, contents");
+        self.fetch_inventory(neo4rs::query(&query).param("uuid", uuid), uuid)
+            .await
+    }
+
+    async fn get_inventory_by_owner_name(&self, name: &str) -> AppResult<Inventory> {
+        let query = format!("MATCH(onwer)-[:OWNS]->(inv:Inventory) WHERE toLower(onwer.name) CONTAINS toLower($name) WITH inv LIMIT 1 {INVENTORY_CONTENTS} RETURN inv, contents");
+        self.fetch_inventory(neo4rs::query(&query).param("name", name), name)
+            .await
+    }
+
+    async fn create_inventory(
+        &self,
+        name: &str,
+        capacity: u16,
+        capacity_enforcement: CapacityEnforcement,
+        owner_uuid: Option<&str>,
+    ) -> AppResult<Inventory> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = async {
+            let mut result = txn
+                .execute(
+                    query(
+                        "CREATE (inv:Inventory {
+                            uuid: apoc.create.uuid(),
+                            name: $name,
+                            capacity: $capacity,
+                            capacity_enforcement: $capacity_enforcement,
+                            cp: 0, sp: 0, gp: 0, pp: 0
+                        }) RETURN inv.uuid AS uuid",
+                    )
+                    .param("name", name)
+                    .param("capacity", capacity)
+                    .param("capacity_enforcement", capacity_enforcement.as_str()),
+                )
+                .await?;
+            let uuid: String = match result.next(&mut txn).await? {
+                Some(row) => row.get("uuid")?,
+                None => {
+                    return Err(AppError::Storage(
+                        "inventory creation returned no rows".to_string(),
+                    ))
+                }
+            };
+            assign_owner(&mut txn, &uuid, owner_uuid).await
+        }
+        .await;
+        finish_transaction(txn, result).await
+    }
+
+    async fn update_inventory(
+        &self,
+        uuid: &str,
+        name: Option<&str>,
+        capacity: Option<u16>,
+        capacity_enforcement: Option<CapacityEnforcement>,
+    ) -> AppResult<Inventory> {
+        let mut params: HashMap<&str, BoltType> = HashMap::new();
+        if let Some(name) = name {
+            params.insert("name", name.into());
+        }
+        if let Some(capacity) = capacity {
+            params.insert("capacity", capacity.into());
+        }
+        if let Some(capacity_enforcement) = capacity_enforcement {
+            params.insert("capacity_enforcement", capacity_enforcement.as_str().into());
+        }
+
+        let set_clause = if params.is_empty() {
+            String::new()
+        } else {
+            let assignments = params
+                .keys()
+                .map(|key| format!("inv.{} = ${}", key, key))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("SET {}", assignments)
+        };
+        let query_string = format!(
+            "MATCH (inv:Inventory {{uuid: $uuid}}) {} WITH inv {} RETURN inv, contents",
+            set_clause, INVENTORY_CONTENTS
+        );
+        params.insert("uuid", uuid.into());
+
+        self.fetch_inventory(query(&query_string).params(params), uuid)
+            .await
+    }
+
+    async fn set_inventory_owner(
+        &self,
+        inventory_uuid: &str,
+        owner_uuid: Option<&str>,
+    ) -> AppResult<Inventory> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = assign_owner(&mut txn, inventory_uuid, owner_uuid).await;
+        finish_transaction(txn, result).await
+    }
+
+    async fn delete_inventory(
+        &self,
+        uuid: &str,
+        transfer_to: Option<&str>,
+    ) -> AppResult<Inventory> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = delete_inventory(&mut txn, uuid, transfer_to).await;
+        finish_transaction(txn, result).await
+    }
+}
+
+fn map_sort_field(field: &str) -> &str {
+    match field {
+        "capacity" => "inv.capacity",
+        "wealth" => "inv.pp * 1000 + inv.gp * 100 + inv.sp * 10 + inv.cp",
+        _ => "inv.name", // Default field if input does not match
+    }
+}
+
+/// Collects every stack held by `inv` into `contents`, which
+/// `parse_inventory` turns into the inventory's contents and load.
+pub(super) const INVENTORY_CONTENTS: &str = "CALL {
+    WITH inv
+    MATCH (inv)-[c:CONTAINS]->(content:Item)
+    RETURN COLLECT({
+        item_uuid: content.uuid,
+        container_uuid: c.container,
+        bulk: COALESCE(content.bulk, '—'),
+        quantity: c.quantity,
+        carry: COALESCE(c.carry, 'STOWED'),
+        invested: COALESCE(c.invested, false),
+        instance_uuid: c.instance,
+        container_capacity: content.container_capacity,
+        container_ignored_bulk: COALESCE(content.container_ignored_bulk, 0),
+        container_ignored_only_when_worn: COALESCE(content.container_ignored_only_when_worn, false)
+    }) AS contents
+}";
+
+#[derive(Deserialize)]
+struct CarriedItem {
+    item_uuid: String,
+    container_uuid: Option<String>,
+    bulk: String,
+    quantity: u32,
+    carry: String,
+    invested: bool,
+    instance_uuid: Option<String>,
+    container_capacity: Option<u32>,
+    container_ignored_bulk: u32,
+    container_ignored_only_when_worn: bool,
+}
+
+pub(super) fn parse_inventory(row: Row) -> AppResult<Inventory> {
+    let contents = row
+        .get::<Vec<CarriedItem>>("contents")?
+        .into_iter()
+        .map(|carried| {
+            Ok(InventoryContent {
+                bulk: parse_bulk(&carried.bulk)?,
+                container: carried
+                    .container_capacity
+                    .map(|capacity| ContainerProperties {
+                        capacity,
+                        ignored_bulk: carried.container_ignored_bulk,
+                        ignored_only_when_worn: carried.container_ignored_only_when_worn,
+                    }),
+                item_uuid: carried.item_uuid,
+                container_uuid: carried.container_uuid,
+                quantity: carried.quantity,
+                carry: CarryState::parse(&carried.carry),
+                invested: carried.invested,
+                instance_uuid: carried.instance_uuid,
+            })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+    let node_properties = row.get::<BoltNode>("inv")?.properties;
+    let mut inventory = Inventory {
+        uuid: node_properties.get("uuid")?,
+        name: node_properties.get("name")?,
+        capacity: node_properties.get("capacity")?,
+        capacity_enforcement: CapacityEnforcement::parse(
+            &node_properties
+                .get::<Option<String>>("capacity_enforcement")?
+                .unwrap_or_default(),
+        ),
+        shop: parse_shop_settings(&node_properties)?,
+        purse: Purse {
+            pp: node_properties.get("pp")?,
+            gp: node_properties.get("gp")?,
+            sp: node_properties.get("sp")?,
+            cp: node_properties.get("cp")?,
+        },
+        load: InventoryLoad::default(),
+        contents: Vec::new(),
+    };
+    inventory.set_contents(contents);
+    Ok(inventory)
+}
+
+/// Shop settings are kept on the inventory node; `shop_markup_percent` is
+/// only set on shops.
+fn parse_shop_settings(node_properties: &BoltMap) -> AppResult<Option<ShopSettings>> {
+    let Some(markup_percent) = node_properties.get::<Option<u32>>("shop_markup_percent")? else {
+        return Ok(None);
+    };
+    Ok(Some(ShopSettings {
+        markup_percent,
+        max_item_level: node_properties.get("shop_max_item_level")?,
+        restock_template_id: node_properties
+            .get::<Option<String>>("shop_restock_template")?
+            .map(ID::from),
+    }))
+}
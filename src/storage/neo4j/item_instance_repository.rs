@@ -0,0 +1,285 @@
+use async_trait::async_trait;
+use neo4rs::{query, Query, Row, Txn};
+
+use super::{
+    finish_transaction, inventory_item_repository::parse_inventory_item,
+    inventory_repository::read_inventory, item_repository::ITEM_FIELD_PATTERN, Neo4jRepository,
+    LOCK_INVENTORY,
+};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    inventory_item_schema::InventoryItem,
+    item_instance_schema::{ItemInstance, ItemInstanceInput},
+};
+use crate::storage::{ensure_capacity, ensure_containers, ItemInstanceRepository};
+
+/// The overrides of an instance, kept on its `CONTAINS` relationship `c`.
+/// Stacks have no `instance` property.
+pub(super) const INSTANCE_FIELD_PATTERN: &str = "c.instance as instance_uuid,
+c.custom_name as custom_name,
+c.notes as notes,
+c.charges as charges,
+c.hit_points as hit_points,
+COALESCE(c.runes, []) as runes,
+COALESCE(c.temporary, false) as temporary";
+
+#[async_trait]
+impl ItemInstanceRepository for Neo4jRepository {
+    async fn get_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<InventoryItem> {
+        let query_string = format!(
+            "OPTIONAL MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+             OPTIONAL MATCH (inv)-[c:CONTAINS {{instance: $instance}}]->(item:Item)
+             OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait)
+             WITH inv, item, c, COLLECT(trait.name) as item_traits
+             RETURN inv IS NOT NULL AS inventory_exists, c IS NOT NULL AS instance_exists,
+                c.quantity AS quantity, c.container AS container_id,
+                COALESCE(c.carry, 'STOWED') AS carry, COALESCE(c.invested, false) AS invested,
+                {INSTANCE_FIELD_PATTERN},
+                {ITEM_FIELD_PATTERN}"
+        );
+        let mut result = self
+            .graph
+            .execute(
+                query(&query_string)
+                    .param("inventory_uuid", inventory_uuid)
+                    .param("instance", instance_uuid),
+            )
+            .await?;
+        let row = result.next().await?.ok_or_else(|| {
+            AppError::Storage("item instance lookup returned no rows".to_string())
+        })?;
+        if !row.get::<bool>("inventory_exists")? {
+            return Err(AppError::not_found("Inventory", inventory_uuid));
+        }
+        if !row.get::<bool>("instance_exists")? {
+            return Err(AppError::not_found("ItemInstance", instance_uuid));
+        }
+        parse_inventory_item(&row)
+    }
+
+    async fn create_item_instance(
+        &self,
+        inventory_uuid: &str,
+        item_uuid: &str,
+        container_uuid: Option<&str>,
+        instance: &ItemInstanceInput,
+        override_capacity: bool,
+    ) -> AppResult<String> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = create_instance(
+            &mut txn,
+            inventory_uuid,
+            item_uuid,
+            container_uuid,
+            instance,
+            override_capacity,
+        )
+        .await;
+        finish_transaction(txn, result).await
+    }
+
+    async fn update_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+        instance: &ItemInstanceInput,
+    ) -> AppResult<()> {
+        let mut result = self
+            .graph
+            .execute(instance_params(
+                query(
+                    "MATCH (:Inventory {uuid: $inventory_uuid})-[c:CONTAINS {instance: $instance}]->(:Item)
+                     SET c.custom_name = $custom_name, c.notes = $notes, c.charges = $charges,
+                         c.hit_points = $hit_points, c.runes = $runes, c.temporary = $temporary
+                     RETURN c.instance AS instance_uuid",
+                )
+                .param("inventory_uuid", inventory_uuid)
+                .param("instance", instance_uuid),
+                instance,
+            ))
+            .await?;
+        match result.next().await? {
+            Some(_) => Ok(()),
+            None => Err(AppError::not_found("ItemInstance", instance_uuid)),
+        }
+    }
+
+    async fn remove_item_instance(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<()> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = remove_instance(&mut txn, inventory_uuid, instance_uuid).await;
+        finish_transaction(txn, result).await
+    }
+
+    async fn spend_instance_charge(
+        &self,
+        inventory_uuid: &str,
+        instance_uuid: &str,
+    ) -> AppResult<()> {
+        let mut result = self
+            .graph
+            .execute(
+                query(&format!(
+                    "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+                     {LOCK_INVENTORY}
+                     WITH inv
+                     MATCH (inv)-[c:CONTAINS {{instance: $instance}}]->(:Item)
+                     WITH c, COALESCE(c.charges, 0) > 0 AS applied
+                     FOREACH (ignoreMe IN CASE WHEN applied THEN [1] ELSE [] END |
+                       SET c.charges = c.charges - 1
+                     )
+                     RETURN applied"
+                ))
+                .param("inventory_uuid", inventory_uuid)
+                .param("instance", instance_uuid),
+            )
+            .await?;
+        let row = result
+            .next()
+            .await?
+            .ok_or_else(|| AppError::not_found("ItemInstance", instance_uuid))?;
+        if !row.get::<bool>("applied")? {
+            return Err(AppError::NoUsesRemaining {
+                item_id: instance_uuid.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    async fn prepare_instances(&self, inventory_uuid: &str) -> AppResult<()> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = prepare_instances(&mut txn, inventory_uuid).await;
+        finish_transaction(txn, result).await
+    }
+}
+
+async fn create_instance(
+    txn: &mut Txn,
+    inventory_uuid: &str,
+    item_uuid: &str,
+    container_uuid: Option<&str>,
+    instance: &ItemInstanceInput,
+    override_capacity: bool,
+) -> AppResult<String> {
+    let before = read_inventory(txn, inventory_uuid).await?;
+    let mut result = txn
+        .execute(instance_params(
+            query(&format!(
+                "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+                 {LOCK_INVENTORY}
+                 WITH inv
+                 OPTIONAL MATCH (item:Item {{uuid: $item_uuid}})
+                 WITH inv, item, apoc.create.uuid() AS instance_uuid
+                 FOREACH (ignoreMe IN CASE WHEN item IS NOT NULL THEN [1] ELSE [] END |
+                   CREATE (inv)-[:CONTAINS {{
+                     quantity: 1, container: $container, instance: instance_uuid,
+                     custom_name: $custom_name, notes: $notes, charges: $charges,
+                     hit_points: $hit_points, runes: $runes, temporary: $temporary
+                   }}]->(item)
+                 )
+                 RETURN item IS NOT NULL AS item_exists, instance_uuid"
+            ))
+            .param("inventory_uuid", inventory_uuid)
+            .param("item_uuid", item_uuid)
+            .param("container", container_uuid),
+            instance,
+        ))
+        .await?;
+    let row = result
+        .next(&mut *txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Inventory", inventory_uuid))?;
+    if !row.get::<bool>("item_exists")? {
+        return Err(AppError::not_found("Item", item_uuid));
+    }
+    let after = read_inventory(txn, inventory_uuid).await?;
+    ensure_containers(&after)?;
+    if !override_capacity {
+        ensure_capacity(&before, &after)?;
+    }
+    Ok(row.get("instance_uuid")?)
+}
+
+async fn remove_instance(
+    txn: &mut Txn,
+    inventory_uuid: &str,
+    instance_uuid: &str,
+) -> AppResult<()> {
+    let mut result = txn
+        .execute(
+            query(&format!(
+                "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+                 {LOCK_INVENTORY}
+                 WITH inv
+                 MATCH (inv)-[c:CONTAINS {{instance: $instance}}]->(:Item)
+                 DELETE c
+                 RETURN inv.uuid AS uuid"
+            ))
+            .param("inventory_uuid", inventory_uuid)
+            .param("instance", instance_uuid),
+        )
+        .await?;
+    if result.next(&mut *txn).await?.is_none() {
+        return Err(AppError::not_found("ItemInstance", instance_uuid));
+    }
+    ensure_containers(&read_inventory(txn, inventory_uuid).await?)
+}
+
+async fn prepare_instances(txn: &mut Txn, inventory_uuid: &str) -> AppResult<()> {
+    let mut result = txn
+        .execute(
+            query(&format!(
+                "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+                 {LOCK_INVENTORY}
+                 WITH inv
+                 OPTIONAL MATCH (inv)-[c:CONTAINS]->(item:Item)
+                   WHERE c.instance IS NOT NULL AND item.charges_per_day IS NOT NULL
+                 SET c.charges = item.charges_per_day
+                 WITH DISTINCT inv
+                 OPTIONAL MATCH (inv)-[expired:CONTAINS {{temporary: true}}]->(:Item)
+                 DELETE expired
+                 WITH DISTINCT inv
+                 RETURN inv.uuid AS uuid"
+            ))
+            .param("inventory_uuid", inventory_uuid),
+        )
+        .await?;
+    if result.next(&mut *txn).await?.is_none() {
+        return Err(AppError::not_found("Inventory", inventory_uuid));
+    }
+    ensure_containers(&read_inventory(txn, inventory_uuid).await?)
+}
+
+fn instance_params(query: Query, instance: &ItemInstanceInput) -> Query {
+    query
+        .param("custom_name", instance.custom_name.clone())
+        .param("notes", instance.notes.clone())
+        .param("charges", instance.charges.map(i64::from))
+        .param("hit_points", instance.hit_points.map(i64::from))
+        .param("runes", instance.runes.clone())
+        .param("temporary", instance.temporary)
+}
+
+/// Reads the instance columns of `INSTANCE_FIELD_PATTERN`; empty for rows
+/// that are stacks.
+pub(super) fn parse_instance(row: &Row) -> AppResult<Option<ItemInstance>> {
+    let Some(uuid) = row.get::<Option<String>>("instance_uuid")? else {
+        return Ok(None);
+    };
+    Ok(Some(ItemInstance {
+        uuid: uuid.into(),
+        custom_name: row.get("custom_name")?,
+        notes: row.get("notes")?,
+        charges: row.get("charges")?,
+        hit_points: row.get("hit_points")?,
+        runes: row.get("runes")?,
+        temporary: row.get("temporary")?,
+    }))
+}
@@ -0,0 +1,584 @@
+use std::collections::HashMap;
+
+use async_graphql::ID;
+use async_trait::async_trait;
+use neo4rs::{BoltType, Row};
+
+use super::Neo4jRepository;
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    activation_schema::{ActivationCost, ActivationKind},
+    bulk_schema::Bulk,
+    container_schema::ContainerProperties,
+    item_category_schema::{ArmorStats, ItemCategory, ItemStatsInput, ShieldStats, WeaponStats},
+    item_schema::{Item, ItemProperties, ItemQueryFilter, Rarity},
+    money_schema::Money,
+    paginated_response_schema::PaginatedResponse,
+};
+use crate::storage::ItemRepository;
+
+pub(super) const ITEM_FIELD_PATTERN: &str = "item.uuid as uuid,
+COALESCE(item.effect, 'No effect') as effect,
+COALESCE(item.level, 0) as level,
+COALESCE(item.value, 0) as value,
+item_traits as traits,
+COALESCE(item.bulk, '—') as bulk,
+CASE COALESCE(item.bulk, '—') WHEN '—' THEN 0 WHEN 'L' THEN 1 ELSE 10 * toInteger(item.bulk) END AS numeric_bulk,
+item.name as name,
+COALESCE(item.description,  'No description') as description,
+item.activation_kind as activation_kind,
+item.activation_minutes as activation_minutes,
+COALESCE(item.usage_requirements, 'Not usable') as usage_requirements,
+item.container_capacity as container_capacity,
+COALESCE(item.container_ignored_bulk, 0) as container_ignored_bulk,
+COALESCE(item.container_ignored_only_when_worn, false) as container_ignored_only_when_worn,
+item.charges_per_day as charges_per_day,
+COALESCE(item.rarity, 'COMMON') as rarity,
+CASE COALESCE(item.rarity, 'COMMON') WHEN 'COMMON' THEN 0 WHEN 'UNCOMMON' THEN 1 WHEN 'RARE' THEN 2 ELSE 3 END AS numeric_rarity,
+COALESCE(item.category, 'OTHER') as category,
+item.weapon_damage as weapon_damage,
+item.weapon_damage_type as weapon_damage_type,
+item.weapon_group as weapon_group,
+item.weapon_range as weapon_range,
+item.armor_ac_bonus as armor_ac_bonus,
+item.armor_dex_cap as armor_dex_cap,
+COALESCE(item.armor_check_penalty, 0) as armor_check_penalty,
+COALESCE(item.armor_speed_penalty, 0) as armor_speed_penalty,
+item.armor_strength as armor_strength,
+item.shield_ac_bonus as shield_ac_bonus,
+item.shield_hardness as shield_hardness,
+item.shield_hit_points as shield_hit_points,
+item.shield_broken_threshold as shield_broken_threshold";
+
+#[async_trait]
+impl ItemRepository for Neo4jRepository {
+    async fn get_items(
+        &self,
+        page_index: u32,
+        page_size: u32,
+        order_by: &str,
+        order_direction: &str,
+        filter: &ItemQueryFilter,
+    ) -> AppResult<PaginatedResponse<Item>> {
+        let skip = page_offset(page_index, page_size);
+        let (query, params) = filter.to_cypher_query(
+            &"
+                        MATCH (item:Item)
+                        <FILTER>
+                        OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait)
+                        WITH item, COLLECT(trait.name) as item_traits
+                        RETURN
+                       <ITEM_FIELD_PATTERN>
+                        ORDER BY <ORDER_FIELD> <ORDER_DIR>, uuid DESC
+                        SKIP $skip LIMIT $limit"
+                .replace("<ORDER_FIELD>", map_sort_field(order_by))
+                .replace("<ITEM_FIELD_PATTERN>", ITEM_FIELD_PATTERN)
+                .replace(
+                    "<ORDER_DIR>",
+                    if order_direction == "ASC" {
+                        "ASC"
+                    } else {
+                        "DESC"
+                    },
+                ),
+        );
+        tracing::debug!("{}", query);
+
+        let (count_query, count_params) =
+            filter.to_cypher_query("MATCH (item:Item) <FILTER> RETURN count(item) as total");
+
+        let mut result = self
+            .graph
+            .execute(
+                neo4rs::query(&query)
+                    .params(params)
+                    .param("skip", skip)
+                    .param("limit", page_size),
+            )
+            .await?;
+
+        let mut count_result = self
+            .graph
+            .execute(neo4rs::query(&count_query).params(count_params))
+            .await?;
+        let mut items = Vec::new();
+        while let Some(row) = result.next().await? {
+            items.push(parse_item(&row)?);
+        }
+        let total_entities = match count_result.next().await? {
+            Some(row) => row.get("total")?,
+            None => 0,
+        };
+        Ok(PaginatedResponse {
+            entities: items,
+            page_index,
+            page_size,
+            total_entities,
+            total_pages: total_pages(total_entities, page_size),
+        })
+    }
+
+    async fn get_item(&self, uuid: &str) -> AppResult<Item> {
+        let query = format!(
+            "MATCH (item:Item {{uuid: $uuid}}) OPTIONAL MATCH (item)-[:HAS_TRAIT]->(trait:Trait) WITH item, COLLECT(trait.name) as item_traits RETURN {}",
+            ITEM_FIELD_PATTERN
+        );
+        let mut result = self
+            .graph
+            .execute(neo4rs::query(&query).param("uuid", uuid))
+            .await?;
+        match result.next().await? {
+            Some(row) => parse_item(&row),
+            None => Err(AppError::not_found("Item", uuid)),
+        }
+    }
+
+    async fn create_item(&self, properties: ItemProperties) -> AppResult<Item> {
+        let mut params: HashMap<&str, BoltType> = HashMap::new();
+        params.insert(
+            "name",
+            properties
+                .name
+                .unwrap_or("Unnamed Item".to_string())
+                .to_string()
+                .into(),
+        );
+        params.insert("level", properties.level.unwrap_or_default().into());
+        insert_activation_params(&mut params, properties.activation_cost);
+        params.insert(
+            "bulk",
+            properties.bulk.unwrap_or_default().to_string().into(),
+        );
+        params.insert(
+            "description",
+            properties.description.unwrap_or_default().into(),
+        );
+        params.insert(
+            "usage_requirements",
+            properties.usage_requirements.unwrap_or_default().into(),
+        );
+        params.insert(
+            "value",
+            stored_value(properties.value.unwrap_or_default())?.into(),
+        );
+        params.insert("effect", properties.effect.unwrap_or_default().into());
+        insert_container_params(&mut params, properties.container);
+        params.insert(
+            "charges_per_day",
+            properties.charges_per_day.map(i64::from).into(),
+        );
+        params.insert(
+            "rarity",
+            properties.rarity.unwrap_or_default().as_str().into(),
+        );
+        params.insert(
+            "category",
+            properties.category.unwrap_or_default().as_str().into(),
+        );
+        insert_stats_params(&mut params, properties.stats.as_ref());
+        params.insert("traits", properties.traits.unwrap_or_default().into());
+
+        // Build the Cypher query using parameterized placeholders
+        let query_string = "CREATE (item:Item {
+            uuid: apoc.create.uuid(),
+            name: $name,
+            level: $level,
+            activation_kind: $activation_kind,
+            activation_minutes: $activation_minutes,
+            bulk: $bulk,
+            description: $description,
+            usage_requirements: $usage_requirements,
+            value: $value,
+            effect: $effect,
+            container_capacity: $container_capacity,
+            container_ignored_bulk: $container_ignored_bulk,
+            container_ignored_only_when_worn: $container_ignored_only_when_worn,
+            charges_per_day: $charges_per_day,
+            rarity: $rarity,
+            category: $category,
+            weapon_damage: $weapon_damage,
+            weapon_damage_type: $weapon_damage_type,
+            weapon_group: $weapon_group,
+            weapon_range: $weapon_range,
+            armor_ac_bonus: $armor_ac_bonus,
+            armor_dex_cap: $armor_dex_cap,
+            armor_check_penalty: $armor_check_penalty,
+            armor_speed_penalty: $armor_speed_penalty,
+            armor_strength: $armor_strength,
+            shield_ac_bonus: $shield_ac_bonus,
+            shield_hardness: $shield_hardness,
+            shield_hit_points: $shield_hit_points,
+            shield_broken_threshold: $shield_broken_threshold
+        })
+        WITH item
+        FOREACH (trait_name IN $traits |
+          MERGE (t:Trait {name: trait_name})
+          MERGE (item)-[:HAS_TRAIT]->(t)
+        )
+        RETURN item.uuid as uuid";
+
+        // Execute the query with parameters
+        let mut result = self
+            .graph
+            .execute(neo4rs::query(query_string).params(params))
+            .await?;
+
+        match result.next().await? {
+            Some(row) => {
+                let uuid: ID = row.get("uuid")?;
+                self.get_item(&uuid).await
+            }
+            None => Err(AppError::Storage("item was not created".to_string())),
+        }
+    }
+
+    async fn update_item(&self, item_uuid: &str, properties: ItemProperties) -> AppResult<Item> {
+        let mut params: HashMap<&str, BoltType> = HashMap::new();
+
+        if let Some(name) = properties.name {
+            params.insert("name", name.into());
+        }
+        if let Some(level) = properties.level {
+            params.insert("level", level.into());
+        }
+        if properties.activation_cost.is_some() {
+            insert_activation_params(&mut params, properties.activation_cost);
+        }
+        if let Some(bulk) = properties.bulk {
+            params.insert("bulk", bulk.to_string().into());
+        }
+        if let Some(description) = properties.description {
+            params.insert("description", description.into());
+        }
+        if let Some(usage_requirements) = properties.usage_requirements {
+            params.insert("usage_requirements", usage_requirements.into());
+        }
+        if let Some(value) = properties.value {
+            params.insert("value", stored_value(value)?.into());
+        }
+        if let Some(effect) = properties.effect {
+            params.insert("effect", effect.into());
+        }
+        if properties.container.is_some() {
+            insert_container_params(&mut params, properties.container);
+        }
+        if let Some(charges_per_day) = properties.charges_per_day {
+            params.insert("charges_per_day", i64::from(charges_per_day).into());
+        }
+        if let Some(rarity) = properties.rarity {
+            params.insert("rarity", rarity.as_str().into());
+        }
+        if let Some(category) = properties.category {
+            params.insert("category", category.as_str().into());
+        }
+        if properties.stats.is_some() {
+            insert_stats_params(&mut params, properties.stats.as_ref());
+        }
+
+        // Build the SET clause for regular properties
+        let set_clause = if params.is_empty() {
+            String::new()
+        } else {
+            let assignments = params
+                .keys()
+                .map(|key| format!("item.{} = ${}", key, key))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("SET {}", assignments)
+        };
+
+        // Construct the final query string with trait handling
+        let query_string = format!(
+            "MATCH (item:Item {{uuid: $item_uuid}})
+             {}
+             WITH item
+             // Remove all existing trait relationships
+             OPTIONAL MATCH (item)-[r:HAS_TRAIT]->(:Trait)
+             DELETE r
+             WITH DISTINCT item
+             // Create new trait relationships
+             FOREACH (trait_name IN $traits |
+               MERGE (t:Trait {{name: trait_name}})
+               MERGE (item)-[:HAS_TRAIT]->(t)
+             )
+             RETURN item.uuid as uuid",
+            set_clause
+        );
+
+        // Insert item UUID and traits into params
+        params.insert("item_uuid", item_uuid.into());
+        if let Some(traits) = properties.traits {
+            params.insert("traits", traits.into());
+        } else {
+            params.insert("traits", Vec::<String>::new().into());
+        }
+
+        // Execute the query with parameters
+        let mut result = self
+            .graph
+            .execute(neo4rs::query(&query_string).params(params))
+            .await?;
+
+        match result.next().await? {
+            Some(row) => {
+                let uuid: ID = row.get("uuid")?;
+                self.get_item(&uuid).await
+            }
+            None => Err(AppError::not_found("Item", item_uuid)),
+        }
+    }
+}
+
+pub(super) fn parse_item(row: &Row) -> AppResult<Item> {
+    let category = ItemCategory::parse(&row.get::<String>("category")?);
+    Ok(Item::new(
+        row.get("uuid")?,
+        ItemProperties {
+            name: row.get("name")?,
+            value: Some(Money::from_copper(row.get::<i64>("value")?.max(0) as u64)),
+            bulk: Some(parse_bulk(&row.get::<String>("bulk")?)?),
+            description: row.get("description").unwrap_or_default(),
+            effect: row.get("effect").unwrap_or_default(),
+            level: row.get("level").unwrap_or_default(),
+            traits: row.get("traits").unwrap_or_default(),
+            activation_cost: parse_activation_cost(row)?,
+            usage_requirements: row.get("usage_requirements").unwrap_or_default(),
+            container: parse_container(row)?,
+            charges_per_day: row.get("charges_per_day")?,
+            rarity: Some(Rarity::parse(&row.get::<String>("rarity")?)),
+            category: Some(category),
+            stats: parse_stats(row, category)?,
+        },
+    ))
+}
+
+/// Container properties are kept as `container_*` properties of the item
+/// node; `container_capacity` is only set on containers.
+pub(super) fn parse_container(row: &Row) -> AppResult<Option<ContainerProperties>> {
+    let Some(capacity) = row.get::<Option<u32>>("container_capacity")? else {
+        return Ok(None);
+    };
+    Ok(Some(ContainerProperties {
+        capacity,
+        ignored_bulk: row.get("container_ignored_bulk")?,
+        ignored_only_when_worn: row.get("container_ignored_only_when_worn")?,
+    }))
+}
+
+fn insert_container_params(
+    params: &mut HashMap<&str, BoltType>,
+    container: Option<ContainerProperties>,
+) {
+    params.insert(
+        "container_capacity",
+        container
+            .map(|container| i64::from(container.capacity))
+            .into(),
+    );
+    params.insert(
+        "container_ignored_bulk",
+        container
+            .map(|container| i64::from(container.ignored_bulk))
+            .into(),
+    );
+    params.insert(
+        "container_ignored_only_when_worn",
+        container
+            .map(|container| container.ignored_only_when_worn)
+            .into(),
+    );
+}
+
+/// The activation cost is kept as the `activation_kind` and
+/// `activation_minutes` properties of the item node.
+fn parse_activation_cost(row: &Row) -> AppResult<Option<ActivationCost>> {
+    let kind = row
+        .get::<Option<String>>("activation_kind")?
+        .and_then(|kind| ActivationKind::parse(&kind));
+    Ok(match kind {
+        Some(kind) => Some(ActivationCost {
+            kind,
+            minutes: row.get("activation_minutes")?,
+        }),
+        None => None,
+    })
+}
+
+/// Values are stored as an integer of copper, which Neo4j caps at i64::MAX.
+fn stored_value(value: Money) -> AppResult<i64> {
+    i64::try_from(value.copper()).map_err(|_| {
+        AppError::Validation(format!("an item cannot be worth more than {} cp", i64::MAX))
+    })
+}
+
+fn insert_activation_params(
+    params: &mut HashMap<&str, BoltType>,
+    activation_cost: Option<ActivationCost>,
+) {
+    params.insert(
+        "activation_kind",
+        activation_cost.map(|cost| cost.kind.as_str()).into(),
+    );
+    params.insert(
+        "activation_minutes",
+        activation_cost
+            .and_then(|cost| cost.minutes)
+            .map(i64::from)
+            .into(),
+    );
+}
+
+/// Stats are kept as `weapon_*`, `armor_*` and `shield_*` properties of the
+/// item node; only those matching the item's category are read.
+fn parse_stats(row: &Row, category: ItemCategory) -> AppResult<Option<ItemStatsInput>> {
+    Ok(match category {
+        ItemCategory::Weapon => match row.get::<Option<String>>("weapon_damage")? {
+            Some(damage) => Some(ItemStatsInput::Weapon(WeaponStats {
+                damage,
+                damage_type: row
+                    .get::<Option<String>>("weapon_damage_type")?
+                    .unwrap_or_default(),
+                group: row.get("weapon_group")?,
+                range: row.get("weapon_range")?,
+            })),
+            None => None,
+        },
+        ItemCategory::Armor => match row.get::<Option<u32>>("armor_ac_bonus")? {
+            Some(ac_bonus) => Some(ItemStatsInput::Armor(ArmorStats {
+                ac_bonus,
+                dex_cap: row.get("armor_dex_cap")?,
+                check_penalty: row.get("armor_check_penalty")?,
+                speed_penalty: row.get("armor_speed_penalty")?,
+                strength: row.get("armor_strength")?,
+            })),
+            None => None,
+        },
+        ItemCategory::Shield => match row.get::<Option<u32>>("shield_ac_bonus")? {
+            Some(ac_bonus) => Some(ItemStatsInput::Shield(ShieldStats {
+                ac_bonus,
+                hardness: row
+                    .get::<Option<u32>>("shield_hardness")?
+                    .unwrap_or_default(),
+                hit_points: row
+                    .get::<Option<u32>>("shield_hit_points")?
+                    .unwrap_or_default(),
+                broken_threshold: row
+                    .get::<Option<u32>>("shield_broken_threshold")?
+                    .unwrap_or_default(),
+            })),
+            None => None,
+        },
+        _ => None,
+    })
+}
+
+/// Sets every stat property, clearing those of the other categories.
+fn insert_stats_params(params: &mut HashMap<&str, BoltType>, stats: Option<&ItemStatsInput>) {
+    let (weapon, armor, shield) = match stats {
+        Some(ItemStatsInput::Weapon(weapon)) => (Some(weapon), None, None),
+        Some(ItemStatsInput::Armor(armor)) => (None, Some(armor), None),
+        Some(ItemStatsInput::Shield(shield)) => (None, None, Some(shield)),
+        None => (None, None, None),
+    };
+    params.insert(
+        "weapon_damage",
+        weapon.map(|weapon| weapon.damage.clone()).into(),
+    );
+    params.insert(
+        "weapon_damage_type",
+        weapon.map(|weapon| weapon.damage_type.clone()).into(),
+    );
+    params.insert(
+        "weapon_group",
+        weapon.and_then(|weapon| weapon.group.clone()).into(),
+    );
+    params.insert(
+        "weapon_range",
+        weapon.and_then(|weapon| weapon.range).map(i64::from).into(),
+    );
+    params.insert(
+        "armor_ac_bonus",
+        armor.map(|armor| i64::from(armor.ac_bonus)).into(),
+    );
+    params.insert(
+        "armor_dex_cap",
+        armor.and_then(|armor| armor.dex_cap).map(i64::from).into(),
+    );
+    params.insert(
+        "armor_check_penalty",
+        armor.map(|armor| i64::from(armor.check_penalty)).into(),
+    );
+    params.insert(
+        "armor_speed_penalty",
+        armor.map(|armor| i64::from(armor.speed_penalty)).into(),
+    );
+    params.insert(
+        "armor_strength",
+        armor.and_then(|armor| armor.strength).map(i64::from).into(),
+    );
+    params.insert(
+        "shield_ac_bonus",
+        shield.map(|shield| i64::from(shield.ac_bonus)).into(),
+    );
+    params.insert(
+        "shield_hardness",
+        shield.map(|shield| i64::from(shield.hardness)).into(),
+    );
+    params.insert(
+        "shield_hit_points",
+        shield.map(|shield| i64::from(shield.hit_points)).into(),
+    );
+    params.insert(
+        "shield_broken_threshold",
+        shield
+            .map(|shield| i64::from(shield.broken_threshold))
+            .into(),
+    );
+}
+
+pub(super) fn parse_bulk(value: &str) -> AppResult<Bulk> {
+    value.parse().map_err(AppError::Storage)
+}
+
+/// How many entities come before the page; the product of two u32 can
+/// overflow a u32 but never a u64.
+pub(super) fn page_offset(page_index: u32, page_size: u32) -> i64 {
+    i64::try_from(u64::from(page_index) * u64::from(page_size)).unwrap_or(i64::MAX)
+}
+
+pub(super) fn total_pages(total_entities: u32, page_size: u32) -> u32 {
+    if page_size == 0 {
+        0
+    } else {
+        total_entities.div_ceil(page_size)
+    }
+}
+
+pub(super) fn map_sort_field(field: &str) -> &str {
+    match field {
+        "name" => "name",
+        "value" => "value",
+        "level" => "level",
+        "bulk" => "numeric_bulk",
+        "rarity" => "numeric_rarity",
+        _ => "name", // Default field if input does not match
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_values_neo4j_cannot_store() {
+        assert_eq!(stored_value(Money::from_copper(400)).unwrap(), 400);
+        assert_eq!(
+            stored_value(Money::from_copper(i64::MAX as u64)).unwrap(),
+            i64::MAX
+        );
+        assert!(matches!(
+            stored_value(Money::from_copper(i64::MAX as u64 + 1)),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
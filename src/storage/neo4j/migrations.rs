@@ -0,0 +1,165 @@
+use neo4rs::{query, Txn};
+
+use super::{finish_transaction, Neo4jRepository};
+use crate::error::AppResult;
+
+/// A data migration, applied once and then recorded as a `Migration` node so
+/// it never runs again.
+struct Migration {
+    name: &'static str,
+    statements: &'static [&'static str],
+    /// Schema changes, which Neo4j cannot run in the same transaction as
+    /// writes. They are created once the statements have committed and must
+    /// be idempotent, as they run again at every start.
+    constraints: &'static [&'static str],
+}
+
+/// Every migration in the order it must be applied. Append new ones; never
+/// edit or reorder those already released.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        name: "0001_item_bulk_notation",
+        // Bulk used to be a float where 0.1 meant light
+        statements: &["MATCH (item:Item)
+            WHERE item.bulk IS NOT NULL AND toFloat(item.bulk) = item.bulk
+            SET item.bulk = CASE
+                WHEN item.bulk <= 0 THEN '—'
+                WHEN item.bulk < 1 THEN 'L'
+                ELSE toString(toInteger(item.bulk))
+            END"],
+        constraints: &[],
+    },
+    Migration {
+        name: "0002_item_value_integer",
+        // Value used to be a string of copper; anything unparseable was read
+        // as zero, so it is stored as zero
+        statements: &["MATCH (item:Item)
+            WHERE item.value IS NOT NULL AND toString(item.value) = item.value
+            SET item.value = COALESCE(toInteger(item.value), 0)"],
+        constraints: &[],
+    },
+    Migration {
+        name: "0003_item_rarity_field",
+        // Rarity used to be recorded as a trait; an item tagged with several
+        // keeps the rarest, and the rarity traits are dropped
+        statements: &[
+            "MATCH (item:Item)-[:HAS_TRAIT]->(t:Trait)
+            WHERE toUpper(t.name) IN ['COMMON', 'UNCOMMON', 'RARE', 'UNIQUE']
+            WITH item, COLLECT(toUpper(t.name)) AS rarities
+            SET item.rarity = CASE
+                WHEN 'UNIQUE' IN rarities THEN 'UNIQUE'
+                WHEN 'RARE' IN rarities THEN 'RARE'
+                WHEN 'UNCOMMON' IN rarities THEN 'UNCOMMON'
+                ELSE 'COMMON'
+            END",
+            "MATCH (t:Trait)
+            WHERE toUpper(t.name) IN ['COMMON', 'UNCOMMON', 'RARE', 'UNIQUE']
+            DETACH DELETE t",
+            "MATCH (item:Item) WHERE item.rarity IS NULL SET item.rarity = 'COMMON'",
+        ],
+        constraints: &[],
+    },
+    Migration {
+        name: "0004_item_activation_cost",
+        // Activation cost used to be free text such as "2 actions",
+        // "[reaction]" or "10 minutes". Text that reads as none of these,
+        // including the old 'n/a' placeholder, becomes no activation
+        statements: &[
+            "MATCH (item:Item)
+            WHERE item.activation_cost IS NOT NULL
+            WITH item, toLower(trim(toString(item.activation_cost))) AS cost
+            WITH item, cost,
+                split(trim(replace(replace(replace(cost, '[', ''), ']', ''), '-', ' ')), ' ')[0] AS first
+            WITH item, cost, CASE first
+                WHEN 'a' THEN 1 WHEN 'an' THEN 1 WHEN 'one' THEN 1 WHEN 'single' THEN 1
+                WHEN 'two' THEN 2 WHEN 'three' THEN 3 WHEN 'ten' THEN 10
+                ELSE toInteger(first)
+            END AS amount
+            WITH item, amount,
+                CASE
+                    WHEN cost CONTAINS 'reaction' THEN 'REACTION'
+                    WHEN cost CONTAINS 'free' THEN 'FREE_ACTION'
+                    WHEN cost =~ '.*(minute|hour|day).*' AND amount > 0 THEN 'DURATION'
+                    WHEN cost =~ '.*(round|turn).*' THEN null
+                    WHEN amount = 1 THEN 'ONE_ACTION'
+                    WHEN amount = 2 THEN 'TWO_ACTIONS'
+                    WHEN amount = 3 THEN 'THREE_ACTIONS'
+                END AS kind,
+                CASE
+                    WHEN cost CONTAINS 'day' THEN 1440
+                    WHEN cost CONTAINS 'hour' THEN 60
+                    ELSE 1
+                END AS unit
+            SET item.activation_kind = kind,
+                item.activation_minutes = CASE WHEN kind = 'DURATION' THEN amount * unit END
+            REMOVE item.activation_cost",
+        ],
+        constraints: &[],
+    },
+    Migration {
+        name: "0005_trait_name_unique",
+        // Traits were created without a constraint, so concurrent requests
+        // may have left several with the same name; they are merged into one
+        statements: &["MATCH (t:Trait)
+            WITH t.name AS name, COLLECT(t) AS traits
+            WHERE size(traits) > 1
+            WITH traits[0] AS kept, traits[1..] AS duplicates
+            UNWIND duplicates AS duplicate
+            OPTIONAL MATCH (item:Item)-[:HAS_TRAIT]->(duplicate)
+            FOREACH (ignoreMe IN CASE WHEN item IS NULL THEN [] ELSE [1] END |
+              MERGE (item)-[:HAS_TRAIT]->(kept)
+            )
+            WITH DISTINCT kept, duplicate
+            SET kept.description = COALESCE(kept.description, duplicate.description),
+                kept.category = COALESCE(kept.category, duplicate.category)
+            DETACH DELETE duplicate"],
+        constraints: &["CREATE CONSTRAINT trait_name_unique IF NOT EXISTS
+            FOR (t:Trait) REQUIRE t.name IS UNIQUE"],
+    },
+];
+
+impl Neo4jRepository {
+    /// Brings the database up to date, running each pending migration in its
+    /// own transaction.
+    pub async fn run_migrations(&self) -> AppResult<()> {
+        for migration in MIGRATIONS {
+            let mut txn = self.graph.start_txn().await?;
+            let result = apply_migration(&mut txn, migration).await;
+            if finish_transaction(txn, result).await? {
+                tracing::info!("applied migration {}", migration.name);
+            }
+            for constraint in migration.constraints {
+                self.graph.run(query(constraint)).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs the migration unless it has already been recorded, returning whether
+/// it ran.
+async fn apply_migration(txn: &mut Txn, migration: &Migration) -> AppResult<bool> {
+    let mut result = txn
+        .execute(
+            query("OPTIONAL MATCH (m:Migration {name: $name}) RETURN m IS NOT NULL AS applied")
+                .param("name", migration.name),
+        )
+        .await?;
+    let applied = match result.next(&mut *txn).await? {
+        Some(row) => row.get::<bool>("applied")?,
+        None => false,
+    };
+    if applied {
+        return Ok(false);
+    }
+
+    for statement in migration.statements {
+        txn.run(query(statement)).await?;
+    }
+    txn.run(
+        query("CREATE (:Migration {name: $name, applied_at: datetime()})")
+            .param("name", migration.name),
+    )
+    .await?;
+    Ok(true)
+}
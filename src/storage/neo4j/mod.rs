@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use inventory_repository::{parse_inventory, INVENTORY_CONTENTS};
+use neo4rs::{query, Graph, Query, Row, Txn};
+
+use super::CurrencyChange;
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::inventory_schema::Inventory;
+
+mod currency_repository;
+mod inventory_item_repository;
+mod inventory_repository;
+mod item_instance_repository;
+mod item_repository;
+mod migrations;
+mod owner_repository;
+mod shop_repository;
+mod trait_repository;
+
+pub struct Neo4jRepository {
+    graph: Arc<Graph>,
+}
+
+impl Neo4jRepository {
+    pub fn new(graph: Arc<Graph>) -> Self {
+        Self { graph }
+    }
+}
+
+/// Commits the transaction when `result` is a success and rolls it back
+/// otherwise, so a dropped `Txn` never leaves its connection mid-transaction.
+async fn finish_transaction<T>(txn: Txn, result: AppResult<T>) -> AppResult<T> {
+    match result {
+        Ok(value) => {
+            txn.commit().await?;
+            Ok(value)
+        }
+        Err(err) => {
+            if let Err(rollback_err) = txn.rollback().await {
+                tracing::error!("failed to roll back transaction: {}", rollback_err);
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Takes the write lock on the matched `inv` node before anything is read, so
+/// concurrent transactions touching the same inventory are serialised and the
+/// guards below always see committed values.
+const LOCK_INVENTORY: &str = "SET inv.__lock = true REMOVE inv.__lock";
+
+/// Reads the result of `adjust_inventory_currency_query`, turning a refused
+/// change into the matching error.
+fn parse_currency_adjustment(row: Option<Row>, change: &CurrencyChange) -> AppResult<Inventory> {
+    let row = row.ok_or_else(|| AppError::not_found("Inventory", change.inventory_uuid.clone()))?;
+    let affordable: bool = row.get("affordable")?;
+    let mut inventory = parse_inventory(row)?;
+    if !affordable {
+        change.apply_to(&mut inventory)?;
+    }
+    Ok(inventory)
+}
+
+/// Adjusts the purse only when no denomination would drop below zero; the
+/// `affordable` column reports whether the change was applied.
+fn adjust_inventory_currency_query(change: &CurrencyChange) -> Query {
+    query(&format!(
+        "MATCH (inv:Inventory {{uuid: $inventory_uuid}})
+        {LOCK_INVENTORY}
+        WITH inv, (inv.pp + $pp >= 0 AND inv.gp + $gp >= 0 AND inv.sp + $sp >= 0 AND inv.cp + $cp >= 0) AS affordable
+        FOREACH (ignoreMe IN CASE WHEN affordable THEN [1] ELSE [] END |
+          SET inv.pp = inv.pp + $pp, inv.gp = inv.gp + $gp, inv.sp = inv.sp + $sp, inv.cp = inv.cp + $cp
+        )
+        WITH inv, affordable
+        {INVENTORY_CONTENTS}
+        RETURN inv, contents, affordable"
+    ))
+    .param("inventory_uuid", change.inventory_uuid.clone())
+    .param("pp", change.pp)
+    .param("gp", change.gp)
+    .param("sp", change.sp)
+    .param("cp", change.cp)
+}
@@ -0,0 +1,158 @@
+use async_trait::async_trait;
+use neo4rs::Row;
+
+use super::Neo4jRepository;
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::owner_schema::{Owner, OwnerType};
+use crate::storage::OwnerRepository;
+
+/// Matches any node carrying one of the owner labels, e.g.
+/// `owner:Character OR owner:Npc OR owner:Party`.
+pub(super) fn owner_label_predicate(variable: &str) -> String {
+    OwnerType::ALL
+        .iter()
+        .map(|owner_type| format!("{}:{}", variable, owner_type.label()))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+const OWNER_FIELD_PATTERN: &str = "owner.uuid AS uuid, owner.name AS name, labels(owner) AS labels,
+    owner.sell_percent AS sell_percent,
+    [inv IN inventories WHERE inv IS NOT NULL | inv.uuid] AS inventory_ids";
+
+#[async_trait]
+impl OwnerRepository for Neo4jRepository {
+    async fn get_owners(&self) -> AppResult<Vec<Owner>> {
+        let query = format!(
+            "MATCH (owner) WHERE {}
+             OPTIONAL MATCH (owner)-[:OWNS]->(inv:Inventory)
+             WITH owner, COLLECT(inv) AS inventories
+             RETURN {}
+             ORDER BY name",
+            owner_label_predicate("owner"),
+            OWNER_FIELD_PATTERN
+        );
+        let mut result = self.graph.execute(neo4rs::query(&query)).await?;
+        let mut owners = Vec::new();
+        while let Some(row) = result.next().await? {
+            owners.push(parse_owner(&row)?);
+        }
+        Ok(owners)
+    }
+
+    async fn get_owner(&self, uuid: &str) -> AppResult<Owner> {
+        let query = format!(
+            "MATCH (owner {{uuid: $uuid}}) WHERE {}
+             OPTIONAL MATCH (owner)-[:OWNS]->(inv:Inventory)
+             WITH owner, COLLECT(inv) AS inventories
+             RETURN {}",
+            owner_label_predicate("owner"),
+            OWNER_FIELD_PATTERN
+        );
+        let mut result = self
+            .graph
+            .execute(neo4rs::query(&query).param("uuid", uuid))
+            .await?;
+        match result.next().await? {
+            Some(row) => parse_owner(&row),
+            None => Err(AppError::not_found("Owner", uuid)),
+        }
+    }
+
+    async fn create_owner(
+        &self,
+        name: &str,
+        owner_type: OwnerType,
+        sell_percent: Option<u32>,
+    ) -> AppResult<Owner> {
+        let query = format!(
+            "CREATE (owner:{} {{uuid: apoc.create.uuid(), name: $name, sell_percent: $sell_percent}})
+             RETURN owner.uuid AS uuid",
+            owner_type.label()
+        );
+        let mut result = self
+            .graph
+            .execute(
+                neo4rs::query(&query)
+                    .param("name", name)
+                    .param("sell_percent", sell_percent.map(i64::from)),
+            )
+            .await?;
+        match result.next().await? {
+            Some(row) => self.get_owner(&row.get::<String>("uuid")?).await,
+            None => Err(AppError::Storage(
+                "owner creation returned no rows".to_string(),
+            )),
+        }
+    }
+
+    async fn update_owner(
+        &self,
+        uuid: &str,
+        name: Option<&str>,
+        sell_percent: Option<u32>,
+    ) -> AppResult<Owner> {
+        if name.is_some() || sell_percent.is_some() {
+            let query = format!(
+                "MATCH (owner {{uuid: $uuid}}) WHERE {}
+                 SET owner.name = COALESCE($name, owner.name),
+                     owner.sell_percent = COALESCE($sell_percent, owner.sell_percent)
+                 RETURN owner.uuid AS uuid",
+                owner_label_predicate("owner")
+            );
+            let mut result = self
+                .graph
+                .execute(
+                    neo4rs::query(&query)
+                        .param("uuid", uuid)
+                        .param("name", name)
+                        .param("sell_percent", sell_percent.map(i64::from)),
+                )
+                .await?;
+            if result.next().await?.is_none() {
+                return Err(AppError::not_found("Owner", uuid));
+            }
+        }
+        self.get_owner(uuid).await
+    }
+
+    async fn delete_owner(&self, uuid: &str) -> AppResult<Owner> {
+        let owner = self.get_owner(uuid).await?;
+        let query = format!(
+            "MATCH (owner {{uuid: $uuid}}) WHERE ({}) AND NOT (owner)-[:OWNS]->(:Inventory)
+             DETACH DELETE owner
+             RETURN count(*) AS deleted",
+            owner_label_predicate("owner")
+        );
+        let mut result = self
+            .graph
+            .execute(neo4rs::query(&query).param("uuid", uuid))
+            .await?;
+        let deleted: i64 = match result.next().await? {
+            Some(row) => row.get("deleted")?,
+            None => 0,
+        };
+        if deleted == 0 {
+            return Err(AppError::Conflict(format!(
+                "owner {} still owns inventories",
+                uuid
+            )));
+        }
+        Ok(owner)
+    }
+}
+
+fn parse_owner(row: &Row) -> AppResult<Owner> {
+    let labels: Vec<String> = row.get("labels")?;
+    let owner_type = labels
+        .iter()
+        .find_map(|label| OwnerType::from_label(label))
+        .ok_or_else(|| AppError::Storage(format!("owner has unknown labels {:?}", labels)))?;
+    Ok(Owner {
+        uuid: row.get("uuid")?,
+        name: row.get("name")?,
+        owner_type,
+        inventory_ids: row.get("inventory_ids")?,
+        sell_percent: row.get("sell_percent")?,
+    })
+}
@@ -0,0 +1,168 @@
+use async_graphql::ID;
+use async_trait::async_trait;
+use neo4rs::{query, Txn};
+
+use super::{
+    finish_transaction,
+    inventory_repository::{read_inventory, INVENTORY_CONTENTS},
+    Neo4jRepository, LOCK_INVENTORY,
+};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::{
+    inventory_schema::Inventory,
+    shop_schema::{ShopListing, ShopSettings},
+};
+use crate::storage::ShopRepository;
+
+#[async_trait]
+impl ShopRepository for Neo4jRepository {
+    async fn set_shop_settings(
+        &self,
+        inventory_uuid: &str,
+        settings: Option<&ShopSettings>,
+    ) -> AppResult<Inventory> {
+        // Setting a property to null removes it
+        let query_string = format!(
+            "MATCH (inv:Inventory {{uuid: $uuid}})
+             SET inv.shop_markup_percent = $markup_percent,
+                 inv.shop_max_item_level = $max_item_level,
+                 inv.shop_restock_template = $restock_template
+             WITH inv
+             {INVENTORY_CONTENTS}
+             RETURN inv, contents"
+        );
+        self.fetch_inventory(
+            query(&query_string)
+                .param("uuid", inventory_uuid)
+                .param(
+                    "markup_percent",
+                    settings.map(|settings| i64::from(settings.markup_percent)),
+                )
+                .param(
+                    "max_item_level",
+                    settings.and_then(|settings| settings.max_item_level.map(i64::from)),
+                )
+                .param(
+                    "restock_template",
+                    settings.and_then(|settings| {
+                        settings
+                            .restock_template_id
+                            .as_ref()
+                            .map(|uuid| uuid.to_string())
+                    }),
+                ),
+            inventory_uuid,
+        )
+        .await
+    }
+
+    async fn get_shop_listings(&self, shop_uuid: &str) -> AppResult<Vec<ShopListing>> {
+        let mut result = self
+            .graph
+            .execute(
+                query(
+                    "MATCH (:Inventory {uuid: $uuid})-[listing:LISTS]->(item:Item)
+                     RETURN item.uuid AS item_uuid, listing.price_percent AS price_percent,
+                        COALESCE(listing.unlimited, false) AS unlimited
+                     ORDER BY item_uuid",
+                )
+                .param("uuid", shop_uuid),
+            )
+            .await?;
+        let mut listings = Vec::new();
+        while let Some(row) = result.next().await? {
+            listings.push(ShopListing {
+                item_id: ID::from(row.get::<String>("item_uuid")?),
+                price_percent: row.get("price_percent")?,
+                unlimited: row.get("unlimited")?,
+            });
+        }
+        Ok(listings)
+    }
+
+    async fn set_shop_listing(&self, shop_uuid: &str, listing: &ShopListing) -> AppResult<()> {
+        let mut result = self
+            .graph
+            .execute(
+                query(
+                    "MATCH (shop:Inventory {uuid: $shop_uuid})
+                     MATCH (item:Item {uuid: $item_uuid})
+                     MERGE (shop)-[listing:LISTS]->(item)
+                     SET listing.price_percent = $price_percent, listing.unlimited = $unlimited
+                     RETURN item.uuid AS item_uuid",
+                )
+                .param("shop_uuid", shop_uuid)
+                .param("item_uuid", listing.item_id.as_str())
+                .param("price_percent", listing.price_percent.map(i64::from))
+                .param("unlimited", listing.unlimited),
+            )
+            .await?;
+        match result.next().await? {
+            Some(_) => Ok(()),
+            None => Err(AppError::Storage(
+                "shop listing update returned no rows".to_string(),
+            )),
+        }
+    }
+
+    async fn remove_shop_listing(&self, shop_uuid: &str, item_uuid: &str) -> AppResult<()> {
+        self.graph
+            .run(
+                query(
+                    "MATCH (:Inventory {uuid: $shop_uuid})-[listing:LISTS]->(:Item {uuid: $item_uuid})
+                     DELETE listing",
+                )
+                .param("shop_uuid", shop_uuid)
+                .param("item_uuid", item_uuid),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn restock_shop(&self, shop_uuid: &str) -> AppResult<Inventory> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = restock(&mut txn, shop_uuid).await;
+        finish_transaction(txn, result).await
+    }
+}
+
+/// Locks the shop before reading the template, so a restock cannot
+/// interleave with a purchase from the same shop. The template's stacks of
+/// an item are totalled wherever they are kept, loose or in any of its
+/// containers, and only the shop's loose stack is raised.
+async fn restock(txn: &mut Txn, shop_uuid: &str) -> AppResult<Inventory> {
+    let mut result = txn
+        .execute(
+            query(&format!(
+                "MATCH (inv:Inventory {{uuid: $uuid}})
+                 {LOCK_INVENTORY}
+                 RETURN inv.uuid AS uuid"
+            ))
+            .param("uuid", shop_uuid),
+        )
+        .await?;
+    if result.next(&mut *txn).await?.is_none() {
+        return Err(AppError::not_found("Inventory", shop_uuid));
+    }
+    txn.run(
+        query(
+            "MATCH (inv:Inventory {uuid: $uuid})
+             MATCH (:Inventory {uuid: inv.shop_restock_template})-[stock:CONTAINS]->(item:Item)
+             WHERE stock.instance IS NULL
+               AND (inv.shop_max_item_level IS NULL
+                 OR COALESCE(item.level, 0) <= inv.shop_max_item_level)
+             WITH inv, item, SUM(stock.quantity) AS quantity
+             OPTIONAL MATCH (inv)-[c:CONTAINS]->(item)
+                WHERE c.container IS NULL AND c.instance IS NULL
+             FOREACH (ignoreMe IN CASE WHEN c IS NULL THEN [1] ELSE [] END |
+                CREATE (inv)-[:CONTAINS {quantity: quantity}]->(item)
+             )
+             FOREACH (ignoreMe IN CASE WHEN c.quantity < quantity THEN [1] ELSE [] END |
+                SET c.quantity = quantity
+             )",
+        )
+        .param("uuid", shop_uuid),
+    )
+    .await?;
+    read_inventory(txn, shop_uuid).await
+}
@@ -0,0 +1,212 @@
+use async_trait::async_trait;
+use neo4rs::{Row, Txn};
+
+use super::{finish_transaction, Neo4jRepository};
+use crate::error::{AppError, AppResult};
+use crate::graphql::schemas::trait_schema::{Trait, TraitCategory, TraitInput, TraitUpdate};
+use crate::storage::TraitRepository;
+
+/// Reads `trait` along with the number of items that have it.
+const TRAIT_RETURN_PATTERN: &str = "OPTIONAL MATCH (item:Item)-[:HAS_TRAIT]->(trait)
+WITH trait, count(item) AS usage_count
+RETURN trait.name as name, trait.description as description,
+    trait.category as category, usage_count";
+
+#[async_trait]
+impl TraitRepository for Neo4jRepository {
+    async fn get_traits(&self) -> AppResult<Vec<Trait>> {
+        let query = format!("MATCH (trait:Trait) {TRAIT_RETURN_PATTERN} ORDER BY name");
+        let mut result = self.graph.execute(neo4rs::query(&query)).await?;
+        let mut traits = Vec::new();
+        while let Some(row) = result.next().await? {
+            traits.push(parse_trait(&row)?);
+        }
+        Ok(traits)
+    }
+
+    async fn get_trait(&self, name: &str) -> AppResult<Trait> {
+        let query = format!("MATCH (trait:Trait {{name: $name}}) {TRAIT_RETURN_PATTERN}");
+        let mut result = self
+            .graph
+            .execute(neo4rs::query(&query).param("name", name))
+            .await?;
+        match result.next().await? {
+            Some(row) => parse_trait(&row),
+            None => Err(AppError::not_found("Trait", name)),
+        }
+    }
+
+    async fn create_trait(&self, params: &TraitInput) -> AppResult<Trait> {
+        let mut result = self
+            .graph
+            .execute(
+                neo4rs::query(
+                    "OPTIONAL MATCH (existing:Trait {name: $name})
+                     WITH existing WHERE existing IS NULL
+                     CREATE (trait:Trait {name: $name, description: $description, category: $category})
+                     RETURN trait.name AS name",
+                )
+                .param("name", params.name.as_str())
+                .param("description", params.description.clone())
+                .param("category", params.category.map(|category| category.as_str())),
+            )
+            .await
+            .map_err(|err| name_taken(&params.name, err))?;
+        let created = result
+            .next()
+            .await
+            .map_err(|err| name_taken(&params.name, err))?;
+        if created.is_none() {
+            return Err(AppError::Conflict(format!(
+                "trait {} already exists",
+                params.name
+            )));
+        }
+        self.get_trait(&params.name).await
+    }
+
+    async fn update_trait(&self, name: &str, params: &TraitUpdate) -> AppResult<Trait> {
+        let new_name = params.name.as_deref().unwrap_or(name);
+        let mut result = self
+            .graph
+            .execute(
+                neo4rs::query(
+                    "MATCH (trait:Trait {name: $name})
+                     OPTIONAL MATCH (other:Trait {name: $new_name}) WHERE other <> trait
+                     WITH trait, other IS NOT NULL AS taken
+                     FOREACH (ignoreMe IN CASE WHEN taken THEN [] ELSE [1] END |
+                       SET trait.name = $new_name,
+                           trait.description = COALESCE($description, trait.description),
+                           trait.category = COALESCE($category, trait.category)
+                     )
+                     RETURN taken",
+                )
+                .param("name", name)
+                .param("new_name", new_name)
+                .param("description", params.description.clone())
+                .param(
+                    "category",
+                    params.category.map(|category| category.as_str()),
+                ),
+            )
+            .await
+            .map_err(|err| name_taken(new_name, err))?;
+        let row = result
+            .next()
+            .await
+            .map_err(|err| name_taken(new_name, err))?
+            .ok_or_else(|| AppError::not_found("Trait", name))?;
+        if row.get::<bool>("taken")? {
+            return Err(AppError::Conflict(format!(
+                "trait {} already exists",
+                new_name
+            )));
+        }
+        self.get_trait(new_name).await
+    }
+
+    async fn delete_trait(&self, name: &str) -> AppResult<Trait> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = delete_trait(&mut txn, name).await;
+        finish_transaction(txn, result).await
+    }
+
+    async fn merge_traits(&self, source: &str, target: &str) -> AppResult<Trait> {
+        let mut txn = self.graph.start_txn().await?;
+        let result = merge_traits(&mut txn, source, target).await;
+        finish_transaction(txn, result).await
+    }
+}
+
+/// Reads a trait inside `txn`, taking its write lock first so it cannot
+/// change or disappear before the transaction ends.
+async fn read_trait(txn: &mut Txn, name: &str) -> AppResult<Trait> {
+    let query = format!(
+        "MATCH (trait:Trait {{name: $name}})
+         SET trait.__lock = true REMOVE trait.__lock
+         WITH trait
+         {TRAIT_RETURN_PATTERN}"
+    );
+    let mut result = txn
+        .execute(neo4rs::query(&query).param("name", name))
+        .await?;
+    match result.next(&mut *txn).await? {
+        Some(row) => parse_trait(&row),
+        None => Err(AppError::not_found("Trait", name)),
+    }
+}
+
+async fn delete_trait(txn: &mut Txn, name: &str) -> AppResult<Trait> {
+    let deleted = read_trait(txn, name).await?;
+    txn.run(
+        neo4rs::query("MATCH (trait:Trait {name: $name}) DETACH DELETE trait").param("name", name),
+    )
+    .await?;
+    Ok(deleted)
+}
+
+async fn merge_traits(txn: &mut Txn, source: &str, target: &str) -> AppResult<Trait> {
+    read_trait(txn, source).await?;
+    read_trait(txn, target).await?;
+    txn.run(
+        neo4rs::query(
+            "MATCH (source:Trait {name: $source}), (target:Trait {name: $target})
+             OPTIONAL MATCH (item:Item)-[r:HAS_TRAIT]->(source)
+             FOREACH (ignoreMe IN CASE WHEN item IS NULL THEN [] ELSE [1] END |
+               MERGE (item)-[:HAS_TRAIT]->(target)
+             )
+             DELETE r
+             WITH DISTINCT source, target
+             SET target.description = COALESCE(target.description, source.description),
+                 target.category = COALESCE(target.category, source.category)
+             DETACH DELETE source",
+        )
+        .param("source", source)
+        .param("target", target),
+    )
+    .await?;
+    read_trait(txn, target).await
+}
+
+/// Reports a write refused by the unique trait name constraint, which a
+/// concurrent create or rename can hit, as the usual conflict.
+fn name_taken(name: &str, err: neo4rs::Error) -> AppError {
+    if err.to_string().contains("ConstraintValidationFailed") {
+        AppError::Conflict(format!("trait {} already exists", name))
+    } else {
+        err.into()
+    }
+}
+
+fn parse_trait(row: &Row) -> AppResult<Trait> {
+    Ok(Trait {
+        name: row.get("name")?,
+        description: row.get("description").unwrap_or_default(),
+        category: row
+            .get::<Option<String>>("category")?
+            .and_then(|category| TraitCategory::parse(&category)),
+        usage_count: row.get::<i64>("usage_count")?.max(0) as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_a_taken_name_as_a_conflict() {
+        let refused = neo4rs::Error::UnexpectedMessage(
+            "unexpected response for PULL: Failure { code: \
+             \"Neo.ClientError.Schema.ConstraintValidationFailed\" }"
+                .to_string(),
+        );
+        assert!(matches!(
+            name_taken("Magical", refused),
+            AppError::Conflict(message) if message == "trait Magical already exists"
+        ));
+        assert!(matches!(
+            name_taken("Magical", neo4rs::Error::ConnectionError),
+            AppError::Storage(_)
+        ));
+    }
+}